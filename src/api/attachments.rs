@@ -0,0 +1,336 @@
+//! Message attachments: shared files and inline images
+//!
+//! Files shared in chats live in SharePoint/OneDrive and are listed in the
+//! message's `properties.files`; they are downloaded through the Graph
+//! `/shares` API. Pasted images are AMS (Azure Media Services) objects
+//! referenced by `<img>` tags in the message HTML and need the Skype token.
+
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::chat::{self, NativeMessage};
+use super::client::{self, TeamsClient};
+
+/// Path segment that precedes the object ID in AMS URLs.
+const AMS_OBJECTS: &str = "/v1/objects/";
+
+/// AMS views for the full-size image and its thumbnail.
+const AMS_ORIGINAL_VIEW: &str = "/views/imgo";
+const AMS_THUMBNAIL_VIEW: &str = "/views/imgt1";
+
+/// Where an attachment is stored, which decides how it is downloaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentKind {
+    /// SharePoint/OneDrive file (Graph token).
+    File,
+    /// AMS image object (Skype token).
+    Image,
+}
+
+/// A file or image attached to a message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AttachmentInfo {
+    pub name: String,
+    pub kind: AttachmentKind,
+    /// Sharing URL (files) or AMS object URL (images).
+    pub url: String,
+}
+
+/// One entry of `properties.files`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SharedFile {
+    file_name: Option<String>,
+    title: Option<String>,
+    object_url: Option<String>,
+    file_info: Option<SharedFileInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SharedFileInfo {
+    share_url: Option<String>,
+}
+
+/// Parse `properties.files` (a JSON array, usually stringified).
+pub(super) fn parse_files(value: &serde_json::Value) -> Vec<AttachmentInfo> {
+    let files: Vec<SharedFile> = match value {
+        serde_json::Value::String(s) => serde_json::from_str(s).unwrap_or_default(),
+        other => serde_json::from_value(other.clone()).unwrap_or_default(),
+    };
+    files
+        .into_iter()
+        .filter_map(|f| {
+            let url = f
+                .file_info
+                .and_then(|i| i.share_url)
+                .filter(|u| !u.is_empty())
+                .or(f.object_url)?;
+            let name = f
+                .file_name
+                .or(f.title)
+                .unwrap_or_else(|| url.rsplit('/').next().unwrap_or("file").to_string());
+            Some(AttachmentInfo {
+                name,
+                kind: AttachmentKind::File,
+                url,
+            })
+        })
+        .collect()
+}
+
+/// Find AMS images (`<img src=".../v1/objects/{id}/views/...">`) in
+/// message HTML. The URL is normalised to the full-size `imgo` view.
+///
+/// Only https images on AMS hosts count: fetching them sends our Skype
+/// token, and the HTML is written by whoever sent the message.
+pub(super) fn ams_images(html: &str) -> Vec<AttachmentInfo> {
+    let mut images = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find("<img") {
+        let tag_end = rest[start..].find('>').map_or(rest.len(), |e| start + e);
+        let tag = &rest[start..tag_end];
+        rest = &rest[tag_end..];
+
+        let Some(src) = tag_attr(tag, "src").filter(|src| client::is_ams_domain_url(src)) else {
+            continue;
+        };
+        let Some(object_start) = src.find(AMS_OBJECTS) else {
+            continue;
+        };
+        let prefix = &src[..object_start + AMS_OBJECTS.len()];
+        let after = &src[prefix.len()..];
+        let object_id = after.split('/').next().unwrap_or(after);
+        if object_id.is_empty() {
+            continue;
+        }
+        images.push(AttachmentInfo {
+            name: format!("image-{}", object_id),
+            kind: AttachmentKind::Image,
+            url: format!("{}{}{}", prefix, object_id, AMS_ORIGINAL_VIEW),
+        });
+    }
+    images
+}
+
+/// Value of a double-quoted attribute in an HTML tag.
+fn tag_attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!(" {}=\"", name);
+    let start = tag.find(&needle)? + needle.len();
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+/// Download every attachment of a message into `out` (default: the
+/// current directory) and print the saved paths.
+pub async fn download(chat_id: &str, message_id: &str, out: Option<&Path>) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    let msg = chat::find_message(&client, chat_id, message_id).await?;
+    let attachments = chat::message_info(&msg)
+        .map(|m| m.attachments)
+        .unwrap_or_default();
+    if attachments.is_empty() {
+        bail!("Message {} has no attachments", message_id);
+    }
+
+    let dir = out.unwrap_or_else(|| Path::new("."));
+    for att in &attachments {
+        let path = download_attachment_with_client(&client, att, dir).await?;
+        println!("Saved {}", path.display());
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Data-returning API functions for TUI integration
+// ---------------------------------------------------------------------------
+
+/// The user's download directory, falling back to the current directory.
+pub fn default_download_dir() -> PathBuf {
+    directories::UserDirs::new()
+        .and_then(|d| d.download_dir().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Download one attachment into `dir`, never overwriting an existing file.
+/// Returns the path written.
+pub async fn download_attachment_with_client(
+    client: &TeamsClient,
+    att: &AttachmentInfo,
+    dir: &Path,
+) -> Result<PathBuf> {
+    let resp = match att.kind {
+        AttachmentKind::File => {
+            let path = format!("/shares/{}/driveItem/content", encode_sharing_url(&att.url));
+            client.graph_get(&path).await?
+        }
+        AttachmentKind::Image => client.ams_get(&att.url).await?,
+    };
+
+    let mut name = sanitize_file_name(&att.name);
+    if att.kind == AttachmentKind::Image && Path::new(&name).extension().is_none() {
+        let ext = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(image_extension)
+            .unwrap_or("img");
+        name = format!("{}.{}", name, ext);
+    }
+
+    let bytes = resp
+        .bytes()
+        .await
+        .with_context(|| format!("Failed to download {}", att.name))?;
+
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = unique_path(dir, &name);
+    std::fs::write(&path, &bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Fetch the bytes of an image attachment for an inline preview, preferring
+/// the smaller AMS thumbnail view when there is one.
+///
+/// Previews load without the user asking, so anything but an AMS URL is
+/// refused up front.
+pub async fn image_preview_with_client(client: &TeamsClient, url: &str) -> Result<Vec<u8>> {
+    if !client.is_ams_url(url) {
+        bail!("Not previewing {}: not an AMS image", url);
+    }
+    if let Some(thumbnail) = thumbnail_url(url) {
+        match client.ams_get(&thumbnail).await {
+            Ok(resp) => {
+                let bytes = resp.bytes().await.context("Failed to read thumbnail")?;
+                return Ok(bytes.to_vec());
+            }
+            Err(e) => tracing::debug!("No thumbnail for {}: {:#}", url, e),
+        }
+    }
+    let resp = client.ams_get(url).await?;
+    let bytes = resp.bytes().await.context("Failed to read image")?;
+    Ok(bytes.to_vec())
+}
+
+/// Thumbnail view URL for a full-size AMS image URL.
+fn thumbnail_url(url: &str) -> Option<String> {
+    url.strip_suffix(AMS_ORIGINAL_VIEW)
+        .map(|object| format!("{}{}", object, AMS_THUMBNAIL_VIEW))
+}
+
+/// Encode a sharing URL for the Graph `/shares/{id}` endpoint.
+fn encode_sharing_url(url: &str) -> String {
+    format!(
+        "u!{}",
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(url)
+    )
+}
+
+/// File extension for an image MIME type.
+fn image_extension(content_type: &str) -> Option<&'static str> {
+    match content_type.split(';').next()?.trim() {
+        "image/png" => Some("png"),
+        "image/jpeg" => Some("jpg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/bmp" => Some("bmp"),
+        _ => None,
+    }
+}
+
+/// Strip path separators and other characters that are unsafe in file names.
+fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '\0' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.').to_string();
+    if cleaned.is_empty() {
+        "attachment".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// `dir/name`, or `dir/stem (n).ext` if that already exists.
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+        .find(|p| !p.exists())
+        .expect("unbounded range always yields a free name")
+}
+
+/// Attachments of a native message: shared files, then inline images.
+pub(super) fn message_attachments(msg: &NativeMessage, html: &str) -> Vec<AttachmentInfo> {
+    let mut attachments = msg.files().map(parse_files).unwrap_or_default();
+    attachments.extend(ams_images(html));
+    attachments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_shared_files() {
+        let files = serde_json::Value::String(
+            serde_json::json!([{
+                "fileName": "build.log",
+                "objectUrl": "https://contoso.sharepoint.com/personal/a/Documents/Microsoft Teams Chat Files/build.log",
+                "fileInfo": { "shareUrl": "https://contoso.sharepoint.com/:t:/g/personal/a/abc" },
+            }])
+            .to_string(),
+        );
+        assert_eq!(
+            parse_files(&files),
+            [AttachmentInfo {
+                name: "build.log".to_string(),
+                kind: AttachmentKind::File,
+                url: "https://contoso.sharepoint.com/:t:/g/personal/a/abc".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_ams_images_in_html() {
+        let html = r#"<p>look</p><p><img alt="image" itemtype="http://schema.skype.com/AMSImage" src="https://us-api.asm.skype.com/v1/objects/0-wus-d1-abc/views/imgpsh" width="250"></p><img src="https://example.com/x.png"><img src="https://attacker.example/v1/objects/0-evil/views/imgo"><img src="http://us-api.asm.skype.com/v1/objects/0-plain/views/imgo">"#;
+        let images = ams_images(html);
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].name, "image-0-wus-d1-abc");
+        assert_eq!(
+            images[0].url,
+            "https://us-api.asm.skype.com/v1/objects/0-wus-d1-abc/views/imgo"
+        );
+    }
+
+    #[test]
+    fn thumbnail_url_swaps_view() {
+        assert_eq!(
+            thumbnail_url("https://us-api.asm.skype.com/v1/objects/0-abc/views/imgo").as_deref(),
+            Some("https://us-api.asm.skype.com/v1/objects/0-abc/views/imgt1")
+        );
+        assert_eq!(thumbnail_url("https://example.com/x.png"), None);
+    }
+
+    #[test]
+    fn unsafe_names_are_sanitized() {
+        assert_eq!(sanitize_file_name("../../etc/passwd"), "_.._etc_passwd");
+        assert_eq!(sanitize_file_name("  "), "attachment");
+        assert_eq!(sanitize_file_name("patch v2.diff"), "patch v2.diff");
+    }
+}
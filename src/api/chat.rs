@@ -4,9 +4,18 @@
 //! bypassing Graph API which requires tenant admin consent for Chat.Read.
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
+use super::attachments::{self, AttachmentInfo};
 use super::client::TeamsClient;
+use super::markdown::markdown_to_html;
+use super::mentions::{self, Mention};
+use super::names::ChatNames;
+use super::reactions::{self, ReactionInfo};
+use super::richtext::{ReplyQuote, RichText, TextFlavor};
+use crate::cache;
+use crate::output::{self, OutputFormat};
 
 // -- Response types for the native chat API --
 
@@ -16,144 +25,397 @@ struct ConversationsResponse {
 }
 
 #[derive(Debug, Deserialize)]
-struct Conversation {
-    id: Option<String>,
+pub(super) struct Conversation {
+    pub(super) id: Option<String>,
     #[serde(rename = "threadProperties")]
-    thread_properties: Option<ThreadProperties>,
+    pub(super) thread_properties: Option<ThreadProperties>,
     #[serde(rename = "lastMessage")]
-    last_message: Option<NativeMessage>,
+    pub(super) last_message: Option<NativeMessage>,
+    pub(super) properties: Option<ConversationProperties>,
+}
+
+/// Our per-conversation settings.
+#[derive(Debug, Deserialize)]
+pub(super) struct ConversationProperties {
+    /// How far we have read: `<message id>;<time set>;<client message id>`.
+    #[serde(rename = "consumptionhorizon")]
+    pub(super) consumption_horizon: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
-struct ThreadProperties {
-    topic: Option<String>,
+pub(super) struct ThreadProperties {
+    pub(super) topic: Option<String>,
     #[serde(rename = "lastjoinat")]
     last_join_at: Option<String>,
-    /// For 1:1 chats, contains member MRIs
-    members: Option<String>,
+    /// Member MRIs (see [`super::names`]).
+    pub(super) members: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct NativeMessage {
+/// A message as returned by the chat service (also the `resource` of
+/// Trouter `NewMessage` push events, and the record kept by the local cache).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NativeMessage {
     id: Option<String>,
+    /// ID given by the sending client (used in consumption horizons).
+    #[serde(rename = "clientmessageid")]
+    client_message_id: Option<String>,
     #[serde(rename = "composetime")]
     compose_time: Option<String>,
     #[serde(rename = "originalarrivaltime")]
     original_arrival_time: Option<String>,
     #[serde(rename = "imdisplayname")]
-    im_display_name: Option<String>,
+    pub(super) im_display_name: Option<String>,
     content: Option<String>,
     messagetype: Option<String>,
     from: Option<String>,
+    #[serde(rename = "conversationLink")]
+    conversation_link: Option<String>,
+    #[serde(rename = "skypeeditedid")]
+    skype_edited_id: Option<String>,
+    /// Root post of the channel thread this message replies to.
+    #[serde(rename = "parentMessageId")]
+    parent_message_id: Option<String>,
+    properties: Option<MessageProperties>,
 }
 
-#[derive(Debug, Deserialize)]
-struct MessagesResponse {
-    messages: Option<Vec<NativeMessage>>,
+/// Subset of the `properties` bag attached to native messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MessageProperties {
+    /// Set (epoch millis, sometimes stringified) when the message was edited.
+    edittime: Option<serde_json::Value>,
+    /// Set (epoch millis, sometimes stringified) when the message was deleted.
+    deletetime: Option<serde_json::Value>,
+    /// Reactions with their users (an array, sometimes stringified).
+    emotions: Option<serde_json::Value>,
+    /// Shared SharePoint/OneDrive files (a stringified array).
+    files: Option<serde_json::Value>,
+    /// People mentioned in the message (a stringified array).
+    mentions: Option<serde_json::Value>,
 }
 
-/// Strip HTML tags from content for CLI display.
-fn strip_html(html: &str) -> String {
-    let mut result = String::with_capacity(html.len());
-    let mut in_tag = false;
-    for ch in html.chars() {
-        match ch {
-            '<' => in_tag = true,
-            '>' => in_tag = false,
-            _ if !in_tag => result.push(ch),
-            _ => {}
-        }
+impl NativeMessage {
+    /// Conversation ID, taken from the trailing segment of `conversationLink`
+    /// (without any `;messageid=` channel thread suffix).
+    pub fn conversation_id(&self) -> Option<&str> {
+        self.conversation_link
+            .as_deref()
+            .and_then(|link| link.rsplit('/').next())
+            .map(|id| split_thread_id(id).0)
+            .filter(|id| !id.is_empty())
     }
-    // Decode common HTML entities
-    result
-        .replace("&amp;", "&")
-        .replace("&lt;", "<")
-        .replace("&gt;", ">")
-        .replace("&quot;", "\"")
-        .replace("&#39;", "'")
-        .replace("&nbsp;", " ")
-}
-
-/// Display name for a conversation.
-fn conversation_name(conv: &Conversation) -> String {
-    if let Some(ref props) = conv.thread_properties {
-        if let Some(ref topic) = props.topic {
-            if !topic.is_empty() {
-                return topic.clone();
-            }
-        }
+
+    /// ID of the channel thread root this message replies to, or `None` for
+    /// chat messages and root posts.
+    pub fn thread_root_id(&self) -> Option<&str> {
+        let root = self.parent_message_id.as_deref().or_else(|| {
+            self.conversation_link
+                .as_deref()
+                .and_then(|link| link.rsplit('/').next())
+                .and_then(|id| split_thread_id(id).1)
+        })?;
+        (!root.is_empty() && Some(root) != self.id.as_deref()).then_some(root)
     }
-    // Fall back to last message sender or the thread ID
-    if let Some(ref msg) = conv.last_message {
-        if let Some(ref name) = msg.im_display_name {
-            if !name.is_empty() {
-                return name.clone();
-            }
-        }
+
+    /// Whether this message has been edited after it was first posted.
+    pub fn is_edited(&self) -> bool {
+        self.skype_edited_id.is_some()
+            || self
+                .properties
+                .as_ref()
+                .is_some_and(|p| property_is_set(&p.edittime))
+    }
+
+    /// Whether this message has been deleted.
+    pub fn is_deleted(&self) -> bool {
+        self.properties
+            .as_ref()
+            .is_some_and(|p| property_is_set(&p.deletetime))
+    }
+
+    /// Message ID (server-assigned, numeric string).
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Server arrival time (RFC 3339), falling back to the client compose time.
+    pub fn arrival_time(&self) -> Option<&str> {
+        self.original_arrival_time
+            .as_deref()
+            .or(self.compose_time.as_deref())
+    }
+
+    /// Message type, e.g. "RichText/Html", "Control/Typing", "ThreadActivity/AddMember".
+    pub fn message_type(&self) -> Option<&str> {
+        self.messagetype.as_deref()
+    }
+
+    /// Sender display name, falling back to the sender MRI from the `from` link.
+    pub fn sender_name(&self) -> Option<&str> {
+        self.im_display_name
+            .as_deref()
+            .filter(|name| !name.is_empty())
+            .or_else(|| self.sender_mri())
     }
-    conv.id.as_deref().unwrap_or("[unknown]").to_string()
+
+    /// Raw `properties.files` value, if the message shares files.
+    pub fn files(&self) -> Option<&serde_json::Value> {
+        self.properties.as_ref().and_then(|p| p.files.as_ref())
+    }
+
+    /// Sender MRI (e.g. `8:orgid:<aad-id>`), the trailing segment of `from`.
+    pub fn sender_mri(&self) -> Option<&str> {
+        self.from
+            .as_deref()
+            .and_then(|f| f.rsplit('/').next())
+            .filter(|mri| !mri.is_empty())
+    }
+}
+
+/// Split a channel thread ID (`19:...@thread.tacv2;messageid=123`) into the
+/// channel ID and the root message ID.
+pub fn split_thread_id(id: &str) -> (&str, Option<&str>) {
+    match id.split_once(";messageid=") {
+        Some((channel, root)) => (channel, Some(root)),
+        None => (id, None),
+    }
+}
+
+/// Whether a `properties` value is present and not empty/zero.
+fn property_is_set(value: &Option<serde_json::Value>) -> bool {
+    match value {
+        Some(serde_json::Value::String(s)) => !s.is_empty() && s != "0",
+        Some(serde_json::Value::Number(n)) => n.as_u64() != Some(0),
+        Some(serde_json::Value::Null) | None => false,
+        Some(_) => true,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    messages: Option<Vec<NativeMessage>>,
+    #[serde(rename = "_metadata")]
+    metadata: Option<MessagesMetadata>,
+}
+
+/// Paging links returned alongside a page of messages.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessagesMetadata {
+    /// URL of the next-older page (absent once history is exhausted).
+    backward_link: Option<String>,
+    /// URL that returns only messages newer than this page.
+    sync_state: Option<String>,
+}
+
+/// Plain text of HTML content (previews, search).
+pub(super) fn strip_html(html: &str) -> String {
+    RichText::from_html(html).plain_text()
 }
 
 /// List recent chats using the native Teams API (prints to stdout).
-pub async fn list_chats(limit: usize) -> Result<()> {
+pub async fn list_chats(limit: usize, format: OutputFormat) -> Result<()> {
     let client = TeamsClient::new().await?;
     let chats = list_chats_data(&client, limit).await?;
 
-    println!("\nRecent Chats:");
-    println!("{:-<60}", "");
+    output::print_list(format, &chats, |chats| {
+        println!("\nRecent Chats:");
+        println!("{:-<60}", "");
 
-    if chats.is_empty() {
-        println!("  (no chats found)");
-        return Ok(());
-    }
+        if chats.is_empty() {
+            println!("  (no chats found)");
+            return;
+        }
 
-    for chat in &chats {
-        println!("{}", chat.name);
-        println!("  ID: {}", chat.id);
+        for chat in chats {
+            println!("{}", chat.name);
+            println!("  ID: {}", chat.id);
 
-        if let Some(ref time) = chat.last_message_time {
-            println!("  Last: {}", time);
-        }
-        if let Some(ref preview) = chat.last_message_preview {
-            if !preview.trim().is_empty() {
-                let sender = chat.last_message_sender.as_deref().unwrap_or("?");
-                println!("  [{}]: {}", sender, preview.trim());
+            if let Some(ref time) = chat.last_message_time {
+                println!("  Last: {}", time);
+            }
+            if let Some(ref preview) = chat.last_message_preview {
+                if !preview.trim().is_empty() {
+                    let sender = chat.last_message_sender.as_deref().unwrap_or("?");
+                    println!("  [{}]: {}", sender, preview.trim());
+                }
             }
-        }
 
-        println!();
-    }
+            println!();
+        }
+    })
+}
 
-    Ok(())
+/// How far back `read` should go.
+pub enum HistoryRange {
+    /// The most recent `limit` messages (a single page).
+    Latest(usize),
+    /// Every message in the thread.
+    All,
+    /// Every message at or after the given time.
+    Since(DateTime<Utc>),
 }
 
 /// Read messages from a specific chat thread (prints to stdout).
-pub async fn read_messages(chat_id: &str, limit: usize) -> Result<()> {
+pub async fn read_messages(chat_id: &str, range: HistoryRange, format: OutputFormat) -> Result<()> {
     let client = TeamsClient::new().await?;
-    let msgs = read_messages_data(&client, chat_id, limit).await?;
+    let msgs = match range {
+        HistoryRange::Latest(limit) => read_latest_cached(&client, chat_id, limit).await?,
+        HistoryRange::All => read_history_data(&client, chat_id, None).await?,
+        HistoryRange::Since(since) => read_history_data(&client, chat_id, Some(since)).await?,
+    };
 
-    if msgs.is_empty() {
-        println!("(no messages)");
-        return Ok(());
+    output::print_list(format, &msgs, |msgs| {
+        if msgs.is_empty() {
+            println!("(no messages)");
+            return;
+        }
+
+        let flavor = TextFlavor::for_stdout();
+        for msg in msgs {
+            // Continuation lines of multi-line messages are indented.
+            let body = msg.rich.render(flavor).replace('\n', "\n    ");
+            println!(
+                "[{}] {}: {}{}",
+                msg.timestamp,
+                msg.sender,
+                body,
+                reaction_summary(&msg.reactions)
+            );
+            for att in &msg.attachments {
+                println!("    [attachment] {}", att.name);
+            }
+        }
+    })
+}
+
+/// Reaction counts appended to a text-mode message line, e.g. `  (like 2)`.
+fn reaction_summary(reactions: &[ReactionInfo]) -> String {
+    if reactions.is_empty() {
+        return String::new();
     }
+    let counts: Vec<String> = reactions
+        .iter()
+        .map(|r| format!("{} {}", r.key, r.users.len()))
+        .collect();
+    format!("  ({})", counts.join(", "))
+}
 
-    for msg in &msgs {
-        println!("[{}] {}: {}", msg.timestamp, msg.sender, msg.content);
+/// Latest `limit` messages, synced through the local cache. Falls back to
+/// cached messages (with a warning) when the chat service is unreachable.
+async fn read_latest_cached(
+    client: &TeamsClient,
+    chat_id: &str,
+    limit: usize,
+) -> Result<Vec<MessageInfo>> {
+    let cache = match cache::MessageCache::open() {
+        Ok(cache) => cache,
+        Err(_) => return read_messages_data(client, chat_id, limit).await,
+    };
+
+    let mut msgs = match cache::sync_conversation(client, &cache, chat_id, limit).await {
+        Ok((page, _)) => page.messages,
+        Err(e) => match cache.load(chat_id) {
+            Ok(Some(conv)) => {
+                eprintln!("Warning: {:#} (showing cached messages)", e);
+                conv.page().messages
+            }
+            _ => return Err(e),
+        },
+    };
+
+    let skip = msgs.len().saturating_sub(limit);
+    msgs.drain(..skip);
+    Ok(msgs)
+}
+
+/// Send a message to a chat thread using the native API, optionally as a
+/// reply quoting the message `reply_to`.
+pub async fn send_message(chat_id: &str, message: &str, reply_to: Option<&str>) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    let quote = match reply_to {
+        Some(message_id) => {
+            let native = find_message(&client, chat_id, message_id).await?;
+            let msg = message_info(&native)
+                .with_context(|| format!("Message {} has no text to quote", message_id))?;
+            Some(QuotedMessage::new(
+                &msg.id,
+                &msg.sender,
+                msg.sender_id.as_deref(),
+                &msg.rich,
+            ))
+        }
+        None => None,
+    };
+    let mentions = mentions::resolve_mentions(&client, chat_id, message).await;
+    send_message_with_client(&client, chat_id, message, &mentions, quote.as_ref()).await?;
+    println!("Message sent.");
+    Ok(())
+}
+
+/// Post to a team channel given as `team/channel` (or a raw channel ID),
+/// either as a new post or as a reply to the thread rooted at `reply_to`.
+pub async fn send_channel_message(
+    channel: &str,
+    message: &str,
+    subject: Option<&str>,
+    reply_to: Option<&str>,
+) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    let channel_id = super::teams::resolve_channel(&client, channel).await?;
+    let mentions = mentions::resolve_mentions(&client, &channel_id, message).await;
+
+    match reply_to {
+        Some(parent_id) => {
+            reply_to_channel_message_with_client(
+                &client,
+                &channel_id,
+                parent_id,
+                message,
+                &mentions,
+            )
+            .await?;
+            println!("Reply sent.");
+        }
+        None => {
+            send_channel_message_with_client(&client, &channel_id, message, subject, &mentions)
+                .await?;
+            println!("Post sent.");
+        }
     }
+    Ok(())
+}
 
+/// Replace the text of one of our messages.
+pub async fn edit_message(chat_id: &str, message_id: &str, message: &str) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    let mentions = mentions::resolve_mentions(&client, chat_id, message).await;
+    // Keep the quote of an edited reply.
+    let native = find_message(&client, chat_id, message_id).await?;
+    let quote =
+        message_info(&native).and_then(|msg| msg.rich.reply().and_then(QuotedMessage::from_reply));
+    edit_message_with_client(
+        &client,
+        chat_id,
+        message_id,
+        message,
+        &mentions,
+        quote.as_ref(),
+    )
+    .await?;
+    println!("Message edited.");
     Ok(())
 }
 
-/// Send a message to a chat thread using the native API.
-pub async fn send_message(chat_id: &str, message: &str) -> Result<()> {
+/// Delete one of our messages.
+pub async fn delete_message(chat_id: &str, message_id: &str) -> Result<()> {
     let client = TeamsClient::new().await?;
-    send_message_with_client(&client, chat_id, message).await?;
-    println!("Message sent.");
+    delete_message_with_client(&client, chat_id, message_id).await?;
+    println!("Message deleted.");
     Ok(())
 }
 
 /// HTML-escape text for embedding in Teams RichText/Html messages.
-fn html_escape(text: &str) -> String {
+pub(super) fn html_escape(text: &str) -> String {
     text.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -161,47 +423,246 @@ fn html_escape(text: &str) -> String {
         .replace('\'', "&#39;")
 }
 
+/// Characters of the quoted text kept in a reply's quote.
+const QUOTE_PREVIEW_CHARS: usize = 120;
+
+/// A message being replied to: quoted in chats, or the root of the thread
+/// in channels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotedMessage {
+    pub message_id: String,
+    pub sender: String,
+    /// Sender MRI, when known.
+    pub sender_id: Option<String>,
+    /// Start of the quoted text, on one line.
+    pub preview: String,
+}
+
+impl QuotedMessage {
+    /// Quote a message. A quote the message itself starts with is left
+    /// out of the preview.
+    pub fn new(message_id: &str, sender: &str, sender_id: Option<&str>, rich: &RichText) -> Self {
+        let text = rich.without_reply().plain_text();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut preview: String = text.chars().take(QUOTE_PREVIEW_CHARS).collect();
+        if preview.len() < text.len() {
+            preview.push('\u{2026}');
+        }
+        Self {
+            message_id: message_id.to_string(),
+            sender: sender.to_string(),
+            sender_id: sender_id.map(String::from),
+            preview,
+        }
+    }
+
+    /// The quote a received reply starts with, if it names its message.
+    pub fn from_reply(quote: &ReplyQuote) -> Option<Self> {
+        let message_id = quote.message_id.as_deref()?;
+        let rich = RichText {
+            blocks: quote.blocks.clone(),
+        };
+        Some(Self::new(
+            message_id,
+            &quote.sender,
+            quote.sender_id.as_deref(),
+            &rich,
+        ))
+    }
+
+    /// Teams quote-reply markup, placed before the reply text.
+    fn to_html(&self) -> String {
+        format!(
+            "<blockquote itemscope=\"\" itemtype=\"http://schema.skype.com/Reply\" itemid=\"{id}\">\
+             <strong itemprop=\"mri\" itemid=\"{mri}\">{sender}</strong>\
+             <span itemprop=\"time\" itemid=\"{id}\"></span>\
+             <p itemprop=\"preview\">{preview}</p></blockquote>",
+            id = html_escape(&self.message_id),
+            mri = html_escape(self.sender_id.as_deref().unwrap_or_default()),
+            sender = html_escape(&self.sender),
+            preview = html_escape(&self.preview),
+        )
+    }
+}
+
+/// Request body for a message composed in Markdown, sent as Teams HTML.
+/// `@Name` text for any of `mentions` becomes a mention; a `quote` is
+/// shown above the text.
+fn message_body(
+    message: &str,
+    mentions: &[Mention],
+    quote: Option<&QuotedMessage>,
+) -> serde_json::Value {
+    let (mut html, used) = markdown_to_html(message, mentions);
+    if let Some(quote) = quote {
+        html.insert_str(0, &quote.to_html());
+    }
+    let mut body = html_message_body(&html);
+    if !used.is_empty() {
+        body["properties"]["mentions"] = mentions::mentions_property(&used);
+    }
+    body
+}
+
+/// Request body for a message with ready-made Teams HTML content.
+pub(super) fn html_message_body(html: &str) -> serde_json::Value {
+    serde_json::json!({
+        "content": html,
+        "messagetype": "RichText/Html",
+        "contenttype": "text"
+    })
+}
+
+/// POST a message body to a conversation. Channel threads are addressed as
+/// `{channel_id};messageid={root_id}`.
+pub(super) async fn post_message(
+    client: &TeamsClient,
+    conversation: &str,
+    body: &serde_json::Value,
+) -> Result<()> {
+    let base = client.chat_service_url();
+    let url = format!(
+        "{}/v1/users/ME/conversations/{}/messages",
+        base, conversation
+    );
+
+    tracing::debug!("Sending message to {}", url);
+    client.chat_post(&url, body).await?;
+    Ok(())
+}
+
 /// Send a message using an existing client (shared helper).
 pub async fn send_message_with_client(
     client: &TeamsClient,
     chat_id: &str,
     message: &str,
+    mentions: &[Mention],
+    quote: Option<&QuotedMessage>,
 ) -> Result<()> {
-    let base = client.chat_service_url();
-    let url = format!("{}/v1/users/ME/conversations/{}/messages", base, chat_id);
+    post_message(client, chat_id, &message_body(message, mentions, quote)).await
+}
 
-    let escaped = html_escape(message);
-    let body = serde_json::json!({
-        "content": format!("<p>{}</p>", escaped),
-        "messagetype": "RichText/Html",
-        "contenttype": "text"
-    });
+/// URL of a single message in a conversation.
+pub(super) fn message_url(client: &TeamsClient, conversation: &str, message_id: &str) -> String {
+    format!(
+        "{}/v1/users/ME/conversations/{}/messages/{}",
+        client.chat_service_url(),
+        conversation,
+        message_id
+    )
+}
 
-    tracing::debug!("Sending message to {}", url);
-    client.chat_post(&url, &body).await?;
+/// Replace the content of an existing message (the server only allows
+/// this for messages we sent).
+pub async fn edit_message_with_client(
+    client: &TeamsClient,
+    chat_id: &str,
+    message_id: &str,
+    message: &str,
+    mentions: &[Mention],
+    quote: Option<&QuotedMessage>,
+) -> Result<()> {
+    let url = message_url(client, chat_id, message_id);
+    tracing::debug!("Editing message {}", url);
+    client
+        .chat_put(&url, &message_body(message, mentions, quote))
+        .await?;
     Ok(())
 }
 
+/// Soft-delete a message; it is replaced by a "message deleted" stub for
+/// other participants.
+pub async fn delete_message_with_client(
+    client: &TeamsClient,
+    chat_id: &str,
+    message_id: &str,
+) -> Result<()> {
+    let url = format!(
+        "{}?behavior=softDelete",
+        message_url(client, chat_id, message_id)
+    );
+    tracing::debug!("Deleting message {}", url);
+    client.chat_delete(&url, None).await?;
+    Ok(())
+}
+
+/// Start a new thread in a team channel, optionally with a subject line.
+pub async fn send_channel_message_with_client(
+    client: &TeamsClient,
+    channel_id: &str,
+    message: &str,
+    subject: Option<&str>,
+    mentions: &[Mention],
+) -> Result<()> {
+    let mut body = message_body(message, mentions, None);
+    if let Some(subject) = subject.filter(|s| !s.trim().is_empty()) {
+        body["properties"]["subject"] = subject.trim().into();
+    }
+    post_message(client, channel_id, &body).await
+}
+
+/// Reply to the channel thread whose root post is `parent_id`.
+pub async fn reply_to_channel_message_with_client(
+    client: &TeamsClient,
+    channel_id: &str,
+    parent_id: &str,
+    message: &str,
+    mentions: &[Mention],
+) -> Result<()> {
+    let thread = format!("{};messageid={}", channel_id, parent_id);
+    post_message(client, &thread, &message_body(message, mentions, None)).await
+}
+
 // ---------------------------------------------------------------------------
 // Data-returning API functions for TUI integration
 // ---------------------------------------------------------------------------
 
 /// Chat metadata for TUI display.
-#[allow(dead_code)]
+#[derive(Serialize)]
 pub struct ChatInfo {
     pub id: String,
     pub name: String,
     pub is_group: bool,
+    /// MRI of the other person in a 1:1 chat.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partner_mri: Option<String>,
     pub last_message_time: Option<String>,
     pub last_message_sender: Option<String>,
     pub last_message_preview: Option<String>,
 }
 
 /// A single message for TUI display.
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct MessageInfo {
+    /// Server-assigned message ID (used to apply live edits/deletes).
+    pub id: String,
+    /// ID given by the sending client, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_message_id: Option<String>,
     pub sender: String,
+    /// Sender MRI (`8:orgid:<aad-id>`), when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_id: Option<String>,
     pub timestamp: String,
+    /// Plain-text content.
     pub content: String,
+    /// Formatted content (not serialized; `content` carries the text).
+    #[serde(skip)]
+    pub rich: RichText,
+    /// Root post ID when this is a reply in a channel thread.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reactions: Vec<ReactionInfo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<AttachmentInfo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mentions: Vec<Mention>,
+}
+
+/// MRI of an Entra ID (AAD) user, as used in chat service `from` links.
+pub fn user_mri(aad_id: &str) -> String {
+    format!("8:orgid:{}", aad_id)
 }
 
 /// List recent chats and return structured data.
@@ -245,6 +706,7 @@ pub async fn list_chats_data(client: &TeamsClient, limit: usize) -> Result<Vec<C
         .context("Failed to parse conversations response")?;
 
     let conversations = body.conversations.unwrap_or_default();
+    let names = ChatNames::resolve(client, &conversations).await;
 
     let mut chats = Vec::new();
     for conv in &conversations {
@@ -253,7 +715,7 @@ pub async fn list_chats_data(client: &TeamsClient, limit: usize) -> Result<Vec<C
             continue;
         }
 
-        let name = conversation_name(conv);
+        let name = names.conversation_name(conv);
         let is_group = id.contains("thread") || id.contains("meeting");
 
         let (last_time, last_sender, last_preview) = if let Some(ref msg) = conv.last_message {
@@ -264,7 +726,7 @@ pub async fn list_chats_data(client: &TeamsClient, limit: usize) -> Result<Vec<C
                 .map(String::from);
             let sender = msg.im_display_name.clone();
             let preview = msg.content.as_deref().map(|c| {
-                let text = strip_html(c);
+                let text = strip_html(c).replace('\n', " ");
                 if text.len() > 80 {
                     let end = text
                         .char_indices()
@@ -283,6 +745,7 @@ pub async fn list_chats_data(client: &TeamsClient, limit: usize) -> Result<Vec<C
         };
 
         chats.push(ChatInfo {
+            partner_mri: names.partner(conv),
             id,
             name,
             is_group,
@@ -301,53 +764,246 @@ pub async fn read_messages_data(
     chat_id: &str,
     limit: usize,
 ) -> Result<Vec<MessageInfo>> {
-    let base = client.chat_service_url();
-    let url = format!(
+    Ok(read_messages_page(client, chat_id, limit).await?.messages)
+}
+
+/// Read the newest page of a chat thread, including its paging links.
+pub async fn read_messages_page(
+    client: &TeamsClient,
+    chat_id: &str,
+    limit: usize,
+) -> Result<MessagePage> {
+    let url = messages_url(client, chat_id, limit);
+    Ok(fetch_native_page(client, &url).await?.into())
+}
+
+/// Read the page older than one previously fetched (via its `backward_link`).
+pub async fn read_older_messages(client: &TeamsClient, backward_link: &str) -> Result<MessagePage> {
+    Ok(fetch_native_page(client, backward_link).await?.into())
+}
+
+/// URL of the newest page of messages in a conversation.
+pub fn messages_url(client: &TeamsClient, chat_id: &str, limit: usize) -> String {
+    format!(
         "{}/v1/users/ME/conversations/{}/messages?pageSize={}",
-        base, chat_id, limit
-    );
+        client.chat_service_url(),
+        chat_id,
+        limit
+    )
+}
 
-    tracing::debug!("Reading messages from {}", url);
+/// Read a thread's history newest-to-oldest, following backward links until
+/// history is exhausted or messages predate `since`.
+///
+/// Returns messages in chronological order.
+pub async fn read_history_data(
+    client: &TeamsClient,
+    chat_id: &str,
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<MessageInfo>> {
+    let mut pages = MessagePager::new(chat_id, HISTORY_PAGE_SIZE);
+    let mut msgs = Vec::new();
+
+    while let Some(page) = pages.next_page(client).await? {
+        let reached_since = since.is_some_and(|since| {
+            page.messages
+                .first()
+                .and_then(|m| parse_timestamp(&m.timestamp))
+                .is_some_and(|oldest| oldest < since)
+        });
+
+        // Pages arrive newest-first; prepend each chronological page.
+        let mut page_msgs = page.messages;
+        if let Some(since) = since {
+            page_msgs.retain(|m| !matches!(parse_timestamp(&m.timestamp), Some(t) if t < since));
+        }
+        page_msgs.append(&mut msgs);
+        msgs = page_msgs;
+
+        if reached_since {
+            break;
+        }
+    }
+
+    Ok(msgs)
+}
+
+/// Page size used when walking full history.
+const HISTORY_PAGE_SIZE: usize = 200;
+
+/// One page of messages plus the links needed to fetch more.
+#[derive(Debug, Clone)]
+pub struct MessagePage {
+    /// Messages in chronological order (oldest first).
+    pub messages: Vec<MessageInfo>,
+    /// URL of the next-older page, if there is more history.
+    pub backward_link: Option<String>,
+}
+
+/// Fetch a single message, from the local cache when it is there.
+pub async fn find_message(
+    client: &TeamsClient,
+    chat_id: &str,
+    message_id: &str,
+) -> Result<NativeMessage> {
+    let cached = cache::MessageCache::open()
+        .ok()
+        .and_then(|cache| cache.load(chat_id).ok().flatten())
+        .and_then(|conv| {
+            conv.messages
+                .into_iter()
+                .find(|m| m.id() == Some(message_id))
+        });
+    if let Some(msg) = cached {
+        return Ok(msg);
+    }
+
+    let url = message_url(client, chat_id, message_id);
     let resp = client.chat_get(&url).await?;
+    resp.json().await.context("Failed to parse message")
+}
+
+/// Walks a thread's history backwards, one page per call.
+pub struct MessagePager {
+    next_url: Option<String>,
+    chat_id: String,
+    page_size: usize,
+    started: bool,
+}
+
+impl MessagePager {
+    /// Start paging from the newest message of `chat_id`.
+    pub fn new(chat_id: &str, page_size: usize) -> Self {
+        Self {
+            next_url: None,
+            chat_id: chat_id.to_string(),
+            page_size,
+            started: false,
+        }
+    }
+
+    /// Fetch the next-older page, or `None` once history is exhausted.
+    pub async fn next_page(&mut self, client: &TeamsClient) -> Result<Option<MessagePage>> {
+        let page = if !self.started {
+            self.started = true;
+            read_messages_page(client, &self.chat_id, self.page_size).await?
+        } else {
+            match self.next_url.take() {
+                Some(url) => read_older_messages(client, &url).await?,
+                None => return Ok(None),
+            }
+        };
+        self.next_url = page.backward_link.clone();
+        Ok(Some(page))
+    }
+}
+
+/// One page of undecoded messages, as returned by the chat service.
+pub struct NativePage {
+    /// Messages newest-first (server order).
+    pub messages: Vec<NativeMessage>,
+    /// URL of the next-older page, if there is more history.
+    pub backward_link: Option<String>,
+    /// URL returning only messages newer than this page.
+    pub sync_state: Option<String>,
+}
+
+impl From<NativePage> for MessagePage {
+    fn from(page: NativePage) -> Self {
+        // Messages come newest-first; reverse for chronological display
+        MessagePage {
+            messages: page
+                .messages
+                .iter()
+                .rev()
+                .filter_map(message_info)
+                .collect(),
+            backward_link: page.backward_link,
+        }
+    }
+}
+
+/// Fetch one page of messages from `url` (a first-page, backward or sync link).
+pub async fn fetch_native_page(client: &TeamsClient, url: &str) -> Result<NativePage> {
+    tracing::debug!("Reading messages from {}", url);
+    let resp = client.chat_get(url).await?;
     let body: MessagesResponse = resp
         .json()
         .await
         .context("Failed to parse messages response")?;
 
     let messages = body.messages.unwrap_or_default();
+    let metadata = body.metadata;
+
+    // An empty page ends the walk even if the server still hands out a link.
+    let backward_link = metadata
+        .as_ref()
+        .and_then(|m| m.backward_link.clone())
+        .filter(|link| !link.is_empty() && link != url && !messages.is_empty());
+    let sync_state = metadata.and_then(|m| m.sync_state);
+
+    Ok(NativePage {
+        messages,
+        backward_link,
+        sync_state,
+    })
+}
 
-    // Messages come newest-first; reverse for chronological display
-    let mut msgs: Vec<&NativeMessage> = messages.iter().collect();
-    msgs.reverse();
-
-    let mut result = Vec::new();
-    for msg in &msgs {
-        let msgtype = msg.messagetype.as_deref().unwrap_or("");
-        // Skip non-text messages (e.g. ThreadActivity/*)
-        if !msgtype.contains("Text") && !msgtype.contains("RichText") {
-            continue;
-        }
-
-        let sender = msg.im_display_name.as_deref().unwrap_or("?").to_string();
-        let time = msg
-            .original_arrival_time
-            .as_deref()
-            .or(msg.compose_time.as_deref())
-            .unwrap_or("")
-            .to_string();
-        let content = msg.content.as_deref().unwrap_or("");
-        let text = strip_html(content);
+/// Parse a chat service timestamp (RFC 3339, e.g. `2024-01-15T10:30:00.123Z`).
+fn parse_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
 
-        if text.trim().is_empty() {
-            continue;
-        }
+/// Convert a native message into display form.
+///
+/// Returns `None` for non-text messages (e.g. ThreadActivity/*), deleted
+/// messages, and messages with neither text (after stripping HTML) nor
+/// attachments.
+pub fn message_info(msg: &NativeMessage) -> Option<MessageInfo> {
+    let msgtype = msg.messagetype.as_deref().unwrap_or("");
+    if !msgtype.contains("Text") && !msgtype.contains("RichText") {
+        return None;
+    }
 
-        result.push(MessageInfo {
-            sender,
-            timestamp: time,
-            content: text.trim().to_string(),
-        });
+    let sender = msg.im_display_name.as_deref().unwrap_or("?").to_string();
+    let time = msg
+        .original_arrival_time
+        .as_deref()
+        .or(msg.compose_time.as_deref())
+        .unwrap_or("")
+        .to_string();
+    let content = msg.content.as_deref().unwrap_or("");
+    let rich = RichText::from_html(content);
+    let text = rich.plain_text();
+    let attachments = attachments::message_attachments(msg, content);
+
+    if msg.is_deleted() || (text.trim().is_empty() && attachments.is_empty()) {
+        return None;
     }
 
-    Ok(result)
+    Some(MessageInfo {
+        id: msg.id.clone().unwrap_or_default(),
+        client_message_id: msg.client_message_id.clone(),
+        sender,
+        sender_id: msg.sender_mri().map(String::from),
+        timestamp: time,
+        content: text.trim().to_string(),
+        rich,
+        parent_id: msg.thread_root_id().map(String::from),
+        reactions: msg
+            .properties
+            .as_ref()
+            .and_then(|p| p.emotions.as_ref())
+            .map(reactions::parse_emotions)
+            .unwrap_or_default(),
+        attachments,
+        mentions: msg
+            .properties
+            .as_ref()
+            .and_then(|p| p.mentions.as_ref())
+            .map(mentions::parse_mentions)
+            .unwrap_or_default(),
+    })
 }
@@ -7,9 +7,13 @@ use anyhow::{bail, Context, Result};
 use crate::auth::TokenStore;
 use crate::config::Config;
 
-const GRAPH_BASE: &str = "https://graph.microsoft.com/v1.0";
+pub(super) const GRAPH_BASE: &str = "https://graph.microsoft.com/v1.0";
 const DEFAULT_CHAT_SERVICE: &str = "https://amer.ng.msg.teams.microsoft.com";
 const CHATSVCAGG: &str = "https://chatsvcagg.teams.microsoft.com";
+const DEFAULT_AMS: &str = "https://us-api.asm.skype.com";
+
+/// Parent domain of the regional AMS hosts (`us-api.asm.skype.com`, ...).
+const AMS_DOMAIN: &str = "asm.skype.com";
 
 /// Authenticated client that handles both Graph (AAD) and Teams (Skype) APIs.
 pub struct TeamsClient {
@@ -112,6 +116,52 @@ impl TeamsClient {
         check_response(resp, &url).await
     }
 
+    /// PUT raw bytes to Microsoft Graph API (bearer auth with Graph token).
+    pub async fn graph_put_bytes(&self, path: &str, bytes: Vec<u8>) -> Result<reqwest::Response> {
+        let token = self.graph_token()?;
+        let url = format!("{}{}", GRAPH_BASE, path);
+        tracing::debug!("Graph PUT {} ({} bytes)", url, bytes.len());
+
+        let resp = self
+            .http
+            .put(&url)
+            .bearer_auth(&token)
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .body(bytes)
+            .send()
+            .await
+            .with_context(|| format!("Graph PUT {} failed", url))?;
+
+        check_response(resp, &url).await
+    }
+
+    /// PUT one chunk to a Graph upload session URL. The URL is
+    /// pre-authorized, so no token is sent.
+    pub async fn upload_chunk(
+        &self,
+        url: &str,
+        bytes: Vec<u8>,
+        offset: usize,
+        total: usize,
+    ) -> Result<reqwest::Response> {
+        let end = offset + bytes.len() - 1;
+        tracing::debug!("Upload chunk {}-{}/{}", offset, end, total);
+
+        let resp = self
+            .http
+            .put(url)
+            .header(
+                reqwest::header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", offset, end, total),
+            )
+            .body(bytes)
+            .send()
+            .await
+            .context("Upload chunk failed")?;
+
+        check_response(resp, "upload session").await
+    }
+
     /// GET request to Teams/Skype API (X-SkypeToken header).
     pub async fn teams_get(&self, url: &str) -> Result<reqwest::Response> {
         let token = self.skype_token()?;
@@ -161,6 +211,30 @@ impl TeamsClient {
             .unwrap_or_else(|| DEFAULT_CHAT_SERVICE.to_string())
     }
 
+    /// AMS (media/object store) base URL from region_gtms, falling back to default.
+    pub fn ams_url(&self) -> String {
+        self.config
+            .get_region_gtms()
+            .and_then(|v| v.get("ams").and_then(|s| s.as_str()).map(String::from))
+            .unwrap_or_else(|| DEFAULT_AMS.to_string())
+    }
+
+    /// Whether `url` may be sent the Skype token by the `ams_*` requests:
+    /// https, on the configured AMS host or another AMS host.
+    pub fn is_ams_url(&self, url: &str) -> bool {
+        is_ams_domain_url(url)
+            || https_host(url).is_some_and(|host| Some(host) == https_host(&self.ams_url()))
+    }
+
+    /// Refuse to attach the Skype token to anything but an AMS URL (image
+    /// URLs come from message HTML that anyone in the chat can write).
+    fn check_ams_url(&self, url: &str) -> Result<()> {
+        if !self.is_ams_url(url) {
+            bail!("Refusing to send credentials to {}: not an AMS URL", url);
+        }
+        Ok(())
+    }
+
     /// Chat service aggregator URL from region_gtms, falling back to default.
     pub fn chatsvcagg_url(&self) -> String {
         self.config
@@ -226,6 +300,120 @@ impl TeamsClient {
 
         check_response(resp, url).await
     }
+
+    /// PUT using `Authentication: skypetoken=...` header (native chat API).
+    pub async fn chat_put(&self, url: &str, body: &serde_json::Value) -> Result<reqwest::Response> {
+        let token = self.skype_token()?;
+        tracing::debug!("Chat PUT {}", url);
+
+        let resp = self
+            .http
+            .put(url)
+            .header("Authentication", format!("skypetoken={}", token))
+            .json(body)
+            .send()
+            .await
+            .with_context(|| format!("Chat PUT {} failed", url))?;
+
+        check_response(resp, url).await
+    }
+
+    /// GET an AMS object (images) using `Authorization: skype_token ...`.
+    pub async fn ams_get(&self, url: &str) -> Result<reqwest::Response> {
+        self.check_ams_url(url)?;
+        let token = self.skype_token()?;
+        tracing::debug!("AMS GET {}", url);
+
+        let resp = self
+            .http
+            .get(url)
+            .header("Authorization", format!("skype_token {}", token))
+            .send()
+            .await
+            .with_context(|| format!("AMS GET {} failed", url))?;
+
+        check_response(resp, url).await
+    }
+
+    /// POST JSON to AMS using `Authorization: skype_token ...`.
+    pub async fn ams_post(&self, url: &str, body: &serde_json::Value) -> Result<reqwest::Response> {
+        self.check_ams_url(url)?;
+        let token = self.skype_token()?;
+        tracing::debug!("AMS POST {}", url);
+
+        let resp = self
+            .http
+            .post(url)
+            .header("Authorization", format!("skype_token {}", token))
+            .json(body)
+            .send()
+            .await
+            .with_context(|| format!("AMS POST {} failed", url))?;
+
+        check_response(resp, url).await
+    }
+
+    /// PUT raw content to an AMS object using `Authorization: skype_token ...`.
+    pub async fn ams_put(&self, url: &str, bytes: Vec<u8>) -> Result<reqwest::Response> {
+        self.check_ams_url(url)?;
+        let token = self.skype_token()?;
+        tracing::debug!("AMS PUT {} ({} bytes)", url, bytes.len());
+
+        let resp = self
+            .http
+            .put(url)
+            .header("Authorization", format!("skype_token {}", token))
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .body(bytes)
+            .send()
+            .await
+            .with_context(|| format!("AMS PUT {} failed", url))?;
+
+        check_response(resp, url).await
+    }
+
+    /// DELETE using `Authentication: skypetoken=...` header (native chat API).
+    pub async fn chat_delete(
+        &self,
+        url: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<reqwest::Response> {
+        let token = self.skype_token()?;
+        tracing::debug!("Chat DELETE {}", url);
+
+        let mut req = self
+            .http
+            .delete(url)
+            .header("Authentication", format!("skypetoken={}", token));
+        if let Some(body) = body {
+            req = req.json(body);
+        }
+        let resp = req
+            .send()
+            .await
+            .with_context(|| format!("Chat DELETE {} failed", url))?;
+
+        check_response(resp, url).await
+    }
+}
+
+/// Whether `url` is an https URL on an AMS host (`*.asm.skype.com`).
+pub fn is_ams_domain_url(url: &str) -> bool {
+    https_host(url).is_some_and(|host| {
+        host == AMS_DOMAIN
+            || host
+                .strip_suffix(AMS_DOMAIN)
+                .is_some_and(|sub| sub.ends_with('.'))
+    })
+}
+
+/// Host of an https URL.
+fn https_host(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    if url.scheme() != "https" {
+        return None;
+    }
+    url.host_str().map(str::to_ascii_lowercase)
 }
 
 /// Check HTTP response status code and return a clear error on failure.
@@ -243,3 +431,27 @@ async fn check_response(resp: reqwest::Response, url: &str) -> Result<reqwest::R
     }
     Ok(resp)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_https_ams_hosts_are_ams_urls() {
+        assert!(is_ams_domain_url(
+            "https://us-api.asm.skype.com/v1/objects/0-abc/views/imgo"
+        ));
+        assert!(is_ams_domain_url("https://ASM.SKYPE.COM/v1/objects/x"));
+        assert!(!is_ams_domain_url(
+            "http://us-api.asm.skype.com/v1/objects/0-abc"
+        ));
+        assert!(!is_ams_domain_url("https://evilasm.skype.com/v1/objects/x"));
+        assert!(!is_ams_domain_url(
+            "https://us-api.asm.skype.com.example.com/v1/objects/x"
+        ));
+        assert!(!is_ams_domain_url(
+            "https://example.com/v1/objects/x?h=us-api.asm.skype.com"
+        ));
+        assert!(!is_ams_domain_url("not a url"));
+    }
+}
@@ -0,0 +1,510 @@
+//! Markdown compose → Teams HTML
+//!
+//! Outgoing messages are written in a small Markdown dialect and sent as
+//! `RichText/Html`:
+//!
+//! - inline: `**bold**`, `_italic_` / `*italic*`, `++underline++`,
+//!   `~~strike~~`, `` `code` ``, `[text](url)`, `<url>` and bare URLs;
+//! - blocks: fenced code (```` ``` ````), `-` / `1.` lists (nested by
+//!   indentation), `>` quotes.
+//!
+//! Single newlines are line breaks and blank lines separate paragraphs, so
+//! pasted stack traces survive even without a fence. `@Name` becomes a
+//! mention span for any of the given mentions. Everything else is
+//! HTML-escaped.
+
+use super::chat::html_escape;
+use super::mentions::{Mention, MENTION_ITEMTYPE};
+
+/// Convert Markdown message text to Teams HTML. Returns the HTML and the
+/// mentions it contains, in `itemid` order (one per occurrence).
+pub fn markdown_to_html(text: &str, mentions: &[Mention]) -> (String, Vec<Mention>) {
+    let mut names: Vec<(Vec<char>, &Mention)> = mentions
+        .iter()
+        .map(|m| (m.display_name.to_lowercase().chars().collect::<Vec<_>>(), m))
+        .filter(|(name, _)| !name.is_empty())
+        .collect();
+    // Longest first, so "@Bob Stone" wins over "@Bob".
+    names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+    let mut converter = Converter {
+        mentions: names,
+        used: Vec::new(),
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    let mut out = String::new();
+    converter.render_blocks(&lines, &mut out);
+    (out, converter.used)
+}
+
+struct Converter<'a> {
+    /// Lowercased mention names and their mentions.
+    mentions: Vec<(Vec<char>, &'a Mention)>,
+    /// Mentions emitted so far; the index is the span's `itemid`.
+    used: Vec<Mention>,
+}
+
+impl Converter<'_> {
+    fn render_blocks(&mut self, lines: &[&str], out: &mut String) {
+        let mut para: Vec<&str> = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            let trimmed = line.trim_start();
+
+            if trimmed.is_empty() {
+                self.flush_paragraph(&mut para, out);
+                i += 1;
+            } else if let Some(fence) = fence(trimmed) {
+                self.flush_paragraph(&mut para, out);
+                let lang = trimmed.trim_start_matches(fence.chars().next().unwrap_or('`'));
+                let mut code = Vec::new();
+                i += 1;
+                while i < lines.len() && !lines[i].trim_start().starts_with(fence) {
+                    code.push(lines[i]);
+                    i += 1;
+                }
+                // Skip the closing fence (an unclosed fence runs to the end).
+                i += 1;
+                out.push_str(&format!(
+                    "<codeblock class=\"{}\"><code>{}</code></codeblock>",
+                    html_escape(lang.trim()),
+                    html_escape(&code.join("\n"))
+                ));
+            } else if trimmed.starts_with('>') {
+                self.flush_paragraph(&mut para, out);
+                let mut quoted = Vec::new();
+                while let Some(rest) = lines.get(i).and_then(|l| l.trim_start().strip_prefix('>')) {
+                    quoted.push(rest.strip_prefix(' ').unwrap_or(rest));
+                    i += 1;
+                }
+                out.push_str("<blockquote>");
+                self.render_blocks(&quoted, out);
+                out.push_str("</blockquote>");
+            } else if list_item(line).is_some() {
+                self.flush_paragraph(&mut para, out);
+                i = self.render_list(lines, i, out);
+            } else {
+                para.push(line);
+                i += 1;
+            }
+        }
+        self.flush_paragraph(&mut para, out);
+    }
+
+    fn flush_paragraph(&mut self, para: &mut Vec<&str>, out: &mut String) {
+        if para.is_empty() {
+            return;
+        }
+        let lines: Vec<String> = para.drain(..).map(|l| self.inline(l.trim_end())).collect();
+        out.push_str(&format!("<p>{}</p>", lines.join("<br>")));
+    }
+
+    /// Render the list starting at `lines[start]`; returns the index of the
+    /// first line after it. Deeper-indented items become nested lists, and
+    /// indented plain lines continue the previous item.
+    fn render_list(&mut self, lines: &[&str], start: usize, out: &mut String) -> usize {
+        let Some((indent, ordered, _)) = list_item(lines[start]) else {
+            return start + 1;
+        };
+        let tag = if ordered { "ol" } else { "ul" };
+        out.push_str(&format!("<{}>", tag));
+
+        let mut i = start;
+        while let Some((_, _, content)) = lines
+            .get(i)
+            .and_then(|l| list_item(l))
+            .filter(|&(ind, ord, _)| ind == indent && ord == ordered)
+        {
+            out.push_str("<li>");
+            out.push_str(&self.inline(content.trim_end()));
+            i += 1;
+            while i < lines.len() {
+                match list_item(lines[i]) {
+                    Some((ind, _, _)) if ind > indent => i = self.render_list(lines, i, out),
+                    Some(_) => break,
+                    None if lines[i].trim().is_empty() => {
+                        // A blank line only stays inside the item before a
+                        // nested list.
+                        match lines.get(i + 1).and_then(|l| list_item(l)) {
+                            Some((ind, _, _)) if ind > indent => i += 1,
+                            _ => break,
+                        }
+                    }
+                    None if lines[i].starts_with(' ') => {
+                        out.push_str("<br>");
+                        out.push_str(&self.inline(lines[i].trim()));
+                        i += 1;
+                    }
+                    None => break,
+                }
+            }
+            out.push_str("</li>");
+        }
+
+        out.push_str(&format!("</{}>", tag));
+        i
+    }
+
+    /// Convert inline Markdown in one line of text.
+    fn inline(&mut self, text: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let mut out = String::new();
+        let mut i = 0;
+
+        'outer: while i < chars.len() {
+            let c = chars[i];
+
+            if c == '\\' && chars.get(i + 1).is_some_and(|n| n.is_ascii_punctuation()) {
+                out.push_str(&html_escape(&chars[i + 1].to_string()));
+                i += 2;
+                continue;
+            }
+
+            if c == '`' {
+                let ticks = run_length(&chars, i, '`');
+                if let Some(close) = find_run(&chars, i + ticks, '`', ticks) {
+                    let code: String = chars[i + ticks..close].iter().collect();
+                    out.push_str(&format!("<code>{}</code>", html_escape(code.trim())));
+                    i = close + ticks;
+                    continue;
+                }
+                // An unmatched run is literal.
+                out.push_str(&"`".repeat(ticks));
+                i += ticks;
+                continue;
+            }
+
+            if c == '[' {
+                if let Some((label, url, end)) = link(&chars, i) {
+                    out.push_str(&format!(
+                        "<a href=\"{}\">{}</a>",
+                        html_escape(&url),
+                        self.inline(&label)
+                    ));
+                    i = end;
+                    continue;
+                }
+            }
+
+            if c == '<' {
+                if let Some(end) = chars[i..].iter().position(|&c| c == '>') {
+                    let url: String = chars[i + 1..i + end].iter().collect();
+                    if is_url(&url) && !url.contains(char::is_whitespace) {
+                        out.push_str(&anchor(&url));
+                        i += end + 1;
+                        continue;
+                    }
+                }
+            }
+
+            if (c == 'h' || c == 'H') && (i == 0 || !chars[i - 1].is_alphanumeric()) {
+                let rest: String = chars[i..].iter().take(8).collect();
+                if is_url(&rest) {
+                    let len = bare_url_length(&chars[i..]);
+                    let url: String = chars[i..i + len].iter().collect();
+                    out.push_str(&anchor(&url));
+                    i += len;
+                    continue;
+                }
+            }
+
+            if c == '@' && (i == 0 || !chars[i - 1].is_alphanumeric()) {
+                if let Some((len, mention)) = self.mention_at(&chars[i + 1..]) {
+                    out.push_str(&format!(
+                        "<span itemscope=\"\" itemtype=\"{}\" itemid=\"{}\">{}</span>",
+                        MENTION_ITEMTYPE,
+                        self.used.len(),
+                        html_escape(&mention.display_name)
+                    ));
+                    self.used.push(mention.clone());
+                    i += 1 + len;
+                    continue;
+                }
+            }
+
+            for (marker, tag) in EMPHASIS {
+                if let Some(close) = emphasis_close(&chars, i, marker) {
+                    let inner: String = chars[i + marker.len()..close].iter().collect();
+                    let inner = self.inline(&inner);
+                    out.push_str(&format!("<{}>{}</{}>", tag, inner, tag));
+                    i = close + marker.len();
+                    continue 'outer;
+                }
+            }
+
+            out.push_str(&html_escape(&c.to_string()));
+            i += 1;
+        }
+        out
+    }
+
+    /// The mention whose name starts `rest` (the text after an `@`), and
+    /// the name's length in characters.
+    fn mention_at(&self, rest: &[char]) -> Option<(usize, &'_ Mention)> {
+        self.mentions.iter().find_map(|(name, mention)| {
+            let len = name.len();
+            let matches = rest.len() >= len
+                && rest[..len]
+                    .iter()
+                    .zip(name)
+                    .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+                && !rest.get(len).is_some_and(|c| c.is_alphanumeric());
+            matches.then_some((len, *mention))
+        })
+    }
+}
+
+/// The fence a line opens, if any.
+fn fence(trimmed: &str) -> Option<&'static str> {
+    ["```", "~~~"].into_iter().find(|f| trimmed.starts_with(f))
+}
+
+/// Indentation, orderedness and content of a list item line.
+fn list_item(line: &str) -> Option<(usize, bool, &str)> {
+    let rest = line.trim_start_matches(' ');
+    let indent = line.len() - rest.len();
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(content) = rest.strip_prefix(bullet) {
+            return Some((indent, false, content));
+        }
+    }
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if (1..=9).contains(&digits) {
+        let after = &rest[digits..];
+        if let Some(content) = after
+            .strip_prefix(". ")
+            .or_else(|| after.strip_prefix(") "))
+        {
+            return Some((indent, true, content));
+        }
+    }
+    None
+}
+
+/// Emphasis markers and their tags, longest first.
+const EMPHASIS: [(&str, &str); 6] = [
+    ("**", "b"),
+    ("__", "b"),
+    ("++", "u"),
+    ("~~", "s"),
+    ("*", "i"),
+    ("_", "i"),
+];
+
+fn is_url(text: &str) -> bool {
+    let lower = text.to_ascii_lowercase();
+    lower.starts_with("https://") || lower.starts_with("http://")
+}
+
+fn anchor(url: &str) -> String {
+    let url = html_escape(url);
+    format!("<a href=\"{}\">{}</a>", url, url)
+}
+
+/// Length of a bare URL, leaving trailing sentence punctuation outside.
+fn bare_url_length(chars: &[char]) -> usize {
+    let mut len = chars
+        .iter()
+        .position(|c| c.is_whitespace() || *c == '<')
+        .unwrap_or(chars.len());
+    while len > 0
+        && matches!(
+            chars[len - 1],
+            '.' | ',' | ';' | ':' | '!' | '?' | ')' | '\'' | '"'
+        )
+    {
+        // Keep a closing paren that balances one inside the URL.
+        if chars[len - 1] == ')'
+            && chars[..len].iter().filter(|&&c| c == '(').count()
+                >= chars[..len].iter().filter(|&&c| c == ')').count()
+        {
+            break;
+        }
+        len -= 1;
+    }
+    len
+}
+
+fn run_length(chars: &[char], start: usize, c: char) -> usize {
+    chars[start..].iter().take_while(|&&x| x == c).count()
+}
+
+/// Start of the next run of exactly `len` `c`s at or after `from`.
+fn find_run(chars: &[char], from: usize, c: char, len: usize) -> Option<usize> {
+    let mut j = from;
+    while j < chars.len() {
+        if chars[j] == c {
+            let run = run_length(chars, j, c);
+            if run == len {
+                return Some(j);
+            }
+            j += run;
+        } else {
+            j += 1;
+        }
+    }
+    None
+}
+
+/// `[label](url)` starting at `start`: the label, the URL and the index
+/// after the closing paren.
+fn link(chars: &[char], start: usize) -> Option<(String, String, usize)> {
+    let mut depth = 0;
+    let mut j = start;
+    let label_end = loop {
+        match chars.get(j)? {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break j;
+                }
+            }
+            _ => {}
+        }
+        j += 1;
+    };
+    if chars.get(label_end + 1) != Some(&'(') {
+        return None;
+    }
+    let url_end = label_end + 2 + chars[label_end + 2..].iter().position(|&c| c == ')')?;
+    let url: String = chars[label_end + 2..url_end].iter().collect();
+    let url = url.trim();
+    if url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    let label: String = chars[start + 1..label_end].iter().collect();
+    Some((label, url.to_string(), url_end + 1))
+}
+
+/// Index of the marker closing emphasis opened by `marker` at `start`.
+///
+/// Openers must be followed and closers preceded by non-space text, and a
+/// single-character marker never matches half of a doubled one. Underscores
+/// only count at word boundaries so `snake_case_names` stay literal.
+fn emphasis_close(chars: &[char], start: usize, marker: &str) -> Option<usize> {
+    let m: Vec<char> = marker.chars().collect();
+    let len = m.len();
+    let at = |j: usize| chars.get(j..j + len) == Some(&m[..]);
+    let single = len == 1;
+    let underscore = m[0] == '_';
+
+    if !at(start) || (single && chars.get(start + 1) == Some(&m[0])) {
+        return None;
+    }
+    if underscore && start > 0 && chars[start - 1].is_alphanumeric() {
+        return None;
+    }
+    if chars.get(start + len).is_none_or(|c| c.is_whitespace()) {
+        return None;
+    }
+
+    let mut j = start + len + 1;
+    while j + len <= chars.len() {
+        let doubled = single && (chars[j - 1] == m[0] || chars.get(j + 1) == Some(&m[0]));
+        if at(j)
+            && !doubled
+            && !chars[j - 1].is_whitespace()
+            && chars[j - 1] != '\\'
+            && !(underscore && chars.get(j + len).is_some_and(|c| c.is_alphanumeric()))
+        {
+            return Some(j);
+        }
+        // Skip over inline code so markers inside it are not matched.
+        if chars[j] == '`' {
+            let ticks = run_length(chars, j, '`');
+            if let Some(close) = find_run(chars, j + ticks, '`', ticks) {
+                j = close + ticks;
+                continue;
+            }
+        }
+        j += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::richtext::{RichText, TextFlavor};
+
+    fn md(text: &str) -> String {
+        markdown_to_html(text, &[]).0
+    }
+
+    #[test]
+    fn inline_formatting() {
+        assert_eq!(
+            md("**bold** _it_ *it* ++u++ ~~s~~ `a<b`"),
+            "<p><b>bold</b> <i>it</i> <i>it</i> <u>u</u> <s>s</s> <code>a&lt;b</code></p>"
+        );
+        assert_eq!(
+            md("*a **b** c* and snake_case_name, 2 * 3 * 4 \\*x\\*"),
+            "<p><i>a <b>b</b> c</i> and snake_case_name, 2 * 3 * 4 *x*</p>"
+        );
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(
+            md("[docs](https://x.test/a?b=1&c=2) <https://y.test> see https://z.test/p."),
+            "<p><a href=\"https://x.test/a?b=1&amp;c=2\">docs</a> \
+             <a href=\"https://y.test\">https://y.test</a> \
+             see <a href=\"https://z.test/p\">https://z.test/p</a>.</p>"
+        );
+    }
+
+    #[test]
+    fn paragraphs_and_line_breaks() {
+        assert_eq!(
+            md("at Foo.bar(x.rs:1)\nat <main>\n\nnext"),
+            "<p>at Foo.bar(x.rs:1)<br>at &lt;main&gt;</p><p>next</p>"
+        );
+    }
+
+    #[test]
+    fn fenced_code_is_verbatim() {
+        assert_eq!(
+            md("```rust\nfn main() {\n    **x** < 1\n}\n```\nafter"),
+            "<codeblock class=\"rust\"><code>fn main() {\n    **x** &lt; 1\n}</code></codeblock><p>after</p>"
+        );
+    }
+
+    #[test]
+    fn lists_and_quotes() {
+        assert_eq!(
+            md("- one\n  - nested\n- two\n1. first\n\n> quoted\n> **text**"),
+            "<ul><li>one<ul><li>nested</li></ul></li><li>two</li></ul>\
+             <ol><li>first</li></ol>\
+             <blockquote><p>quoted<br><b>text</b></p></blockquote>"
+        );
+    }
+
+    #[test]
+    fn round_trips_through_rich_text() {
+        let text = "**bold** _it_ ~~s~~ `code`\n\n- a\n- b\n\n> quote\n\n```\nx = 1\n```";
+        let rich = RichText::from_html(&md(text));
+        assert_eq!(rich.render(TextFlavor::Markdown), text);
+    }
+
+    #[test]
+    fn mentions_become_spans() {
+        let mention = |mri: &str, name: &str| Mention {
+            mri: mri.to_string(),
+            display_name: name.to_string(),
+        };
+        let known = [
+            mention("8:orgid:b", "Bob"),
+            mention("8:orgid:s", "Bob Stone"),
+        ];
+        let (html, used) = markdown_to_html("hi @bob stone and **@Bob**, not me@bob.test", &known);
+        assert_eq!(
+            html,
+            "<p>hi <span itemscope=\"\" itemtype=\"http://schema.skype.com/Mention\" itemid=\"0\">Bob Stone</span> \
+             and <b><span itemscope=\"\" itemtype=\"http://schema.skype.com/Mention\" itemid=\"1\">Bob</span></b>, \
+             not me@bob.test</p>"
+        );
+        assert_eq!(used, [known[1].clone(), known[0].clone()]);
+    }
+}
@@ -1,9 +1,10 @@
 //! User profile endpoint (/me)
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use super::client::TeamsClient;
+use crate::output::{self, OutputFormat};
 
 #[derive(Debug, Deserialize)]
 struct MeResponse {
@@ -16,16 +17,16 @@ struct MeResponse {
 }
 
 /// Fetch and display current user info from Graph /me endpoint (prints to stdout).
-pub async fn whoami() -> Result<()> {
+pub async fn whoami(format: OutputFormat) -> Result<()> {
     let client = TeamsClient::new().await?;
     let info = whoami_data(&client).await?;
 
-    println!();
-    println!("Display Name: {}", info.display_name);
-    println!("Mail:         {}", info.mail.as_deref().unwrap_or("(none)"));
-    println!("ID:           {}", info.id);
-
-    Ok(())
+    output::print_one(format, &info, |info| {
+        println!();
+        println!("Display Name: {}", info.display_name);
+        println!("Mail:         {}", info.mail.as_deref().unwrap_or("(none)"));
+        println!("ID:           {}", info.id);
+    })
 }
 
 // ---------------------------------------------------------------------------
@@ -33,7 +34,7 @@ pub async fn whoami() -> Result<()> {
 // ---------------------------------------------------------------------------
 
 /// User info for TUI display.
-#[allow(dead_code)]
+#[derive(Serialize)]
 pub struct UserInfo {
     pub display_name: String,
     pub mail: Option<String>,
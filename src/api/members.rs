@@ -0,0 +1,136 @@
+//! Conversation members (Microsoft Graph)
+//!
+//! Chats list their members under `/chats/{id}/members`; channels under
+//! `/teams/{team}/channels/{id}/members`, once the owning team is known.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::chat::user_mri;
+use super::client::{TeamsClient, GRAPH_BASE};
+use super::teams;
+
+#[derive(Debug, Deserialize)]
+struct MembersResponse {
+    value: Vec<ConversationMember>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct ConversationMember {
+    pub(super) display_name: Option<String>,
+    /// Entra ID (AAD) object ID; absent for some guests and bots.
+    pub(super) user_id: Option<String>,
+    pub(super) email: Option<String>,
+    /// Home tenant of `user_id`; differs from ours for federated users.
+    pub(super) tenant_id: Option<String>,
+    /// `owner` and/or `guest`; empty for ordinary members.
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+/// What a member may do in a conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemberRole {
+    /// Can add and remove people and change the topic.
+    Owner,
+    Member,
+    /// From outside the organisation.
+    Guest,
+}
+
+impl MemberRole {
+    fn from_roles(roles: &[String]) -> Self {
+        let has = |role: &str| roles.iter().any(|r| r.eq_ignore_ascii_case(role));
+        if has("owner") {
+            MemberRole::Owner
+        } else if has("guest") {
+            MemberRole::Guest
+        } else {
+            MemberRole::Member
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MemberRole::Owner => "owner",
+            MemberRole::Member => "member",
+            MemberRole::Guest => "guest",
+        }
+    }
+}
+
+/// A member of a chat or channel.
+#[derive(Debug, Clone, Serialize)]
+pub struct MemberInfo {
+    /// User MRI (`8:orgid:<aad-id>`).
+    pub mri: String,
+    pub display_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    pub role: MemberRole,
+}
+
+// ---------------------------------------------------------------------------
+// Data-returning API functions for TUI integration
+// ---------------------------------------------------------------------------
+
+/// List the members of a chat or channel, sorted by display name.
+pub async fn list_members_with_client(
+    client: &TeamsClient,
+    conversation_id: &str,
+) -> Result<Vec<MemberInfo>> {
+    let mut members: Vec<MemberInfo> = fetch_members(client, conversation_id)
+        .await?
+        .into_iter()
+        .filter_map(|m| {
+            let aad_id = m.user_id?;
+            Some(MemberInfo {
+                mri: user_mri(&aad_id),
+                display_name: m.display_name.unwrap_or_else(|| aad_id.clone()),
+                email: m.email.filter(|e| !e.is_empty()),
+                role: MemberRole::from_roles(&m.roles),
+            })
+        })
+        .collect();
+    members.sort_by_key(|m| m.display_name.to_lowercase());
+    Ok(members)
+}
+
+/// Graph's member entries for a chat or channel, including those without
+/// an MRI of their own.
+pub(super) async fn fetch_members(
+    client: &TeamsClient,
+    conversation_id: &str,
+) -> Result<Vec<ConversationMember>> {
+    let path = if teams::is_channel_id(conversation_id) {
+        let team_id = teams::team_of_channel(client, conversation_id).await?;
+        format!("/teams/{}/channels/{}/members", team_id, conversation_id)
+    } else {
+        format!("/chats/{}/members", conversation_id)
+    };
+
+    let mut members = Vec::new();
+    let mut next = Some(path);
+    while let Some(path) = next.take() {
+        tracing::debug!("Fetching members: {}", path);
+        let resp = client.graph_get(&path).await?;
+        let body: MembersResponse = resp
+            .json()
+            .await
+            .context("Failed to parse members response")?;
+        members.extend(body.value);
+        // Large chats and channels are split across pages.
+        next = body
+            .next_link
+            .map(|link| match link.strip_prefix(GRAPH_BASE) {
+                Some(path) => Ok(path.to_string()),
+                None => Err(anyhow::anyhow!("Unexpected members page link: {}", link)),
+            })
+            .transpose()?;
+    }
+    Ok(members)
+}
@@ -0,0 +1,187 @@
+//! @mentions
+//!
+//! A mention is a `<span itemtype="http://schema.skype.com/Mention"
+//! itemid="N">` in the message HTML plus entry `N` of the `properties.mentions`
+//! array (sent as a JSON string), which carries the mentioned user's MRI.
+//! Outgoing text mentions people as `@Display Name`.
+
+use serde::{Deserialize, Serialize};
+
+use super::client::TeamsClient;
+use super::members::{self, MemberInfo};
+
+/// `itemtype` of mention spans and entries.
+pub(super) const MENTION_ITEMTYPE: &str = "http://schema.skype.com/Mention";
+
+/// A person mentioned in a message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Mention {
+    /// User MRI (`8:orgid:<aad-id>`).
+    pub mri: String,
+    /// Text of the mention, without the `@`.
+    pub display_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMention {
+    mri: Option<String>,
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+}
+
+/// Parse `properties.mentions`, which arrives either as a JSON array or as
+/// a string containing one. Each person is listed once.
+pub(super) fn parse_mentions(value: &serde_json::Value) -> Vec<Mention> {
+    let raw: Vec<RawMention> = match value {
+        serde_json::Value::String(s) => serde_json::from_str(s).unwrap_or_default(),
+        other => serde_json::from_value(other.clone()).unwrap_or_default(),
+    };
+    let mut mentions: Vec<Mention> = Vec::new();
+    for m in raw {
+        let Some(mri) = m.mri.filter(|mri| !mri.is_empty()) else {
+            continue;
+        };
+        if mentions.iter().all(|existing| existing.mri != mri) {
+            mentions.push(Mention {
+                display_name: m.display_name.unwrap_or_default(),
+                mri,
+            });
+        }
+    }
+    mentions
+}
+
+/// `properties.mentions` value for mentions in `itemid` order.
+pub(super) fn mentions_property(mentions: &[Mention]) -> serde_json::Value {
+    let entries: Vec<serde_json::Value> = mentions
+        .iter()
+        .enumerate()
+        .map(|(itemid, m)| {
+            serde_json::json!({
+                "@type": MENTION_ITEMTYPE,
+                "itemid": itemid,
+                "mri": m.mri,
+                "mentionType": "person",
+                "displayName": m.display_name,
+            })
+        })
+        .collect();
+    serde_json::Value::String(serde_json::Value::Array(entries).to_string())
+}
+
+/// Members mentioned in `text` as `@Display Name`, or as `@First` when only
+/// one member has that first name. Matching ignores case.
+pub fn find_mentions(members: &[MemberInfo], text: &str) -> Vec<Mention> {
+    let text_lower = text.to_lowercase();
+    let first_name = |m: &MemberInfo| m.display_name.split_whitespace().next().map(str::to_string);
+
+    let mut found: Vec<Mention> = Vec::new();
+    for member in members {
+        let mut names = vec![member.display_name.clone()];
+        if let Some(first) = first_name(member) {
+            let shared = members
+                .iter()
+                .filter(|m| first_name(m).is_some_and(|f| f.eq_ignore_ascii_case(&first)))
+                .count();
+            if shared == 1 && first != member.display_name {
+                names.push(first);
+            }
+        }
+        for name in names {
+            if mentions_name(&text_lower, &name.to_lowercase())
+                && found.iter().all(|f| f.mri != member.mri)
+            {
+                found.push(Mention {
+                    mri: member.mri.clone(),
+                    display_name: name,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Whether `text` contains `@name` at word boundaries (both lowercase).
+fn mentions_name(text: &str, name: &str) -> bool {
+    let needle = format!("@{}", name);
+    text.match_indices(&needle).any(|(i, _)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + needle.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// Look up the people `text` mentions among the conversation's members.
+/// Lookup failures only lose the mentions, never the message.
+pub(super) async fn resolve_mentions(
+    client: &TeamsClient,
+    conversation_id: &str,
+    text: &str,
+) -> Vec<Mention> {
+    if !text.contains('@') {
+        return Vec::new();
+    }
+    match members::list_members_with_client(client, conversation_id).await {
+        Ok(members) => find_mentions(&members, text),
+        Err(e) => {
+            tracing::warn!("Could not look up members for mentions: {:#}", e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::MemberRole;
+
+    fn member(mri: &str, name: &str) -> MemberInfo {
+        MemberInfo {
+            mri: mri.to_string(),
+            display_name: name.to_string(),
+            email: None,
+            role: MemberRole::Member,
+        }
+    }
+
+    #[test]
+    fn finds_full_and_unique_first_names() {
+        let members = [
+            member("8:orgid:a", "Alice Smith"),
+            member("8:orgid:b", "Bob Jones"),
+            member("8:orgid:c", "Bob Stone"),
+        ];
+        let found = find_mentions(
+            &members,
+            "@alice can you and @bob stone look? mail bob@x.test",
+        );
+        assert_eq!(
+            found,
+            [
+                Mention {
+                    mri: "8:orgid:a".to_string(),
+                    display_name: "Alice".to_string(),
+                },
+                Mention {
+                    mri: "8:orgid:c".to_string(),
+                    display_name: "Bob Stone".to_string(),
+                },
+            ]
+        );
+        assert!(find_mentions(&members, "@bob").is_empty());
+    }
+
+    #[test]
+    fn parses_stringified_mentions() {
+        let value = serde_json::json!(
+            "[{\"@type\":\"http://schema.skype.com/Mention\",\"itemid\":\"0\",\"mri\":\"8:orgid:a\",\"displayName\":\"Alice\"},\
+             {\"itemid\":\"1\",\"mri\":\"8:orgid:a\",\"displayName\":\"Smith\"}]"
+        );
+        let mentions = parse_mentions(&value);
+        assert_eq!(mentions.len(), 1);
+        assert_eq!(mentions[0].mri, "8:orgid:a");
+
+        let round_trip = parse_mentions(&mentions_property(&mentions));
+        assert_eq!(round_trip, mentions);
+    }
+}
@@ -1,19 +1,44 @@
 //! API client module for Microsoft Teams
 
+mod attachments;
 mod chat;
 pub mod client;
 mod graph;
+mod markdown;
 mod me;
+mod members;
+mod mentions;
+mod names;
+mod people;
 mod presence;
+mod reactions;
+mod resolve;
+mod richtext;
+mod search;
 mod teams;
+mod threads;
+mod unread;
+mod upload;
 
 use anyhow::Result;
+use std::path::Path;
+
+use crate::output::OutputFormat;
 
 // Re-export data types for TUI integration
-pub use chat::{ChatInfo, MessageInfo};
+pub use attachments::{AttachmentInfo, AttachmentKind};
+pub use chat::{ChatInfo, HistoryRange, MessageInfo, MessagePage, NativeMessage, QuotedMessage};
 pub use me::UserInfo;
+pub use members::{MemberInfo, MemberRole};
+pub use mentions::Mention;
+pub use people::PersonInfo;
 pub use presence::PresenceInfo;
+pub use reactions::{reaction_label, ReactionInfo, REACTIONS};
+pub use resolve::AliasInfo;
+pub use richtext::{Block, RichText, Span as RichSpan, SpanStyle, TextFlavor};
+pub use search::SearchHit;
 pub use teams::TeamInfo;
+pub use unread::UnreadInfo;
 
 // Re-export ChannelInfo for use in TUI sidebar (currently consumed
 // only through TeamInfo.channels, but kept public for future callers).
@@ -21,29 +46,117 @@ pub use teams::TeamInfo;
 pub use teams::ChannelInfo;
 
 // Re-export data-returning functions for TUI integration
-pub use chat::{list_chats_data, read_messages_data, send_message_with_client};
+pub use attachments::{
+    default_download_dir, download_attachment_with_client, image_preview_with_client,
+};
+pub use chat::{
+    delete_message_with_client, edit_message_with_client, fetch_native_page, list_chats_data,
+    message_info, messages_url, read_messages_page, read_older_messages,
+    reply_to_channel_message_with_client, send_message_with_client, split_thread_id, user_mri,
+};
 pub use me::whoami_data;
-pub use presence::get_presence_data;
+pub use members::list_members_with_client;
+pub use mentions::find_mentions;
+pub use people::search_people_with_client;
+pub use presence::{get_presence_data, get_presences_with_client};
+pub use reactions::set_reaction_with_client;
+pub use search::{merge_hits, search_messages_data};
 pub use teams::list_teams_data;
+pub use threads::{
+    add_members_with_client, create_chat_with_client, leave_chat_with_client,
+    remove_member_with_client,
+};
+pub use unread::{list_unread_data, mark_read_with_client, message_order};
+pub use upload::send_file_with_client;
 
 /// List recent chats (native Teams API)
-pub async fn list_chats(limit: usize) -> Result<()> {
-    chat::list_chats(limit).await
+pub async fn list_chats(limit: usize, format: OutputFormat) -> Result<()> {
+    chat::list_chats(limit, format).await
 }
 
 /// Read messages from a chat (native Teams API)
-pub async fn read_messages(chat_id: &str, limit: usize) -> Result<()> {
-    chat::read_messages(chat_id, limit).await
+pub async fn read_messages(chat_id: &str, range: HistoryRange, format: OutputFormat) -> Result<()> {
+    chat::read_messages(chat_id, range, format).await
+}
+
+/// Send a message to a chat, optionally quoting a message (native Teams API)
+pub async fn send_message(to: &str, message: &str, reply_to: Option<&str>) -> Result<()> {
+    chat::send_message(to, message, reply_to).await
+}
+
+/// Edit one of our messages (native Teams API)
+pub async fn edit_message(chat_id: &str, message_id: &str, message: &str) -> Result<()> {
+    chat::edit_message(chat_id, message_id, message).await
+}
+
+/// Delete one of our messages (native Teams API)
+pub async fn delete_message(chat_id: &str, message_id: &str) -> Result<()> {
+    chat::delete_message(chat_id, message_id).await
+}
+
+/// Add or remove a reaction on a message (native Teams API)
+pub async fn react(chat_id: &str, message_id: &str, emoji: &str, remove: bool) -> Result<()> {
+    reactions::react(chat_id, message_id, emoji, remove).await
+}
+
+/// Download a message's attachments (SharePoint/OneDrive files and AMS images)
+pub async fn download(chat_id: &str, message_id: &str, out: Option<&Path>) -> Result<()> {
+    attachments::download(chat_id, message_id, out).await
+}
+
+/// Upload a file and send it to a chat (AMS for images, OneDrive otherwise)
+pub async fn send_file(to: &str, path: &Path, message: Option<&str>) -> Result<()> {
+    upload::send_file(to, path, message).await
+}
+
+/// Post to a team channel, or reply to a channel thread (native Teams API)
+pub async fn send_channel_message(
+    channel: &str,
+    message: &str,
+    subject: Option<&str>,
+    reply_to: Option<&str>,
+) -> Result<()> {
+    chat::send_channel_message(channel, message, subject, reply_to).await
+}
+
+/// Start a 1:1 or group chat with people by email address (native Teams API)
+pub async fn new_chat(emails: &[String], topic: Option<&str>) -> Result<()> {
+    threads::new_chat(emails, topic).await
+}
+
+/// List a chat's members and their roles
+pub async fn chat_members(chat_id: &str, format: OutputFormat) -> Result<()> {
+    threads::chat_members(chat_id, format).await
+}
+
+/// Add people to a group chat by email address (native Teams API)
+pub async fn add_chat_members(chat_id: &str, emails: &[String]) -> Result<()> {
+    threads::add_chat_members(chat_id, emails).await
 }
 
-/// Send a message to a chat (native Teams API)
-pub async fn send_message(to: &str, message: &str) -> Result<()> {
-    chat::send_message(to, message).await
+/// Remove people from a group chat by email address (native Teams API)
+pub async fn remove_chat_members(chat_id: &str, emails: &[String]) -> Result<()> {
+    threads::remove_chat_members(chat_id, emails).await
+}
+
+/// Change a group chat's topic (native Teams API)
+pub async fn rename_chat(chat_id: &str, topic: &str) -> Result<()> {
+    threads::rename_chat(chat_id, topic).await
+}
+
+/// Leave a group chat (native Teams API)
+pub async fn leave_chat(chat_id: &str) -> Result<()> {
+    threads::leave_chat(chat_id).await
 }
 
 /// Get current presence status
-pub async fn get_presence() -> Result<()> {
-    presence::get_presence().await
+pub async fn get_presence(format: OutputFormat) -> Result<()> {
+    presence::get_presence(format).await
+}
+
+/// Get another user's presence by email address
+pub async fn get_user_presence(email: &str, format: OutputFormat) -> Result<()> {
+    presence::get_user_presence(email, format).await
 }
 
 /// Set presence status
@@ -51,12 +164,54 @@ pub async fn set_presence(status: &str) -> Result<()> {
     presence::set_presence(status).await
 }
 
+/// Search cached and server-side message history
+pub async fn search_messages(
+    query: &crate::cache::search::SearchQuery,
+    limit: usize,
+    local_only: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    search::search_messages(query, limit, local_only, format).await
+}
+
 /// Show current user info
-pub async fn whoami() -> Result<()> {
-    me::whoami().await
+pub async fn whoami(format: OutputFormat) -> Result<()> {
+    me::whoami(format).await
+}
+
+/// List conversations with unread messages (native Teams API)
+pub async fn list_unread(limit: usize, format: OutputFormat) -> Result<()> {
+    unread::list_unread(limit, format).await
 }
 
 /// List joined teams and their channels
-pub async fn list_teams() -> Result<()> {
-    teams::list_teams().await
+pub async fn list_teams(format: OutputFormat) -> Result<()> {
+    teams::list_teams(format).await
+}
+
+/// Resolve a thread ID, alias, email address, `team/channel` or chat name
+/// to a thread ID
+pub async fn resolve_conversation(target: &str) -> Result<String> {
+    resolve::resolve_conversation(target).await
+}
+
+/// Resolve a conversation like [`resolve_conversation`], starting a 1:1
+/// chat for an email address we have no chat with yet
+pub async fn resolve_or_start_conversation(target: &str) -> Result<String> {
+    resolve::resolve_or_start_conversation(target).await
+}
+
+/// Save an alias for a conversation
+pub async fn set_alias(name: &str, target: &str) -> Result<()> {
+    resolve::set_alias(name, target).await
+}
+
+/// Delete a conversation alias
+pub async fn remove_alias(name: &str) -> Result<()> {
+    resolve::remove_alias(name).await
+}
+
+/// List conversation aliases
+pub async fn list_aliases(format: OutputFormat) -> Result<()> {
+    resolve::list_aliases(format).await
 }
@@ -0,0 +1,227 @@
+//! Chat names for conversations without a topic
+//!
+//! Untitled chats are named after the other people in them: the other
+//! participant of a 1:1, or a comma-joined list for a group. Participant
+//! MRIs come from `threadProperties.members` (or, for 1:1 chats, the thread
+//! ID itself) and are resolved to display names through Graph, with the
+//! results kept in the persistent [`NameCache`].
+
+use std::collections::{HashMap, HashSet};
+
+use super::chat::{user_mri, Conversation};
+use super::client::TeamsClient;
+use super::me;
+use super::people;
+use crate::cache::names::NameCache;
+
+/// Prefix of the MRIs of users in our directory.
+const ORGID_PREFIX: &str = "8:orgid:";
+
+/// Members named in a group chat's title; the rest become "+N".
+const GROUP_NAME_MEMBERS: usize = 4;
+
+/// Display names for the participants of a set of conversations.
+pub(super) struct ChatNames {
+    own_mri: Option<String>,
+    names: HashMap<String, String>,
+}
+
+impl ChatNames {
+    /// Resolve the participants of every untitled conversation in `convs`.
+    ///
+    /// Lookups go to the name cache first, then to Graph for the rest.
+    /// Failures are logged: an unresolved person only costs a worse name.
+    pub(super) async fn resolve(client: &TeamsClient, convs: &[Conversation]) -> Self {
+        let own_mri = match me::whoami_data(client).await {
+            Ok(me) => Some(user_mri(&me.id)),
+            Err(e) => {
+                tracing::warn!("Could not identify ourselves for chat names: {:#}", e);
+                None
+            }
+        };
+        let mut cache = match NameCache::open() {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                tracing::warn!("Name cache unavailable: {:#}", e);
+                None
+            }
+        };
+
+        let mut wanted = HashSet::new();
+        for conv in convs.iter().filter(|c| topic(c).is_none()) {
+            wanted.extend(participant_mris(conv));
+        }
+        if let Some(ref own) = own_mri {
+            wanted.remove(own);
+        }
+
+        let mut names = HashMap::new();
+        let mut missing = Vec::new();
+        for mri in wanted {
+            match cache.as_ref().and_then(|c| c.get(&mri)) {
+                Some(name) => {
+                    names.insert(mri, name.to_string());
+                }
+                None => missing.push(mri),
+            }
+        }
+
+        let ids: Vec<&str> = missing
+            .iter()
+            .filter_map(|mri| mri.strip_prefix(ORGID_PREFIX))
+            .collect();
+        if !ids.is_empty() {
+            tracing::debug!("Looking up {} chat members", ids.len());
+            match people::lookup_users_with_client(client, &ids).await {
+                Ok(found) => {
+                    for person in found {
+                        if let Some(ref mut cache) = cache {
+                            cache.insert(person.mri(), person.display_name.clone());
+                        }
+                        names.insert(person.mri(), person.display_name);
+                    }
+                    if let Some(Err(e)) = cache.as_mut().map(NameCache::save) {
+                        tracing::warn!("Failed to save name cache: {:#}", e);
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to look up chat members: {:#}", e),
+            }
+        }
+
+        Self { own_mri, names }
+    }
+
+    /// Display name for a conversation: its topic, else the people in it,
+    /// else the last sender or the raw thread ID.
+    pub(super) fn conversation_name(&self, conv: &Conversation) -> String {
+        if let Some(topic) = topic(conv) {
+            return topic.to_string();
+        }
+
+        let others: Vec<String> = participant_mris(conv)
+            .into_iter()
+            .filter(|mri| Some(mri) != self.own_mri.as_ref())
+            .collect();
+        let known: Vec<&str> = others
+            .iter()
+            .filter_map(|mri| self.names.get(mri).map(String::as_str))
+            .collect();
+        if !known.is_empty() {
+            let mut name = known[..known.len().min(GROUP_NAME_MEMBERS)].join(", ");
+            let rest = others.len() - known.len().min(GROUP_NAME_MEMBERS);
+            if rest > 0 {
+                name.push_str(&format!(" +{}", rest));
+            }
+            return name;
+        }
+
+        // Fall back to the last sender (unless that was us) or the thread ID.
+        if let Some(ref msg) = conv.last_message {
+            let from_us = self.own_mri.is_some() && msg.sender_mri() == self.own_mri.as_deref();
+            let name = msg.im_display_name.as_deref().filter(|n| !n.is_empty());
+            if let (Some(name), false) = (name, from_us) {
+                return name.to_string();
+            }
+        }
+        conv.id.as_deref().unwrap_or("[unknown]").to_string()
+    }
+
+    /// MRI of the other person in a 1:1 chat.
+    pub(super) fn partner(&self, conv: &Conversation) -> Option<String> {
+        if !conv.id.as_deref()?.ends_with("@unq.gbl.spaces") {
+            return None;
+        }
+        let mut others = participant_mris(conv)
+            .into_iter()
+            .filter(|mri| Some(mri) != self.own_mri.as_ref());
+        let partner = others.next()?;
+        others.next().is_none().then_some(partner)
+    }
+}
+
+/// Non-empty topic of a conversation.
+fn topic(conv: &Conversation) -> Option<&str> {
+    conv.thread_properties
+        .as_ref()
+        .and_then(|p| p.topic.as_deref())
+        .filter(|t| !t.trim().is_empty())
+}
+
+/// MRIs of a conversation's participants, from `threadProperties.members`
+/// (a JSON array in a string, or a comma-separated list) or, failing that,
+/// the two AAD IDs in a 1:1 thread ID (`19:<a>_<b>@unq.gbl.spaces`).
+fn participant_mris(conv: &Conversation) -> Vec<String> {
+    let members = conv
+        .thread_properties
+        .as_ref()
+        .and_then(|p| p.members.as_deref())
+        .unwrap_or("");
+    let mris: Vec<String> = serde_json::from_str::<Vec<String>>(members).unwrap_or_else(|_| {
+        members
+            .split(',')
+            .map(|m| m.trim_matches(|c: char| c.is_whitespace() || "[]\"".contains(c)))
+            .map(String::from)
+            .collect()
+    });
+    let mris: Vec<String> = mris.into_iter().filter(|m| m.starts_with("8:")).collect();
+    if !mris.is_empty() {
+        return mris;
+    }
+
+    conv.id
+        .as_deref()
+        .and_then(|id| id.strip_prefix("19:")?.strip_suffix("@unq.gbl.spaces"))
+        .map(|ids| ids.split('_').map(user_mri).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation(value: serde_json::Value) -> Conversation {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn names_untitled_chats_after_the_other_people() {
+        let names = ChatNames {
+            own_mri: Some("8:orgid:me".to_string()),
+            names: [("bob", "Bob"), ("carol", "Carol"), ("me", "Me")]
+                .into_iter()
+                .map(|(id, name)| (user_mri(id), name.to_string()))
+                .collect(),
+        };
+
+        let one_to_one = conversation(serde_json::json!({
+            "id": "19:me_bob@unq.gbl.spaces",
+            "lastMessage": { "imdisplayname": "Me", "from": "https://x/contacts/8:orgid:me" },
+        }));
+        assert_eq!(names.conversation_name(&one_to_one), "Bob");
+        assert_eq!(names.partner(&one_to_one).as_deref(), Some("8:orgid:bob"));
+
+        let group = conversation(serde_json::json!({
+            "id": "19:abc@thread.v2",
+            "threadProperties": {
+                "members": "[\"8:orgid:me\",\"8:orgid:bob\",\"8:orgid:carol\",\"8:orgid:dave\"]",
+            },
+        }));
+        assert_eq!(names.conversation_name(&group), "Bob, Carol +1");
+
+        let titled = conversation(serde_json::json!({
+            "id": "19:abc@thread.v2",
+            "threadProperties": { "topic": "Release", "members": "8:orgid:bob, 8:orgid:carol" },
+        }));
+        assert_eq!(names.conversation_name(&titled), "Release");
+        assert_eq!(participant_mris(&titled), ["8:orgid:bob", "8:orgid:carol"]);
+
+        let unknown = conversation(serde_json::json!({
+            "id": "19:me_zed@unq.gbl.spaces",
+            "lastMessage": { "imdisplayname": "Me", "from": "https://x/contacts/8:orgid:me" },
+        }));
+        assert_eq!(
+            names.conversation_name(&unknown),
+            "19:me_zed@unq.gbl.spaces"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! People lookup in the organisation directory (Microsoft Graph `/users`)
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::chat::user_mri;
+use super::client::TeamsClient;
+
+/// Fields requested for each user.
+const USER_FIELDS: &str = "id,displayName,mail,userPrincipalName";
+
+/// Graph accepts at most 15 values in an `in (...)` filter.
+const MAX_IDS_PER_FILTER: usize = 15;
+
+#[derive(Debug, Deserialize)]
+struct UsersResponse {
+    value: Vec<User>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct User {
+    id: String,
+    display_name: Option<String>,
+    mail: Option<String>,
+    user_principal_name: Option<String>,
+}
+
+/// A person in the directory.
+#[derive(Debug, Clone, Serialize)]
+pub struct PersonInfo {
+    /// Entra ID (AAD) object ID.
+    pub id: String,
+    pub display_name: String,
+    /// Email address, or the UPN when the user has no mailbox.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+impl PersonInfo {
+    /// Chat service MRI (`8:orgid:<aad-id>`).
+    pub fn mri(&self) -> String {
+        user_mri(&self.id)
+    }
+}
+
+impl From<User> for PersonInfo {
+    fn from(u: User) -> Self {
+        let email = u.mail.or(u.user_principal_name).filter(|e| !e.is_empty());
+        PersonInfo {
+            display_name: u
+                .display_name
+                .or_else(|| email.clone())
+                .unwrap_or_else(|| u.id.clone()),
+            email,
+            id: u.id,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Data-returning API functions for TUI integration
+// ---------------------------------------------------------------------------
+
+/// Find the user with the given email address or UPN.
+pub async fn find_user_with_client(client: &TeamsClient, email: &str) -> Result<PersonInfo> {
+    let email = email.trim();
+    let literal = odata_string(email);
+    let filter = format!("mail eq {} or userPrincipalName eq {}", literal, literal);
+    let mut users = query_users(client, &filter, 2).await?;
+    match users.len() {
+        0 => bail!("No user with address {}", email),
+        1 => Ok(users.remove(0)),
+        _ => bail!("Several users have address {}", email),
+    }
+}
+
+/// People whose name, email or UPN starts with `query`, at most `limit`.
+pub async fn search_people_with_client(
+    client: &TeamsClient,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<PersonInfo>> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let q = odata_string(query);
+    let filter = [
+        "displayName",
+        "givenName",
+        "surname",
+        "mail",
+        "userPrincipalName",
+    ]
+    .iter()
+    .map(|field| format!("startswith({},{})", field, q))
+    .collect::<Vec<_>>()
+    .join(" or ");
+    query_users(client, &filter, limit).await
+}
+
+/// Look up users by Entra ID (AAD) object ID. IDs Graph does not know are
+/// left out.
+pub async fn lookup_users_with_client(
+    client: &TeamsClient,
+    ids: &[&str],
+) -> Result<Vec<PersonInfo>> {
+    let mut people = Vec::new();
+    for batch in ids.chunks(MAX_IDS_PER_FILTER) {
+        let list: Vec<String> = batch.iter().map(|id| odata_string(id)).collect();
+        let filter = format!("id in ({})", list.join(","));
+        people.extend(query_users(client, &filter, batch.len()).await?);
+    }
+    Ok(people)
+}
+
+/// `/users` filtered by an OData expression.
+async fn query_users(client: &TeamsClient, filter: &str, top: usize) -> Result<Vec<PersonInfo>> {
+    let filter: String = url::form_urlencoded::byte_serialize(filter.as_bytes()).collect();
+    let path = format!(
+        "/users?$select={}&$top={}&$filter={}",
+        USER_FIELDS, top, filter
+    );
+    let resp = client.graph_get(&path).await?;
+    let users: UsersResponse = resp
+        .json()
+        .await
+        .context("Failed to parse users response")?;
+    Ok(users.value.into_iter().map(PersonInfo::from).collect())
+}
+
+/// Quote a string literal for an OData filter.
+fn odata_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_odata_literals() {
+        assert_eq!(odata_string("o'neil@x.test"), "'o''neil@x.test'");
+    }
+}
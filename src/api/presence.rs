@@ -1,26 +1,67 @@
 //! Presence API for Microsoft Teams
+//!
+//! Our own presence and other people's, through Graph. Live changes arrive
+//! over Trouter once subscribed (see [`crate::trouter::presence`]).
 
 use anyhow::{bail, Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use super::client::TeamsClient;
+use super::people;
+use crate::output::{self, OutputFormat};
+
+/// Graph accepts up to 650 user IDs per `getPresencesByUserId` call.
+const MAX_PRESENCE_IDS: usize = 650;
 
 #[derive(Debug, Deserialize)]
 struct PresenceResponse {
+    id: String,
     availability: String,
     activity: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct PresencesResponse {
+    value: Vec<PresenceResponse>,
+}
+
+impl From<PresenceResponse> for PresenceInfo {
+    fn from(p: PresenceResponse) -> Self {
+        PresenceInfo {
+            id: p.id,
+            availability: p.availability,
+            activity: p.activity,
+        }
+    }
+}
+
 /// Get current presence status (prints to stdout).
-pub async fn get_presence() -> Result<()> {
+pub async fn get_presence(format: OutputFormat) -> Result<()> {
     let client = TeamsClient::new().await?;
     let info = get_presence_data(&client).await?;
 
-    println!("\nPresence Status:");
-    println!("  Availability: {}", info.availability);
-    println!("  Activity: {}", info.activity);
+    output::print_one(format, &info, |info| {
+        println!("\nPresence Status:");
+        println!("  Availability: {}", info.availability);
+        println!("  Activity: {}", info.activity);
+    })
+}
 
-    Ok(())
+/// Get another user's presence by email address (prints to stdout).
+pub async fn get_user_presence(email: &str, format: OutputFormat) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    let person = people::find_user_with_client(&client, email).await?;
+    let info = get_presences_with_client(&client, &[person.id.as_str()])
+        .await?
+        .into_iter()
+        .next()
+        .with_context(|| format!("No presence for {}", person.display_name))?;
+
+    output::print_one(format, &info, |info| {
+        println!("\n{}:", person.display_name);
+        println!("  Availability: {}", info.availability);
+        println!("  Activity: {}", info.activity);
+    })
 }
 
 // ---------------------------------------------------------------------------
@@ -28,8 +69,10 @@ pub async fn get_presence() -> Result<()> {
 // ---------------------------------------------------------------------------
 
 /// Presence info for TUI display.
-#[allow(dead_code)]
+#[derive(Serialize)]
 pub struct PresenceInfo {
+    /// Entra ID (AAD) object ID of the user.
+    pub id: String,
     pub availability: String,
     pub activity: String,
 }
@@ -42,10 +85,28 @@ pub async fn get_presence_data(client: &TeamsClient) -> Result<PresenceInfo> {
         .await
         .context("Failed to parse presence response")?;
 
-    Ok(PresenceInfo {
-        availability: presence.availability,
-        activity: presence.activity,
-    })
+    Ok(presence.into())
+}
+
+/// Fetch the presence of several users by Entra ID (AAD) object ID.
+pub async fn get_presences_with_client(
+    client: &TeamsClient,
+    ids: &[&str],
+) -> Result<Vec<PresenceInfo>> {
+    let mut result = Vec::new();
+    for batch in ids.chunks(MAX_PRESENCE_IDS) {
+        tracing::debug!("Fetching presence of {} users", batch.len());
+        let body = serde_json::json!({ "ids": batch });
+        let resp = client
+            .graph_post("/communications/getPresencesByUserId", &body)
+            .await?;
+        let presences: PresencesResponse = resp
+            .json()
+            .await
+            .context("Failed to parse presences response")?;
+        result.extend(presences.value.into_iter().map(PresenceInfo::from));
+    }
+    Ok(result)
 }
 
 /// Set presence status
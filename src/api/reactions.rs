@@ -0,0 +1,165 @@
+//! Message reactions ("emotions" on the chat service)
+//!
+//! Reactions are stored in the message's `properties.emotions` bag and are
+//! changed through the per-message `properties?name=emotions` endpoint.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::chat::message_url;
+use super::client::TeamsClient;
+
+/// Built-in Teams reactions: (key, emoji, ASCII label for the TUI).
+pub const REACTIONS: &[(&str, &str, &str)] = &[
+    ("like", "\u{1F44D}", "+1"),
+    ("heart", "\u{2764}\u{FE0F}", "<3"),
+    ("laugh", "\u{1F606}", "haha"),
+    ("surprised", "\u{1F62E}", "wow"),
+    ("sad", "\u{1F622}", "sad"),
+    ("angry", "\u{1F621}", "grr"),
+];
+
+/// A reaction on a message and the MRIs of the users who added it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReactionInfo {
+    pub key: String,
+    pub users: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Emotion {
+    key: String,
+    #[serde(default)]
+    users: Vec<EmotionUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmotionUser {
+    mri: String,
+}
+
+/// Parse `properties.emotions`, which arrives either as a JSON array or as
+/// a string containing one. Reactions nobody holds any more are dropped.
+pub(super) fn parse_emotions(value: &serde_json::Value) -> Vec<ReactionInfo> {
+    let emotions: Vec<Emotion> = match value {
+        serde_json::Value::String(s) => serde_json::from_str(s).unwrap_or_default(),
+        other => serde_json::from_value(other.clone()).unwrap_or_default(),
+    };
+    emotions
+        .into_iter()
+        .filter(|e| !e.users.is_empty())
+        .map(|e| ReactionInfo {
+            key: e.key,
+            users: e.users.into_iter().map(|u| u.mri).collect(),
+        })
+        .collect()
+}
+
+/// Map user input (key, emoji or TUI label) to a reaction key.
+///
+/// Unknown plain words are passed through, since Teams accepts many more
+/// keys than the built-in six.
+pub fn reaction_key(input: &str) -> Result<String> {
+    let input = input.trim();
+    let wanted = input.trim_end_matches('\u{FE0F}');
+    for (key, emoji, label) in REACTIONS {
+        if key.eq_ignore_ascii_case(input)
+            || emoji.trim_end_matches('\u{FE0F}') == wanted
+            || *label == input
+        {
+            return Ok(key.to_string());
+        }
+    }
+    if !input.is_empty() && input.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Ok(input.to_lowercase());
+    }
+    bail!(
+        "Unknown reaction '{}'. Use one of: {}",
+        input,
+        REACTIONS
+            .iter()
+            .map(|(key, emoji, _)| format!("{} ({})", key, emoji))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// Short ASCII label for a reaction key ("+1", "<3", ...).
+pub fn reaction_label(key: &str) -> &str {
+    REACTIONS
+        .iter()
+        .find(|(k, _, _)| *k == key)
+        .map_or(key, |(_, _, label)| label)
+}
+
+/// Add or remove our reaction on a message (prints to stdout).
+pub async fn react(chat_id: &str, message_id: &str, emoji: &str, remove: bool) -> Result<()> {
+    let key = reaction_key(emoji)?;
+    let client = TeamsClient::new().await?;
+    set_reaction_with_client(&client, chat_id, message_id, &key, !remove).await?;
+    if remove {
+        println!("Reaction '{}' removed.", key);
+    } else {
+        println!("Reaction '{}' added.", key);
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Data-returning API functions for TUI integration
+// ---------------------------------------------------------------------------
+
+/// Add (`add = true`) or remove our `key` reaction on a message.
+pub async fn set_reaction_with_client(
+    client: &TeamsClient,
+    chat_id: &str,
+    message_id: &str,
+    key: &str,
+    add: bool,
+) -> Result<()> {
+    let url = format!(
+        "{}/properties?name=emotions",
+        message_url(client, chat_id, message_id)
+    );
+
+    if add {
+        let body = serde_json::json!({
+            "emotions": { "key": key, "value": chrono::Utc::now().timestamp_millis() }
+        });
+        client.chat_put(&url, &body).await?;
+    } else {
+        let body = serde_json::json!({ "emotions": { "key": key } });
+        client.chat_delete(&url, Some(&body)).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stringified_and_array_emotions() {
+        let array = serde_json::json!([
+            {"key": "like", "users": [{"mri": "8:orgid:a", "time": 1, "value": "1"}]},
+            {"key": "heart", "users": []},
+        ]);
+        let parsed = parse_emotions(&array);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].key, "like");
+        assert_eq!(parsed[0].users, ["8:orgid:a"]);
+
+        let string = serde_json::Value::String(array.to_string());
+        assert_eq!(parse_emotions(&string).len(), 1);
+    }
+
+    #[test]
+    fn reaction_key_accepts_keys_emoji_and_labels() {
+        assert_eq!(reaction_key("Like").unwrap(), "like");
+        assert_eq!(reaction_key("\u{1F44D}").unwrap(), "like");
+        assert_eq!(reaction_key("\u{2764}").unwrap(), "heart");
+        assert_eq!(reaction_key("<3").unwrap(), "heart");
+        assert_eq!(reaction_key("yes").unwrap(), "yes");
+        assert!(reaction_key("\u{1F980}").is_err());
+    }
+}
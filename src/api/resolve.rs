@@ -0,0 +1,315 @@
+//! Addressing conversations by name
+//!
+//! Commands take a conversation as any of:
+//! - a thread ID (`19:...`), used as is;
+//! - an alias from the config file (see `teams-cli alias`);
+//! - a person's email address or UPN, for our 1:1 chat with them (only
+//!   `send` starts one when there is none yet);
+//! - `team/channel`, for a team channel;
+//! - anything else, matched against chat names (topics, or the members of
+//!   untitled chats).
+//!
+//! Names match case-insensitively: exact names first, then names containing
+//! the text, then names containing each of its words. When several match,
+//! an interactive terminal is asked to choose; otherwise it is an error.
+
+use std::io::{BufRead, IsTerminal, Write};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use super::chat;
+use super::client::TeamsClient;
+use super::people;
+use super::teams;
+use super::threads;
+use crate::config::Config;
+use crate::output::{self, OutputFormat};
+
+/// Recent chats searched for a name.
+const CHAT_LOOKUP_LIMIT: usize = 100;
+
+/// A conversation alias.
+#[derive(Debug, Clone, Serialize)]
+pub struct AliasInfo {
+    pub name: String,
+    pub id: String,
+}
+
+/// Resolve a conversation given on the command line to its thread ID.
+///
+/// Thread IDs and aliases are answered without touching the network. An
+/// email address must name someone we already have a 1:1 chat with.
+pub async fn resolve_conversation(target: &str) -> Result<String> {
+    resolve(target, false).await
+}
+
+/// Like [`resolve_conversation`], but an email address of someone we have
+/// never talked to starts a 1:1 chat with them (for `send`).
+pub async fn resolve_or_start_conversation(target: &str) -> Result<String> {
+    resolve(target, true).await
+}
+
+async fn resolve(target: &str, start: bool) -> Result<String> {
+    let target = target.trim();
+    if is_thread_id(target) {
+        return Ok(target.to_string());
+    }
+    if let Some(id) = Config::load()?.aliases.get(target) {
+        tracing::debug!("Alias {} is {}", target, id);
+        return Ok(id.clone());
+    }
+    let client = TeamsClient::new().await?;
+    resolve_conversation_with_client(&client, target, start).await
+}
+
+/// Save `name` as an alias for `target`, resolved now (prints to stdout).
+pub async fn set_alias(name: &str, target: &str) -> Result<()> {
+    let name = name.trim();
+    if name.is_empty() || is_thread_id(name) {
+        bail!("'{}' cannot be an alias", name);
+    }
+    let id = resolve_conversation(target).await?;
+
+    // Reload: resolving may have refreshed and saved tokens.
+    let mut config = Config::load()?;
+    config.aliases.insert(name.to_string(), id.clone());
+    config.save()?;
+    println!("{} -> {}", name, id);
+    Ok(())
+}
+
+/// Delete an alias (prints to stdout).
+pub async fn remove_alias(name: &str) -> Result<()> {
+    let mut config = Config::load()?;
+    if config.aliases.remove(name.trim()).is_none() {
+        bail!("No alias named '{}'", name.trim());
+    }
+    config.save()?;
+    println!("Removed alias {}.", name.trim());
+    Ok(())
+}
+
+/// List aliases (prints to stdout).
+pub async fn list_aliases(format: OutputFormat) -> Result<()> {
+    let aliases: Vec<AliasInfo> = Config::load()?
+        .aliases
+        .into_iter()
+        .map(|(name, id)| AliasInfo { name, id })
+        .collect();
+
+    output::print_list(format, &aliases, |aliases| {
+        if aliases.is_empty() {
+            println!("No aliases. Add one with `teams-cli alias set <name> <chat>`.");
+            return;
+        }
+        for a in aliases {
+            println!("{:<16} {}", a.name, a.id);
+        }
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Data-returning API functions for TUI integration
+// ---------------------------------------------------------------------------
+
+/// Resolve an email address, `team/channel` or chat name to a thread ID
+/// (thread IDs pass through; aliases are left to the caller).
+///
+/// An email address gives our 1:1 chat with that person. If we have never
+/// talked, it is created when `start` is set and an error otherwise, so a
+/// mistyped address cannot quietly open a chat with a stranger.
+pub async fn resolve_conversation_with_client(
+    client: &TeamsClient,
+    target: &str,
+    start: bool,
+) -> Result<String> {
+    let target = target.trim();
+    if target.is_empty() {
+        bail!("No chat given");
+    }
+    if is_thread_id(target) {
+        return Ok(target.to_string());
+    }
+
+    if is_address(target) {
+        let person = people::find_user_with_client(client, target).await?;
+        if start {
+            return threads::create_chat_with_client(client, std::slice::from_ref(&person), None)
+                .await
+                .with_context(|| format!("Failed to find the chat with {}", person.display_name));
+        }
+        let mri = person.mri();
+        return chat::list_chats_data(client, CHAT_LOOKUP_LIMIT)
+            .await?
+            .into_iter()
+            .find(|c| c.partner_mri.as_deref() == Some(mri.as_str()))
+            .map(|c| c.id)
+            .with_context(|| {
+                format!(
+                    "No chat with {} yet (start one with `teams-cli chat new --with {}`)",
+                    person.display_name, target
+                )
+            });
+    }
+
+    if target.contains('/') {
+        return teams::resolve_channel(client, target).await;
+    }
+
+    let chats: Vec<Candidate> = chat::list_chats_data(client, CHAT_LOOKUP_LIMIT)
+        .await?
+        .into_iter()
+        .map(|c| Candidate {
+            name: c.name,
+            id: c.id,
+        })
+        .collect();
+    Ok(choose(&chats, target, "chat")?.id.clone())
+}
+
+/// Whether `target` is already a conversation ID.
+fn is_thread_id(target: &str) -> bool {
+    target.starts_with("19:") || target.starts_with("48:")
+}
+
+/// Whether `target` looks like an email address or UPN.
+fn is_address(target: &str) -> bool {
+    target.contains('@') && !target.contains('/') && !target.contains(char::is_whitespace)
+}
+
+/// Something that can be picked by name.
+#[derive(Debug)]
+pub(super) struct Candidate {
+    pub(super) name: String,
+    pub(super) id: String,
+}
+
+/// The candidate named `wanted`, asking the user to choose among several
+/// matches when the terminal is interactive.
+pub(super) fn choose<'a>(
+    candidates: &'a [Candidate],
+    wanted: &str,
+    what: &str,
+) -> Result<&'a Candidate> {
+    let found = find_by_name(candidates, wanted);
+    match found.as_slice() {
+        [one] => Ok(one),
+        [] => bail!("No {} matching '{}'", what, wanted),
+        many if std::io::stdin().is_terminal() && std::io::stderr().is_terminal() => {
+            prompt_choice(many, wanted, what)
+        }
+        many => {
+            let names: Vec<&str> = many.iter().map(|c| c.name.as_str()).collect();
+            bail!(
+                "'{}' matches several {}s: {}",
+                wanted,
+                what,
+                names.join(", ")
+            )
+        }
+    }
+}
+
+/// Candidates matching `wanted`, best tier only: exact names, else names
+/// containing it, else names containing each of its words.
+fn find_by_name<'a>(candidates: &'a [Candidate], wanted: &str) -> Vec<&'a Candidate> {
+    let wanted = wanted.trim().to_lowercase();
+    let words: Vec<&str> = wanted.split_whitespace().collect();
+    let tiers: [&dyn Fn(&str) -> bool; 3] = [
+        &|name| name == wanted,
+        &|name| name.contains(&wanted),
+        &|name| !words.is_empty() && words.iter().all(|w| name.contains(w)),
+    ];
+    for matches in tiers {
+        let found: Vec<&Candidate> = candidates
+            .iter()
+            .filter(|c| matches(&c.name.to_lowercase()))
+            .collect();
+        if !found.is_empty() {
+            return found;
+        }
+    }
+    Vec::new()
+}
+
+/// Ask on the terminal which of several matches was meant.
+fn prompt_choice<'a>(many: &[&'a Candidate], wanted: &str, what: &str) -> Result<&'a Candidate> {
+    let mut err = std::io::stderr();
+    writeln!(err, "'{}' matches several {}s:", wanted, what)?;
+    for (i, c) in many.iter().enumerate() {
+        // Show IDs only where names alone cannot tell the matches apart.
+        if many.iter().filter(|o| o.name == c.name).count() > 1 {
+            writeln!(err, "  {}) {}  ({})", i + 1, c.name, c.id)?;
+        } else {
+            writeln!(err, "  {}) {}", i + 1, c.name)?;
+        }
+    }
+    write!(err, "Which one? [1-{}]: ", many.len())?;
+    err.flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| many.get(i).copied())
+        .with_context(|| format!("No {} chosen", what))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<Candidate> {
+        [
+            "General",
+            "General Chat",
+            "Releases",
+            "Alice Smith, Bob Jones",
+        ]
+        .iter()
+        .map(|name| Candidate {
+            name: name.to_string(),
+            id: format!("19:{}", name.len()),
+        })
+        .collect()
+    }
+
+    fn names<'a>(found: &[&'a Candidate]) -> Vec<&'a str> {
+        found.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn exact_name_beats_partial_matches() {
+        let items = candidates();
+        assert_eq!(names(&find_by_name(&items, "general")), ["General"]);
+    }
+
+    #[test]
+    fn falls_back_to_substrings_then_words() {
+        let items = candidates();
+        assert_eq!(names(&find_by_name(&items, "rel")), ["Releases"]);
+        assert_eq!(
+            names(&find_by_name(&items, "gen")),
+            ["General", "General Chat"]
+        );
+        assert_eq!(
+            names(&find_by_name(&items, "bob alice")),
+            ["Alice Smith, Bob Jones"]
+        );
+        assert!(find_by_name(&items, "random").is_empty());
+        assert!(choose(&items, "random", "chat").is_err());
+    }
+
+    #[test]
+    fn recognises_ids_and_addresses() {
+        assert!(is_thread_id("19:abc@thread.v2"));
+        assert!(!is_thread_id("Release planning"));
+        assert!(is_address("alice@contoso.com"));
+        assert!(!is_address("Engineering/General"));
+        assert!(!is_address("lunch @ noon"));
+    }
+}
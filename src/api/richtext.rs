@@ -0,0 +1,973 @@
+//! Rich message content
+//!
+//! Teams message bodies are HTML. [`RichText::from_html`] parses the subset
+//! the clients produce into a small styled representation: paragraphs of
+//! styled spans, quotes, lists, code blocks and tables. The CLI renders it as
+//! Markdown or ANSI-styled text ([`RichText::render`]); the TUI maps it onto
+//! ratatui styles.
+
+use std::io::IsTerminal;
+
+/// Inline text attributes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpanStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strike: bool,
+    pub code: bool,
+    /// An @mention of a person, tag or team.
+    pub mention: bool,
+    /// Link target.
+    pub link: Option<String>,
+}
+
+/// A run of text with one style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: SpanStyle,
+}
+
+/// A block of content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// Inline text; `\n` inside spans is a hard line break.
+    Paragraph(Vec<Span>),
+    Quote(Vec<Block>),
+    List {
+        ordered: bool,
+        items: Vec<Vec<Block>>,
+    },
+    /// Preformatted text, whitespace preserved.
+    Code(String),
+    /// Rows of cells; the first row is treated as the header.
+    Table(Vec<Vec<Vec<Span>>>),
+    Rule,
+    /// The message a chat reply quotes.
+    Reply(ReplyQuote),
+}
+
+/// A quoted message at the top of a chat reply
+/// (`<blockquote itemtype="http://schema.skype.com/Reply">`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplyQuote {
+    /// ID of the quoted message.
+    pub message_id: Option<String>,
+    pub sender: String,
+    /// Sender MRI, when given.
+    pub sender_id: Option<String>,
+    /// Quoted text (usually a shortened preview).
+    pub blocks: Vec<Block>,
+}
+
+/// Parsed message content.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RichText {
+    pub blocks: Vec<Block>,
+}
+
+/// Text output flavour for [`RichText::render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFlavor {
+    /// Unstyled text (previews, search, JSON output).
+    Plain,
+    Markdown,
+    /// SGR escape sequences for terminals.
+    Ansi,
+}
+
+impl TextFlavor {
+    /// ANSI when stdout is a terminal (and `NO_COLOR` is unset), Markdown
+    /// when it is piped.
+    pub fn for_stdout() -> Self {
+        if std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none() {
+            TextFlavor::Ansi
+        } else {
+            TextFlavor::Markdown
+        }
+    }
+}
+
+impl RichText {
+    /// Parse Teams message HTML. Never fails: unknown tags are treated as
+    /// inline containers and unclosed tags are closed at the end.
+    pub fn from_html(html: &str) -> Self {
+        let nodes = parse_dom(html);
+        let mut out = BlockBuilder::default();
+        walk(&nodes, &SpanStyle::default(), &mut out);
+        Self {
+            blocks: out.finish(),
+        }
+    }
+
+    /// Unstyled text as a single paragraph (for content without HTML).
+    pub fn plain(text: &str) -> Self {
+        if text.is_empty() {
+            return Self::default();
+        }
+        Self {
+            blocks: vec![Block::Paragraph(vec![Span {
+                text: text.to_string(),
+                style: SpanStyle::default(),
+            }])],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Render as text, one block per line group.
+    pub fn render(&self, flavor: TextFlavor) -> String {
+        let mut lines = Vec::new();
+        render_blocks(&self.blocks, flavor, &mut lines);
+        lines.join("\n")
+    }
+
+    pub fn plain_text(&self) -> String {
+        self.render(TextFlavor::Plain)
+    }
+
+    /// The quoted message, if this is a chat reply.
+    pub fn reply(&self) -> Option<&ReplyQuote> {
+        self.blocks.iter().find_map(|b| match b {
+            Block::Reply(quote) => Some(quote),
+            _ => None,
+        })
+    }
+
+    /// Content without the quoted message of a reply.
+    pub fn without_reply(&self) -> RichText {
+        RichText {
+            blocks: self
+                .blocks
+                .iter()
+                .filter(|b| !matches!(b, Block::Reply(_)))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// HTML parsing
+// ---------------------------------------------------------------------------
+
+enum Token<'a> {
+    Text(&'a str),
+    Open {
+        name: String,
+        attrs: Vec<(String, String)>,
+        self_closing: bool,
+    },
+    Close(String),
+}
+
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn new(name: String, attrs: Vec<(String, String)>) -> Self {
+        Self {
+            name,
+            attrs,
+            children: Vec::new(),
+        }
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether `itemtype` names the given schema.skype.com type.
+    fn is_item(&self, kind: &str) -> bool {
+        self.attr("itemtype")
+            .is_some_and(|t| t.rsplit('/').next() == Some(kind))
+    }
+}
+
+/// Elements that never have content.
+fn is_void(name: &str) -> bool {
+    matches!(
+        name,
+        "br" | "hr" | "img" | "input" | "meta" | "link" | "col" | "wbr"
+    )
+}
+
+/// Build an element tree. Close tags without a matching open tag are
+/// ignored; a close tag closes any unclosed tags nested inside it.
+fn parse_dom(html: &str) -> Vec<Node> {
+    let mut stack = vec![Element::new(String::new(), Vec::new())];
+    for token in tokenize(html) {
+        match token {
+            Token::Text(text) => {
+                if let Some(top) = stack.last_mut() {
+                    top.children.push(Node::Text(decode_entities(text)));
+                }
+            }
+            Token::Open {
+                name,
+                attrs,
+                self_closing,
+            } => {
+                let el = Element::new(name, attrs);
+                if self_closing || is_void(&el.name) {
+                    if let Some(top) = stack.last_mut() {
+                        top.children.push(Node::Element(el));
+                    }
+                } else {
+                    stack.push(el);
+                }
+            }
+            Token::Close(name) => {
+                if let Some(pos) = stack.iter().rposition(|e| e.name == name) {
+                    if pos > 0 {
+                        close_to(&mut stack, pos);
+                    }
+                }
+            }
+        }
+    }
+    close_to(&mut stack, 1);
+    stack.pop().map(|root| root.children).unwrap_or_default()
+}
+
+/// Pop elements until `len` remain, attaching each to its parent.
+fn close_to(stack: &mut Vec<Element>, len: usize) {
+    while stack.len() > len {
+        if let Some(el) = stack.pop() {
+            if let Some(parent) = stack.last_mut() {
+                parent.children.push(Node::Element(el));
+            }
+        }
+    }
+}
+
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |i| &after[i + 3..]);
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |i| &rest[i + 1..]);
+        } else if rest.starts_with('<') {
+            match parse_tag(rest) {
+                Some((token, after)) => {
+                    tokens.push(token);
+                    rest = after;
+                }
+                None => {
+                    // A stray '<' is text.
+                    tokens.push(Token::Text("<"));
+                    rest = &rest[1..];
+                }
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            tokens.push(Token::Text(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    tokens
+}
+
+/// Parse a tag at the start of `s` (which begins with `<`). Returns the
+/// token and the remaining input.
+fn parse_tag(s: &str) -> Option<(Token<'_>, &str)> {
+    let body = &s[1..];
+    let (closing, body) = match body.strip_prefix('/') {
+        Some(b) => (true, b),
+        None => (false, body),
+    };
+    let bytes = body.as_bytes();
+    let name_len = bytes
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b':'))
+        .count();
+    if name_len == 0 {
+        return None;
+    }
+    let name = body[..name_len].to_ascii_lowercase();
+
+    let mut attrs = Vec::new();
+    let mut self_closing = false;
+    let mut i = name_len;
+    loop {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        match bytes.get(i)? {
+            b'>' => {
+                i += 1;
+                break;
+            }
+            b'/' => {
+                self_closing = true;
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let start = i;
+        while i < bytes.len()
+            && !bytes[i].is_ascii_whitespace()
+            && !matches!(bytes[i], b'=' | b'>' | b'/')
+        {
+            i += 1;
+        }
+        if i == start {
+            // Stray '=': skip it.
+            i += 1;
+            continue;
+        }
+        let attr_name = body[start..i].to_ascii_lowercase();
+        self_closing = false;
+
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let mut value = "";
+        if bytes.get(i) == Some(&b'=') {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            match bytes.get(i) {
+                Some(&q) if q == b'"' || q == b'\'' => {
+                    let end = body[i + 1..].find(q as char)? + i + 1;
+                    value = &body[i + 1..end];
+                    i = end + 1;
+                }
+                _ => {
+                    let start = i;
+                    while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                        i += 1;
+                    }
+                    value = &body[start..i];
+                }
+            }
+        }
+        attrs.push((attr_name, decode_entities(value)));
+    }
+
+    let token = if closing {
+        Token::Close(name)
+    } else {
+        Token::Open {
+            name,
+            attrs,
+            self_closing,
+        }
+    };
+    Some((token, &body[i..]))
+}
+
+/// Decode character references (`&amp;`, `&#39;`, `&#x1F600;`, ...).
+/// Unknown references are left as they are. Control characters other than
+/// newlines and tabs are dropped, whether literal or encoded, so a message
+/// cannot smuggle terminal escape sequences onto the screen.
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        out.extend(rest[..i].chars().filter(|&c| is_printable(c)));
+        rest = &rest[i..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&n| n > 0 && n <= 10)
+            .and_then(|n| entity(&rest[1..n + 1]).map(|c| (c, n)));
+        match decoded {
+            Some((c, n)) => {
+                if is_printable(c) {
+                    out.push(c);
+                }
+                rest = &rest[n + 2..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.extend(rest.chars().filter(|&c| is_printable(c)));
+    out
+}
+
+fn is_printable(c: char) -> bool {
+    !c.is_control() || c == '\n' || c == '\t'
+}
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(num) = name.strip_prefix('#') {
+        let code = match num.strip_prefix('x').or_else(|| num.strip_prefix('X')) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "hellip" => '\u{2026}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "ldquo" => '\u{201c}',
+        "rdquo" => '\u{201d}',
+        "bull" => '\u{2022}',
+        "middot" => '\u{b7}',
+        "copy" => '\u{a9}',
+        "reg" => '\u{ae}',
+        "trade" => '\u{2122}',
+        "times" => '\u{d7}',
+        "rarr" => '\u{2192}',
+        "larr" => '\u{2190}',
+        _ => return None,
+    };
+    Some(c)
+}
+
+// ---------------------------------------------------------------------------
+// Tree to blocks
+// ---------------------------------------------------------------------------
+
+/// Collects blocks, gathering inline content into the current paragraph.
+#[derive(Default)]
+struct BlockBuilder {
+    blocks: Vec<Block>,
+    spans: Vec<Span>,
+}
+
+impl BlockBuilder {
+    /// End the current paragraph, if it has any text.
+    fn flush(&mut self) {
+        while let Some(last) = self.spans.last_mut() {
+            let trimmed = last.text.trim_end_matches([' ', '\n']).len();
+            last.text.truncate(trimmed);
+            if last.text.is_empty() {
+                self.spans.pop();
+            } else {
+                break;
+            }
+        }
+        if !self.spans.is_empty() {
+            self.blocks
+                .push(Block::Paragraph(std::mem::take(&mut self.spans)));
+        }
+    }
+
+    fn push_block(&mut self, block: Block) {
+        self.flush();
+        self.blocks.push(block);
+    }
+
+    /// Append text, collapsing whitespace as HTML does.
+    fn text(&mut self, text: &str, style: &SpanStyle) {
+        let mut collapsed = String::with_capacity(text.len());
+        let mut at_space = match self.spans.last() {
+            Some(last) => last.text.ends_with([' ', '\n']),
+            None => true,
+        };
+        for c in text.chars() {
+            if c.is_ascii_whitespace() {
+                if !at_space {
+                    collapsed.push(' ');
+                    at_space = true;
+                }
+            } else {
+                collapsed.push(if c == '\u{a0}' { ' ' } else { c });
+                at_space = false;
+            }
+        }
+        self.push_span(collapsed, style);
+    }
+
+    fn line_break(&mut self) {
+        if let Some(last) = self.spans.last_mut() {
+            let trimmed = last.text.trim_end_matches(' ').len();
+            last.text.truncate(trimmed);
+        }
+        self.push_span("\n".to_string(), &SpanStyle::default());
+    }
+
+    fn push_span(&mut self, text: String, style: &SpanStyle) {
+        if text.is_empty() {
+            return;
+        }
+        match self.spans.last_mut() {
+            Some(last) if last.style == *style => last.text.push_str(&text),
+            _ => self.spans.push(Span {
+                text,
+                style: style.clone(),
+            }),
+        }
+    }
+
+    fn finish(mut self) -> Vec<Block> {
+        self.flush();
+        self.blocks
+    }
+}
+
+fn walk(nodes: &[Node], style: &SpanStyle, out: &mut BlockBuilder) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.text(text, style),
+            Node::Element(el) => element(el, style, out),
+        }
+    }
+}
+
+/// Blocks for a container element's children.
+fn child_blocks(el: &Element, style: &SpanStyle) -> Vec<Block> {
+    let mut inner = BlockBuilder::default();
+    walk(&el.children, style, &mut inner);
+    inner.finish()
+}
+
+fn element(el: &Element, style: &SpanStyle, out: &mut BlockBuilder) {
+    let styled = |f: fn(&mut SpanStyle)| {
+        let mut s = style.clone();
+        f(&mut s);
+        s
+    };
+    match el.name.as_str() {
+        "br" => out.line_break(),
+        "hr" => out.push_block(Block::Rule),
+        "p" | "div" | "li" | "tr" => {
+            out.flush();
+            walk(&el.children, style, out);
+            out.flush();
+        }
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            out.flush();
+            walk(&el.children, &styled(|s| s.bold = true), out);
+            out.flush();
+        }
+        "blockquote" if el.is_item("Reply") => out.push_block(reply_block(el, style)),
+        "blockquote" => out.push_block(Block::Quote(child_blocks(el, style))),
+        "ul" | "ol" => {
+            let items = el
+                .children
+                .iter()
+                .filter_map(|n| match n {
+                    Node::Element(li) if li.name == "li" => Some(child_blocks(li, style)),
+                    _ => None,
+                })
+                .collect();
+            out.push_block(Block::List {
+                ordered: el.name == "ol",
+                items,
+            });
+        }
+        "pre" | "codeblock" => {
+            let mut text = String::new();
+            raw_text(&el.children, &mut text);
+            let text = text.strip_prefix('\n').unwrap_or(&text).trim_end();
+            out.push_block(Block::Code(text.to_string()));
+        }
+        "table" => {
+            let mut rows = Vec::new();
+            table_rows(&el.children, style, &mut rows);
+            out.push_block(Block::Table(rows));
+        }
+        "img" => {
+            // Emoji are images with the character as alt text; pictures are
+            // shown as attachments.
+            if el.is_item("Emoji") {
+                if let Some(alt) = el.attr("alt") {
+                    out.text(alt, style);
+                }
+            }
+        }
+        "b" | "strong" => walk(&el.children, &styled(|s| s.bold = true), out),
+        "i" | "em" => walk(&el.children, &styled(|s| s.italic = true), out),
+        "u" | "ins" => walk(&el.children, &styled(|s| s.underline = true), out),
+        "s" | "strike" | "del" => walk(&el.children, &styled(|s| s.strike = true), out),
+        "code" => walk(&el.children, &styled(|s| s.code = true), out),
+        "a" => {
+            let mut s = style.clone();
+            s.link = el.attr("href").map(String::from);
+            walk(&el.children, &s, out);
+        }
+        "span" if el.is_item("Mention") => walk(&el.children, &styled(|s| s.mention = true), out),
+        "script" | "style" | "head" | "title" => {}
+        _ => walk(&el.children, style, out),
+    }
+}
+
+/// A quote-reply blockquote: the sender (`itemprop="mri"`), a timestamp
+/// (`itemprop="time"`) and the quoted preview.
+fn reply_block(el: &Element, style: &SpanStyle) -> Block {
+    let mut quote = ReplyQuote {
+        message_id: el.attr("itemid").map(String::from),
+        ..Default::default()
+    };
+    let mut inner = BlockBuilder::default();
+    for node in &el.children {
+        match node {
+            Node::Element(child) if child.attr("itemprop") == Some("mri") => {
+                raw_text(&child.children, &mut quote.sender);
+                quote.sender_id = child.attr("itemid").map(String::from);
+            }
+            Node::Element(child) if child.attr("itemprop") == Some("time") => {}
+            other => walk(std::slice::from_ref(other), style, &mut inner),
+        }
+    }
+    quote.sender = quote.sender.trim().to_string();
+    quote.blocks = inner.finish();
+    Block::Reply(quote)
+}
+
+/// Concatenate text as-is (for preformatted blocks); `<br>` is a newline.
+fn raw_text(nodes: &[Node], out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(el) if el.name == "br" => out.push('\n'),
+            Node::Element(el) => raw_text(&el.children, out),
+        }
+    }
+}
+
+/// Collect `<tr>` rows (through `<thead>`/`<tbody>`) as rows of cell spans.
+fn table_rows(nodes: &[Node], style: &SpanStyle, rows: &mut Vec<Vec<Vec<Span>>>) {
+    for node in nodes {
+        let Node::Element(el) = node else { continue };
+        if el.name == "tr" {
+            let cells = el
+                .children
+                .iter()
+                .filter_map(|n| match n {
+                    Node::Element(cell) if cell.name == "td" || cell.name == "th" => {
+                        Some(cell_spans(cell, style))
+                    }
+                    _ => None,
+                })
+                .collect();
+            rows.push(cells);
+        } else {
+            table_rows(&el.children, style, rows);
+        }
+    }
+}
+
+/// A table cell's content flattened to one line of spans.
+fn cell_spans(cell: &Element, style: &SpanStyle) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    for block in child_blocks(cell, style) {
+        if let Block::Paragraph(para) = block {
+            if !spans.is_empty() {
+                spans.push(Span {
+                    text: " ".to_string(),
+                    style: SpanStyle::default(),
+                });
+            }
+            spans.extend(para.into_iter().map(|mut s| {
+                s.text = s.text.replace('\n', " ");
+                s
+            }));
+        }
+    }
+    spans
+}
+
+// ---------------------------------------------------------------------------
+// Text rendering
+// ---------------------------------------------------------------------------
+
+fn render_blocks(blocks: &[Block], flavor: TextFlavor, lines: &mut Vec<String>) {
+    for (i, block) in blocks.iter().enumerate() {
+        // Markdown needs blank lines between blocks.
+        if i > 0 && flavor == TextFlavor::Markdown {
+            lines.push(String::new());
+        }
+        match block {
+            Block::Paragraph(spans) => {
+                lines.extend(render_spans(spans, flavor).split('\n').map(String::from))
+            }
+            Block::Quote(inner) => {
+                let prefix = match flavor {
+                    TextFlavor::Ansi => "\x1b[2m\u{2502}\x1b[0m ",
+                    _ => "> ",
+                };
+                let mut quoted = Vec::new();
+                render_blocks(inner, flavor, &mut quoted);
+                lines.extend(
+                    quoted
+                        .into_iter()
+                        .map(|l| format!("{}{}", prefix, l).trim_end().to_string()),
+                );
+            }
+            Block::List { ordered, items } => {
+                for (n, item) in items.iter().enumerate() {
+                    let marker = if *ordered {
+                        format!("{}. ", n + 1)
+                    } else {
+                        "- ".to_string()
+                    };
+                    let indent = " ".repeat(marker.len());
+                    let mut item_lines = Vec::new();
+                    render_blocks(item, flavor, &mut item_lines);
+                    if item_lines.is_empty() {
+                        lines.push(marker.trim_end().to_string());
+                    }
+                    for (j, line) in item_lines.into_iter().enumerate() {
+                        let prefix = if j == 0 { &marker } else { &indent };
+                        lines.push(format!("{}{}", prefix, line).trim_end().to_string());
+                    }
+                }
+            }
+            Block::Code(text) => match flavor {
+                TextFlavor::Markdown => {
+                    lines.push("```".to_string());
+                    lines.extend(text.lines().map(String::from));
+                    lines.push("```".to_string());
+                }
+                TextFlavor::Ansi => {
+                    lines.extend(text.lines().map(|l| format!("  \x1b[36m{}\x1b[0m", l)))
+                }
+                TextFlavor::Plain => lines.extend(text.lines().map(String::from)),
+            },
+            Block::Table(rows) => {
+                for (r, row) in rows.iter().enumerate() {
+                    let cells: Vec<String> = row.iter().map(|c| render_spans(c, flavor)).collect();
+                    if flavor == TextFlavor::Markdown {
+                        lines.push(format!("| {} |", cells.join(" | ")));
+                        if r == 0 {
+                            lines.push(format!("|{}", " --- |".repeat(cells.len())));
+                        }
+                    } else {
+                        lines.push(cells.join(" | "));
+                    }
+                }
+            }
+            Block::Reply(quote) => {
+                // Shown as a quote headed by the sender.
+                let header = Block::Paragraph(vec![Span {
+                    text: format!("{}:", quote.sender),
+                    style: SpanStyle {
+                        bold: true,
+                        ..Default::default()
+                    },
+                }]);
+                let blocks = [vec![header], quote.blocks.clone()].concat();
+                render_blocks(&[Block::Quote(blocks)], flavor, lines);
+            }
+            Block::Rule => lines.push(match flavor {
+                TextFlavor::Ansi => format!("\x1b[2m{}\x1b[0m", "\u{2500}".repeat(20)),
+                _ => "---".to_string(),
+            }),
+        }
+    }
+}
+
+fn render_spans(spans: &[Span], flavor: TextFlavor) -> String {
+    spans.iter().map(|s| render_span(s, flavor)).collect()
+}
+
+/// Render one span. Markers are applied per line so that line prefixes
+/// (quotes, list indents) never end up inside them.
+fn render_span(span: &Span, flavor: TextFlavor) -> String {
+    let style = &span.style;
+    let link = style.link.as_deref().filter(|url| *url != span.text.trim());
+    span.text
+        .split('\n')
+        .map(|line| match flavor {
+            TextFlavor::Plain => line.to_string(),
+            TextFlavor::Markdown => markdown_span(line, style),
+            TextFlavor::Ansi => {
+                let mut codes = Vec::new();
+                if style.bold || style.mention {
+                    codes.push("1");
+                }
+                if style.italic {
+                    codes.push("3");
+                }
+                if style.underline || style.link.is_some() {
+                    codes.push("4");
+                }
+                if style.strike {
+                    codes.push("9");
+                }
+                if style.code {
+                    codes.push("36");
+                } else if style.mention {
+                    codes.push("33");
+                } else if style.link.is_some() {
+                    codes.push("34");
+                }
+                let mut out = if codes.is_empty() || line.is_empty() {
+                    line.to_string()
+                } else {
+                    format!("\x1b[{}m{}\x1b[0m", codes.join(";"), line)
+                };
+                if let Some(url) = link {
+                    out.push_str(&format!(" \x1b[2m<{}>\x1b[0m", url));
+                }
+                out
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn markdown_span(line: &str, style: &SpanStyle) -> String {
+    let core = line.trim();
+    if core.is_empty() {
+        return line.to_string();
+    }
+    // Emphasis markers must hug the text, so keep surrounding spaces outside.
+    let lead = &line[..line.len() - line.trim_start().len()];
+    let trail = &line[line.trim_end().len()..];
+
+    let mut s = if style.code {
+        format!("`{}`", core)
+    } else {
+        escape_markdown(core)
+    };
+    if style.mention {
+        // Written the way mentions are typed, so edits keep them.
+        s = format!("@{}", s);
+    }
+    if style.bold {
+        s = format!("**{}**", s);
+    }
+    if style.italic {
+        s = format!("_{}_", s);
+    }
+    if style.strike {
+        s = format!("~~{}~~", s);
+    }
+    if let Some(ref url) = style.link {
+        s = if url == core {
+            format!("<{}>", url)
+        } else {
+            format!("[{}]({})", s, url)
+        };
+    }
+    format!("{}{}{}", lead, s, trail)
+}
+
+/// Escape characters that would otherwise start Markdown formatting.
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '`') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn md(html: &str) -> String {
+        RichText::from_html(html).render(TextFlavor::Markdown)
+    }
+
+    #[test]
+    fn inline_styles_and_entities() {
+        let rich = RichText::from_html(
+            "<p>Hi <b>bold</b> &amp; <i>it</i>&nbsp;<a href=\"https://x.test/?a=1&amp;b=2\">link</a> &#x1F600;</p>",
+        );
+        assert_eq!(rich.plain_text(), "Hi bold & it link \u{1F600}");
+        assert_eq!(
+            rich.render(TextFlavor::Markdown),
+            "Hi **bold** & _it_ [link](https://x.test/?a=1&b=2) \u{1F600}"
+        );
+    }
+
+    #[test]
+    fn control_characters_are_dropped() {
+        let rich = RichText::from_html("<p>&#27;[31mred&#x1b;[0m \u{1b}]0;title\u{7} &#155;2J</p>");
+        let text = rich.plain_text();
+        assert!(!text.chars().any(|c| c.is_control()), "{:?}", text);
+        assert_eq!(text, "[31mred[0m ]0;title 2J");
+    }
+
+    #[test]
+    fn paragraphs_breaks_and_whitespace() {
+        let rich = RichText::from_html("<div>\n  <p>one\n two</p><p>three<br>four</p></div>");
+        assert_eq!(rich.plain_text(), "one two\nthree\nfour");
+        assert_eq!(rich.blocks.len(), 2);
+    }
+
+    #[test]
+    fn code_blocks_keep_whitespace() {
+        let rich = RichText::from_html(
+            "<pre>fn main() {\n    println!(\"&lt;hi&gt;\");\n}</pre><p>x <code>a*b</code></p>",
+        );
+        assert_eq!(
+            rich.blocks[0],
+            Block::Code("fn main() {\n    println!(\"<hi>\");\n}".to_string())
+        );
+        assert!(md("<p>x <code>a*b</code> c*d</p>").ends_with("x `a*b` c\\*d"));
+    }
+
+    #[test]
+    fn lists_quotes_and_mentions() {
+        let html = concat!(
+            "<p><span itemscope itemtype=\"http://schema.skype.com/Mention\" itemid=\"0\">Alice</span> see:</p>",
+            "<ol><li>first</li><li><p>second</p><ul><li>nested</li></ul></li></ol>",
+            "<blockquote><p>quoted</p></blockquote>",
+        );
+        let rich = RichText::from_html(html);
+        assert!(matches!(&rich.blocks[0], Block::Paragraph(s) if s[0].style.mention));
+        assert_eq!(
+            rich.plain_text(),
+            "Alice see:\n1. first\n2. second\n   - nested\n> quoted"
+        );
+    }
+
+    #[test]
+    fn quote_replies() {
+        let html = concat!(
+            "<blockquote itemscope=\"\" itemtype=\"http://schema.skype.com/Reply\" itemid=\"1700000000000\">",
+            "<strong itemprop=\"mri\" itemid=\"8:orgid:a\">Alice &amp; Co</strong>",
+            "<span itemprop=\"time\" itemid=\"1700000000000\"></span>",
+            "<p itemprop=\"preview\">Ship it?</p></blockquote><p>Yes</p>",
+        );
+        let rich = RichText::from_html(html);
+        let quote = rich.reply().unwrap();
+        assert_eq!(quote.message_id.as_deref(), Some("1700000000000"));
+        assert_eq!(quote.sender, "Alice & Co");
+        assert_eq!(quote.sender_id.as_deref(), Some("8:orgid:a"));
+        assert_eq!(rich.plain_text(), "> Alice & Co:\n> Ship it?\nYes");
+        assert_eq!(rich.without_reply().plain_text(), "Yes");
+    }
+
+    #[test]
+    fn tables_render_as_pipe_rows() {
+        let html = "<table><tbody><tr><th>Name</th><th>Role</th></tr><tr><td>Bob</td><td><p>Dev</p></td></tr></tbody></table>";
+        assert_eq!(md(html), "| Name | Role |\n| --- | --- |\n| Bob | Dev |");
+    }
+
+    #[test]
+    fn malformed_html_is_tolerated() {
+        let rich = RichText::from_html("<p>a < b <b>bold</p> tail</i>");
+        assert_eq!(rich.plain_text(), "a < b bold\ntail");
+    }
+}
@@ -0,0 +1,285 @@
+//! Server-side message search (Microsoft Graph `/search/query`)
+//!
+//! Finds chat and channel messages that were never downloaded, using the
+//! `chatMessage` entity type. Results are merged with local cache hits by
+//! the CLI and the TUI search overlay.
+
+use std::cmp::Reverse;
+
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use super::chat::{user_mri, MessageInfo};
+use super::client::TeamsClient;
+use super::richtext::RichText;
+use super::unread::message_order;
+use crate::cache::{
+    self,
+    search::{SearchIndex, SearchQuery},
+};
+use crate::output::{self, OutputFormat};
+
+/// A message matching a search, with the conversation it belongs to.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub conversation_id: String,
+    pub message: MessageInfo,
+}
+
+// -- Response types for Graph search --
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    value: Vec<SearchResponseValue>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResponseValue {
+    #[serde(default)]
+    hits_containers: Vec<HitsContainer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HitsContainer {
+    #[serde(default)]
+    hits: Vec<Hit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Hit {
+    summary: Option<String>,
+    resource: Option<ChatMessageResource>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChatMessageResource {
+    id: Option<String>,
+    reply_to_id: Option<String>,
+    created_date_time: Option<String>,
+    chat_id: Option<String>,
+    channel_identity: Option<ChannelIdentity>,
+    from: Option<IdentitySet>,
+    body: Option<ItemBody>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChannelIdentity {
+    channel_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdentitySet {
+    user: Option<Identity>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Identity {
+    id: Option<String>,
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemBody {
+    content: Option<String>,
+}
+
+/// Search cached and server-side history and print merged matches.
+pub async fn search_messages(
+    query: &SearchQuery,
+    limit: usize,
+    local_only: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let index = cache::MessageCache::open().and_then(|cache| SearchIndex::load(&cache));
+    let mut hits = match index {
+        Ok(index) => index.search(query, limit),
+        Err(e) if !local_only => {
+            tracing::warn!("Local message cache unavailable: {:#}", e);
+            Vec::new()
+        }
+        Err(e) => return Err(e),
+    };
+
+    if !local_only {
+        let remote = match TeamsClient::new().await {
+            Ok(client) => search_messages_data(&client, &query.text, limit).await,
+            Err(e) => Err(e),
+        };
+        match remote {
+            Ok(remote) => {
+                let remote = remote.into_iter().filter(|h| query.matches_filters(h));
+                merge_hits(&mut hits, remote);
+                hits.truncate(limit);
+            }
+            Err(e) => eprintln!(
+                "Warning: server search failed: {:#} (local results only)",
+                e
+            ),
+        }
+    }
+
+    output::print_list(format, &hits, |hits| {
+        if hits.is_empty() {
+            println!("No matches.");
+            return;
+        }
+
+        for hit in hits {
+            println!(
+                "[{}] {} {}: {}",
+                hit.message.timestamp, hit.conversation_id, hit.message.sender, hit.message.content
+            );
+        }
+    })
+}
+
+/// Add `remote` hits not already in `hits` (by conversation and message ID),
+/// keeping the list newest first.
+pub fn merge_hits(hits: &mut Vec<SearchHit>, remote: impl IntoIterator<Item = SearchHit>) {
+    for hit in remote {
+        let dup = hits
+            .iter()
+            .any(|h| h.conversation_id == hit.conversation_id && h.message.id == hit.message.id);
+        if !dup {
+            hits.push(hit);
+        }
+    }
+    hits.sort_by_key(|h| Reverse(sent_at(h)));
+}
+
+/// When a hit was sent, in milliseconds since the epoch.
+///
+/// Graph and the chat service format timestamps with different precision,
+/// so they are compared parsed; chat service message IDs are the arrival
+/// time in milliseconds and stand in for unparseable timestamps.
+fn sent_at(hit: &SearchHit) -> i64 {
+    DateTime::parse_from_rfc3339(&hit.message.timestamp)
+        .map(|t| t.timestamp_millis())
+        .ok()
+        .or_else(|| message_order(&hit.message.id).and_then(|id| i64::try_from(id).ok()))
+        .unwrap_or(0)
+}
+
+// ---------------------------------------------------------------------------
+// Data-returning API functions for TUI integration
+// ---------------------------------------------------------------------------
+
+/// Query Graph search for chat/channel messages matching `text`.
+pub async fn search_messages_data(
+    client: &TeamsClient,
+    text: &str,
+    limit: usize,
+) -> Result<Vec<SearchHit>> {
+    let body = serde_json::json!({
+        "requests": [{
+            "entityTypes": ["chatMessage"],
+            "query": { "queryString": text },
+            "from": 0,
+            "size": limit.min(50),
+        }]
+    });
+
+    let resp = client.graph_post("/search/query", &body).await?;
+    let body: SearchResponse = resp
+        .json()
+        .await
+        .context("Failed to parse search response")?;
+
+    let hits = body
+        .value
+        .into_iter()
+        .flat_map(|v| v.hits_containers)
+        .flat_map(|c| c.hits)
+        .filter_map(search_hit)
+        .collect();
+    Ok(hits)
+}
+
+/// Convert a Graph search hit into a `SearchHit`, if it names a conversation.
+fn search_hit(hit: Hit) -> Option<SearchHit> {
+    let resource = hit.resource?;
+    let conversation_id = resource
+        .chat_id
+        .or_else(|| resource.channel_identity.and_then(|c| c.channel_id))?;
+
+    let rich = resource
+        .body
+        .and_then(|b| b.content)
+        .map(|c| RichText::from_html(&c))
+        .filter(|r| !r.is_empty())
+        .or_else(|| hit.summary.map(|s| RichText::from_html(&s)))
+        .unwrap_or_default();
+
+    let user = resource.from.and_then(|f| f.user);
+    Some(SearchHit {
+        conversation_id,
+        message: MessageInfo {
+            id: resource.id.unwrap_or_default(),
+            client_message_id: None,
+            sender_id: user.as_ref().and_then(|u| u.id.as_deref()).map(user_mri),
+            sender: user
+                .and_then(|u| u.display_name)
+                .unwrap_or_else(|| "?".to_string()),
+            timestamp: resource.created_date_time.unwrap_or_default(),
+            content: rich.plain_text(),
+            rich,
+            parent_id: resource.reply_to_id,
+            reactions: Vec::new(),
+            attachments: Vec::new(),
+            mentions: Vec::new(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(id: &str, timestamp: &str) -> SearchHit {
+        SearchHit {
+            conversation_id: "19:a@thread.v2".to_string(),
+            message: MessageInfo {
+                id: id.to_string(),
+                timestamp: timestamp.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn ids(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|h| h.message.id.as_str()).collect()
+    }
+
+    #[test]
+    fn merged_hits_are_ordered_by_time_across_formats() {
+        let mut hits = vec![
+            hit("1704103260000", "2024-01-01T10:01:00.000Z"),
+            hit("1704103320000", "2024-01-01T10:02:00.000Z"),
+        ];
+        merge_hits(
+            &mut hits,
+            vec![
+                hit("1704103260500", "2024-01-01T10:01:00.5Z"),
+                hit("1704103380000", "2024-01-01T11:03:00+01:00"),
+                hit("1704103320000", "2024-01-01T10:02:00Z"),
+                hit("1704103200000", ""),
+            ],
+        );
+        assert_eq!(
+            ids(&hits),
+            [
+                "1704103380000",
+                "1704103320000",
+                "1704103260500",
+                "1704103260000",
+                "1704103200000",
+            ]
+        );
+    }
+}
@@ -1,9 +1,11 @@
 //! Microsoft Graph API: joined teams and channels
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use super::client::TeamsClient;
+use super::resolve::{self, Candidate};
+use crate::output::{self, OutputFormat};
 
 #[derive(Debug, Deserialize)]
 struct TeamsResponse {
@@ -30,27 +32,27 @@ struct Channel {
 }
 
 /// List joined teams and channels (prints to stdout).
-pub async fn list_teams() -> Result<()> {
+pub async fn list_teams(format: OutputFormat) -> Result<()> {
     let client = TeamsClient::new().await?;
     let teams = list_teams_data(&client).await?;
 
-    println!("\nTeams and Channels:");
-    println!("{:-<60}", "");
+    output::print_list(format, &teams, |teams| {
+        println!("\nTeams and Channels:");
+        println!("{:-<60}", "");
 
-    if teams.is_empty() {
-        println!("  (no teams found)");
-        return Ok(());
-    }
-
-    for team in &teams {
-        println!("Team: {} ({} channels)", team.name, team.channels.len());
-        for ch in &team.channels {
-            println!("  {:<30} {}", ch.name, ch.id);
+        if teams.is_empty() {
+            println!("  (no teams found)");
+            return;
         }
-        println!();
-    }
 
-    Ok(())
+        for team in teams {
+            println!("Team: {} ({} channels)", team.name, team.channels.len());
+            for ch in &team.channels {
+                println!("  {:<30} {}", ch.name, ch.id);
+            }
+            println!();
+        }
+    })
 }
 
 // ---------------------------------------------------------------------------
@@ -58,6 +60,7 @@ pub async fn list_teams() -> Result<()> {
 // ---------------------------------------------------------------------------
 
 /// Team metadata for TUI display.
+#[derive(Serialize)]
 pub struct TeamInfo {
     pub id: String,
     pub name: String,
@@ -65,6 +68,7 @@ pub struct TeamInfo {
 }
 
 /// Channel metadata for TUI display.
+#[derive(Serialize)]
 pub struct ChannelInfo {
     pub id: String,
     pub name: String,
@@ -116,3 +120,84 @@ pub async fn list_teams_data(client: &TeamsClient) -> Result<Vec<TeamInfo>> {
 
     Ok(result)
 }
+
+/// Resolve a `team/channel` spec to a channel thread ID.
+///
+/// Raw channel IDs (`19:...`) are returned unchanged. Names are matched
+/// as described in [`super::resolve`].
+/// The channel part is split at the last `/`, since channel names cannot
+/// contain one but team names can.
+pub async fn resolve_channel(client: &TeamsClient, spec: &str) -> Result<String> {
+    if spec.starts_with("19:") {
+        return Ok(spec.to_string());
+    }
+    let (team_name, channel_name) = spec
+        .rsplit_once('/')
+        .with_context(|| format!("Expected <team>/<channel>, got '{}'", spec))?;
+
+    let resp = client.graph_get("/me/joinedTeams").await?;
+    let teams: TeamsResponse = resp
+        .json()
+        .await
+        .context("Failed to parse joinedTeams response")?;
+    let teams: Vec<Candidate> = teams
+        .value
+        .into_iter()
+        .map(|t| Candidate {
+            name: t.display_name.unwrap_or_else(|| t.id.clone()),
+            id: t.id,
+        })
+        .collect();
+    let team = resolve::choose(&teams, team_name, "team")?;
+
+    let path = format!("/teams/{}/channels", team.id);
+    let resp = client.graph_get(&path).await?;
+    let channels: ChannelsResponse = resp
+        .json()
+        .await
+        .context("Failed to parse channels response")?;
+    let channels: Vec<Candidate> = channels
+        .value
+        .into_iter()
+        .map(|c| Candidate {
+            name: c.display_name.unwrap_or_else(|| c.id.clone()),
+            id: c.id,
+        })
+        .collect();
+    let channel = resolve::choose(&channels, channel_name, "channel")?;
+
+    Ok(channel.id.clone())
+}
+
+/// Whether a conversation ID names a team channel rather than a chat.
+pub fn is_channel_id(id: &str) -> bool {
+    let (id, _) = super::chat::split_thread_id(id);
+    id.ends_with("@thread.tacv2") || id.ends_with("@thread.skype")
+}
+
+/// ID of the joined team a channel belongs to.
+pub(super) async fn team_of_channel(client: &TeamsClient, channel_id: &str) -> Result<String> {
+    let resp = client.graph_get("/me/joinedTeams").await?;
+    let teams: TeamsResponse = resp
+        .json()
+        .await
+        .context("Failed to parse joinedTeams response")?;
+
+    for team in &teams.value {
+        let path = format!("/teams/{}/channels", team.id);
+        let channels: ChannelsResponse = match client.graph_get(&path).await {
+            Ok(resp) => resp
+                .json()
+                .await
+                .context("Failed to parse channels response")?,
+            Err(e) => {
+                tracing::warn!("Failed to fetch channels for {}: {:#}", team.id, e);
+                continue;
+            }
+        };
+        if channels.value.iter().any(|c| c.id == channel_id) {
+            return Ok(team.id.clone());
+        }
+    }
+    anyhow::bail!("Channel {} is not in any joined team", channel_id)
+}
@@ -0,0 +1,254 @@
+//! Chat threads on the chat service: starting new 1:1 and group chats,
+//! and managing the members and topic of group chats
+//!
+//! Members are listed through Graph (see [`super::members`]), which knows
+//! their names and roles; changes go to the chat service's
+//! `/v1/threads/{id}` resources.
+
+use anyhow::{bail, Context, Result};
+
+use super::chat::user_mri;
+use super::client::TeamsClient;
+use super::me;
+use super::members::{self, MemberRole};
+use super::people::{self, PersonInfo};
+use super::teams;
+use crate::output::{self, OutputFormat};
+
+/// Start a chat with the people at the given email addresses (prints to
+/// stdout).
+pub async fn new_chat(emails: &[String], topic: Option<&str>) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    let mut people = Vec::new();
+    for email in emails {
+        people.push(people::find_user_with_client(&client, email).await?);
+    }
+    let id = create_chat_with_client(&client, &people, topic).await?;
+
+    let names: Vec<&str> = people.iter().map(|p| p.display_name.as_str()).collect();
+    println!("Chat with {} ready.", names.join(", "));
+    println!("  ID: {}", id);
+    Ok(())
+}
+
+/// List the members of a chat with their roles (prints to stdout).
+pub async fn chat_members(chat_id: &str, format: OutputFormat) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    let members = members::list_members_with_client(&client, chat_id).await?;
+
+    output::print_list(format, &members, |members| {
+        println!("\n{} members:", members.len());
+        println!("{:-<60}", "");
+        for m in members {
+            let role = match m.role {
+                MemberRole::Member => String::new(),
+                role => format!(" ({})", role.as_str()),
+            };
+            match m.email {
+                Some(ref email) => println!("{}{}  <{}>", m.display_name, role, email),
+                None => println!("{}{}", m.display_name, role),
+            }
+        }
+    })
+}
+
+/// Add people to a group chat by email address (prints to stdout).
+pub async fn add_chat_members(chat_id: &str, emails: &[String]) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    let mut people = Vec::new();
+    for email in emails {
+        people.push(people::find_user_with_client(&client, email).await?);
+    }
+    add_members_with_client(&client, chat_id, &people).await?;
+    for person in &people {
+        println!("Added {}.", person.display_name);
+    }
+    Ok(())
+}
+
+/// Remove people from a group chat by email address (prints to stdout).
+pub async fn remove_chat_members(chat_id: &str, emails: &[String]) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    for email in emails {
+        let person = people::find_user_with_client(&client, email).await?;
+        remove_member_with_client(&client, chat_id, &person.mri()).await?;
+        println!("Removed {}.", person.display_name);
+    }
+    Ok(())
+}
+
+/// Change the topic of a group chat (prints to stdout).
+pub async fn rename_chat(chat_id: &str, topic: &str) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    rename_chat_with_client(&client, chat_id, topic).await?;
+    println!("Chat renamed to \"{}\".", topic.trim());
+    Ok(())
+}
+
+/// Leave a group chat (prints to stdout).
+pub async fn leave_chat(chat_id: &str) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    leave_chat_with_client(&client, chat_id).await?;
+    println!("Left the chat.");
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Data-returning API functions for TUI integration
+// ---------------------------------------------------------------------------
+
+/// Create a chat between us and `people`, returning its thread ID.
+///
+/// One other person makes a 1:1 chat; the chat service hands back the
+/// existing thread if we already have one with them. More make a group
+/// chat, titled `topic` if given.
+pub async fn create_chat_with_client(
+    client: &TeamsClient,
+    people: &[PersonInfo],
+    topic: Option<&str>,
+) -> Result<String> {
+    let me = me::whoami_data(client).await?;
+    let own_mri = user_mri(&me.id);
+
+    let mut members = vec![serde_json::json!({ "id": own_mri, "role": "Admin" })];
+    for person in people.iter().filter(|p| p.id != me.id) {
+        members.push(serde_json::json!({ "id": person.mri(), "role": "Admin" }));
+    }
+    if members.len() < 2 {
+        bail!("A chat needs at least one other person");
+    }
+    let one_to_one = members.len() == 2;
+
+    let mut properties = serde_json::json!({
+        "threadType": "chat",
+        "chatFilesIndexId": "2",
+        "fixedRoster": one_to_one.to_string(),
+        "uniquerosterthread": one_to_one.to_string(),
+    });
+    if let Some(topic) = topic.map(str::trim).filter(|t| !t.is_empty()) {
+        if one_to_one {
+            bail!("Only group chats can have a topic");
+        }
+        properties["topic"] = topic.into();
+    }
+    let body = serde_json::json!({ "members": members, "properties": properties });
+
+    let url = format!("{}/v1/threads", client.chat_service_url());
+    tracing::debug!("Creating chat with {} members", members.len());
+    let resp = client.chat_post(&url, &body).await?;
+
+    // The new thread's URL comes back in the Location header.
+    let location = resp
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .context("Chat service did not return the new chat's location")?;
+    thread_id_from_location(location)
+        .map(String::from)
+        .with_context(|| format!("Unexpected chat location: {}", location))
+}
+
+/// Fail unless `chat_id` is a group chat: 1:1 chats have a fixed roster
+/// and channel membership belongs to the team.
+fn ensure_group_chat(chat_id: &str) -> Result<()> {
+    if teams::is_channel_id(chat_id) {
+        bail!("Channel members are managed through the team");
+    }
+    if chat_id.ends_with("@unq.gbl.spaces") {
+        bail!("1:1 chats cannot change members or topic; start a group chat instead");
+    }
+    Ok(())
+}
+
+/// URL of one member of a thread.
+fn member_url(client: &TeamsClient, chat_id: &str, mri: &str) -> String {
+    format!(
+        "{}/v1/threads/{}/members/{}",
+        client.chat_service_url(),
+        chat_id,
+        mri
+    )
+}
+
+/// Add people to a group chat. They can see the chat's earlier history.
+pub async fn add_members_with_client(
+    client: &TeamsClient,
+    chat_id: &str,
+    people: &[PersonInfo],
+) -> Result<()> {
+    ensure_group_chat(chat_id)?;
+    for person in people {
+        tracing::debug!("Adding {} to {}", person.mri(), chat_id);
+        let body = serde_json::json!({ "role": "Admin" });
+        client
+            .chat_put(&member_url(client, chat_id, &person.mri()), &body)
+            .await
+            .with_context(|| format!("Failed to add {}", person.display_name))?;
+    }
+    Ok(())
+}
+
+/// Remove the member with the given MRI from a group chat.
+pub async fn remove_member_with_client(
+    client: &TeamsClient,
+    chat_id: &str,
+    mri: &str,
+) -> Result<()> {
+    ensure_group_chat(chat_id)?;
+    tracing::debug!("Removing {} from {}", mri, chat_id);
+    client
+        .chat_delete(&member_url(client, chat_id, mri), None)
+        .await?;
+    Ok(())
+}
+
+/// Set the topic (title) of a group chat.
+pub async fn rename_chat_with_client(
+    client: &TeamsClient,
+    chat_id: &str,
+    topic: &str,
+) -> Result<()> {
+    ensure_group_chat(chat_id)?;
+    let topic = topic.trim();
+    if topic.is_empty() {
+        bail!("The topic cannot be empty");
+    }
+    let url = format!(
+        "{}/v1/threads/{}/properties?name=topic",
+        client.chat_service_url(),
+        chat_id
+    );
+    client
+        .chat_put(&url, &serde_json::json!({ "topic": topic }))
+        .await?;
+    Ok(())
+}
+
+/// Leave a group chat.
+pub async fn leave_chat_with_client(client: &TeamsClient, chat_id: &str) -> Result<()> {
+    let me = me::whoami_data(client).await?;
+    remove_member_with_client(client, chat_id, &user_mri(&me.id)).await
+}
+
+/// Thread ID at the end of a `.../threads/<id>` URL.
+fn thread_id_from_location(location: &str) -> Option<&str> {
+    let (_, id) = location.rsplit_once("/threads/")?;
+    let id = id.split(['?', '/']).next()?;
+    (!id.is_empty()).then_some(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thread_id_from_location_header() {
+        assert_eq!(
+            thread_id_from_location(
+                "https://amer.ng.msg.teams.microsoft.com/v1/threads/19:abc@thread.v2"
+            ),
+            Some("19:abc@thread.v2")
+        );
+        assert_eq!(thread_id_from_location("https://x/v1/threads/"), None);
+    }
+}
@@ -0,0 +1,242 @@
+//! Unread tracking (consumption horizons)
+//!
+//! The chat service keeps how far we have read each conversation in its
+//! `consumptionhorizon` property: `<message id>;<time set>;<client message
+//! id>`. Message IDs are arrival times in epoch milliseconds, so messages
+//! from other people with a larger ID than the horizon are unread.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::chat::{self, user_mri, Conversation, NativeMessage};
+use super::client::TeamsClient;
+use super::me;
+use super::names::ChatNames;
+use crate::output::{self, OutputFormat};
+
+/// Messages fetched per conversation when counting; counts stop here.
+const UNREAD_PAGE_SIZE: usize = 50;
+
+#[derive(Debug, Deserialize)]
+struct ConversationsResponse {
+    conversations: Option<Vec<Conversation>>,
+}
+
+/// List conversations with unread messages (prints to stdout).
+pub async fn list_unread(limit: usize, format: OutputFormat) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    let unread: Vec<UnreadInfo> = list_unread_data(&client, limit)
+        .await?
+        .into_iter()
+        .filter(|u| u.unread > 0)
+        .collect();
+
+    output::print_list(format, &unread, |unread| {
+        if unread.is_empty() {
+            println!("All caught up.");
+            return;
+        }
+        for u in unread {
+            let count = if u.more {
+                format!("{}+", u.unread)
+            } else {
+                u.unread.to_string()
+            };
+            println!("{:>4}  {}", count, u.name);
+            println!("      ID: {}", u.id);
+        }
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Data-returning API functions for TUI integration
+// ---------------------------------------------------------------------------
+
+/// Read state of one conversation.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreadInfo {
+    pub id: String,
+    pub name: String,
+    /// Messages from other people after our consumption horizon.
+    pub unread: u32,
+    /// Whether there may be more unread messages than counted.
+    pub more: bool,
+    /// ID of the newest message we have read (0 if none).
+    pub last_read: u64,
+}
+
+/// Read state of our most recent `limit` chats and channels, most
+/// recently active first.
+///
+/// Only conversations whose last message is newer than the horizon have
+/// their messages fetched for counting.
+pub async fn list_unread_data(client: &TeamsClient, limit: usize) -> Result<Vec<UnreadInfo>> {
+    let url = format!(
+        "{}/v1/users/ME/conversations?view=msnp24Equivalent&pageSize={}",
+        client.chat_service_url(),
+        limit
+    );
+    tracing::debug!("Fetching conversations for unread counts: {}", url);
+    let resp = client.chat_get(&url).await?;
+    let body: ConversationsResponse = resp
+        .json()
+        .await
+        .context("Failed to parse conversations response")?;
+    let me = user_mri(&me::whoami_data(client).await?.id);
+    let conversations = body.conversations.unwrap_or_default();
+    let names = ChatNames::resolve(client, &conversations).await;
+
+    let mut result = Vec::new();
+    for conv in conversations {
+        let Some(id) = conv.id.clone().filter(|id| !id.is_empty()) else {
+            continue;
+        };
+        let last_read = conv
+            .properties
+            .as_ref()
+            .and_then(|p| p.consumption_horizon.as_deref())
+            .and_then(horizon_message_id)
+            .unwrap_or(0);
+        let maybe_unread = conv
+            .last_message
+            .as_ref()
+            .is_some_and(|m| is_unread(m, last_read, &me));
+
+        let (unread, more) = if maybe_unread {
+            let url = chat::messages_url(client, &id, UNREAD_PAGE_SIZE);
+            match chat::fetch_native_page(client, &url).await {
+                Ok(page) => {
+                    // Pages are newest-first: if even the oldest message is
+                    // past the horizon, older pages may hold more.
+                    let more = page.backward_link.is_some()
+                        && page
+                            .messages
+                            .last()
+                            .and_then(|m| m.id())
+                            .and_then(message_order)
+                            .is_some_and(|id| id > last_read);
+                    (count_unread(&page.messages, last_read, &me), more)
+                }
+                Err(e) => {
+                    tracing::warn!("Could not count unread messages in {}: {:#}", id, e);
+                    (1, true)
+                }
+            }
+        } else {
+            (0, false)
+        };
+
+        result.push(UnreadInfo {
+            name: names.conversation_name(&conv),
+            id,
+            unread,
+            more,
+            last_read,
+        });
+    }
+    Ok(result)
+}
+
+/// Move our consumption horizon to `message_id` (sent with the client
+/// message ID `client_message_id`), marking it and everything before it as
+/// read.
+pub async fn mark_read_with_client(
+    client: &TeamsClient,
+    conversation_id: &str,
+    message_id: &str,
+    client_message_id: Option<&str>,
+) -> Result<()> {
+    let url = format!(
+        "{}/v1/users/ME/conversations/{}/properties?name=consumptionhorizon",
+        client.chat_service_url(),
+        conversation_id
+    );
+    let now = chrono::Utc::now().timestamp_millis();
+    let body = serde_json::json!({
+        "consumptionhorizon": horizon_value(message_id, now, client_message_id),
+    });
+    tracing::debug!("Marking {} read up to {}", conversation_id, message_id);
+    client.chat_put(&url, &body).await?;
+    Ok(())
+}
+
+/// A `consumptionhorizon` value. Messages without a client message ID
+/// (e.g. from bots) repeat the server ID in its place.
+fn horizon_value(message_id: &str, time_set: i64, client_message_id: Option<&str>) -> String {
+    format!(
+        "{};{};{}",
+        message_id,
+        time_set,
+        client_message_id.unwrap_or(message_id)
+    )
+}
+
+/// Numeric form of a message ID, for comparing against horizons.
+pub fn message_order(message_id: &str) -> Option<u64> {
+    message_id.parse().ok()
+}
+
+/// The last read message ID in a `consumptionhorizon` value.
+fn horizon_message_id(horizon: &str) -> Option<u64> {
+    horizon.split(';').next().and_then(message_order)
+}
+
+/// Whether a message from someone else arrived after `last_read`.
+fn is_unread(msg: &NativeMessage, last_read: u64, me: &str) -> bool {
+    msg.id()
+        .and_then(message_order)
+        .is_some_and(|id| id > last_read)
+        && msg.sender_mri() != Some(me)
+        && chat::message_info(msg).is_some()
+}
+
+fn count_unread(messages: &[NativeMessage], last_read: u64, me: &str) -> u32 {
+    messages
+        .iter()
+        .filter(|m| is_unread(m, last_read, me))
+        .count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, from: &str) -> NativeMessage {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "messagetype": "RichText/Html",
+            "content": "<p>hi</p>",
+            "from": format!("https://host/v1/users/ME/contacts/{}", from),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn counts_messages_from_others_after_horizon() {
+        let last_read = horizon_message_id("1700000000200;1700000000999;42").unwrap();
+        let messages = [
+            message("1700000000400", "8:orgid:bob"),
+            message("1700000000300", "8:orgid:me"),
+            message("1700000000200", "8:orgid:bob"),
+            message("1700000000100", "8:orgid:bob"),
+        ];
+        assert_eq!(count_unread(&messages, last_read, "8:orgid:me"), 1);
+        assert_eq!(count_unread(&messages, 0, "8:orgid:me"), 3);
+        assert_eq!(horizon_message_id(""), None);
+    }
+
+    #[test]
+    fn horizon_ends_with_the_client_message_id() {
+        let msg: NativeMessage = serde_json::from_value(serde_json::json!({
+            "id": "1700000000400",
+            "clientmessageid": "8123456789012345678",
+            "messagetype": "RichText/Html",
+            "content": "<p>hi</p>",
+        }))
+        .unwrap();
+        let info = chat::message_info(&msg).unwrap();
+        let value = horizon_value(&info.id, 1700000000999, info.client_message_id.as_deref());
+        assert_eq!(value, "1700000000400;1700000000999;8123456789012345678");
+        assert_eq!(horizon_message_id(&value), Some(1700000000400));
+    }
+}
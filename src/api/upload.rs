@@ -0,0 +1,387 @@
+//! File upload for chats
+//!
+//! Images go to AMS (readable by the conversation's members) and are shown
+//! inline. Other files go to the user's OneDrive `Microsoft Teams Chat Files`
+//! folder, as the Teams clients do, shared read-only with the conversation's
+//! members and referenced from the message's `properties.files`.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+use super::chat::{html_escape, html_message_body, post_message};
+use super::client::TeamsClient;
+use super::markdown::markdown_to_html;
+use super::me;
+use super::members::{fetch_members, ConversationMember};
+
+/// OneDrive folder Teams uses for files shared in chats.
+const CHAT_FILES_FOLDER: &str = "Microsoft Teams Chat Files";
+
+/// Largest file sent with a single Graph PUT; bigger files use an upload
+/// session.
+const SIMPLE_UPLOAD_LIMIT: usize = 4 * 1024 * 1024;
+
+/// Upload session chunk size (Graph requires a multiple of 320 KiB).
+const UPLOAD_CHUNK: usize = 16 * 320 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct AmsObject {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadSession {
+    upload_url: String,
+}
+
+/// The OneDrive item created by an upload.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DriveItem {
+    id: String,
+    name: String,
+    web_url: String,
+}
+
+/// Upload a file and send it to a chat, with optional message text.
+pub async fn send_file(chat_id: &str, path: &Path, message: Option<&str>) -> Result<()> {
+    let client = TeamsClient::new().await?;
+    send_file_with_client(&client, chat_id, path, message).await?;
+    println!("File sent.");
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Data-returning API functions for TUI integration
+// ---------------------------------------------------------------------------
+
+/// Upload `path` and post a message referencing it to `chat_id`.
+pub async fn send_file_with_client(
+    client: &TeamsClient,
+    chat_id: &str,
+    path: &Path,
+    message: Option<&str>,
+) -> Result<()> {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .with_context(|| format!("Not a file path: {}", path.display()))?
+        .to_string();
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if bytes.is_empty() {
+        bail!("{} is empty", path.display());
+    }
+
+    let text_html = message
+        .filter(|m| !m.trim().is_empty())
+        .map(|m| markdown_to_html(m, &[]).0)
+        .unwrap_or_default();
+
+    let body = if is_image(&name) {
+        let url = upload_ams_image(client, chat_id, &name, bytes).await?;
+        html_message_body(&format!(
+            "{}<p><img itemtype=\"http://schema.skype.com/AMSImage\" src=\"{}\" alt=\"{}\"></p>",
+            text_html,
+            url,
+            html_escape(&name)
+        ))
+    } else {
+        let item = upload_chat_file(client, &name, bytes).await?;
+        let share_url = share_with_members(client, chat_id, &item).await?;
+        let mut body = html_message_body(&text_html);
+        body["properties"] = serde_json::json!({
+            "files": serde_json::to_string(&[file_property(&item, &share_url)])?,
+        });
+        body
+    };
+
+    post_message(client, chat_id, &body).await
+}
+
+/// Whether a file name looks like an image Teams can show inline.
+fn is_image(name: &str) -> bool {
+    let ext = name.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase());
+    matches!(
+        ext.as_deref(),
+        Some("png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp")
+    )
+}
+
+/// Create an AMS image object readable by the conversation and upload the
+/// bytes. Returns the full-size view URL.
+async fn upload_ams_image(
+    client: &TeamsClient,
+    chat_id: &str,
+    name: &str,
+    bytes: Vec<u8>,
+) -> Result<String> {
+    let base = client.ams_url();
+    let body = serde_json::json!({
+        "type": "pish/image",
+        "permissions": { chat_id: ["read"] },
+        "filename": name,
+    });
+    let resp = client
+        .ams_post(&format!("{}/v1/objects", base), &body)
+        .await?;
+    let object: AmsObject = resp
+        .json()
+        .await
+        .context("Failed to parse AMS object response")?;
+
+    client
+        .ams_put(
+            &format!("{}/v1/objects/{}/content/imgpsh", base, object.id),
+            bytes,
+        )
+        .await?;
+    Ok(format!("{}/v1/objects/{}/views/imgo", base, object.id))
+}
+
+/// Upload to OneDrive `Microsoft Teams Chat Files`, renaming on conflict.
+async fn upload_chat_file(client: &TeamsClient, name: &str, bytes: Vec<u8>) -> Result<DriveItem> {
+    let item_path = format!(
+        "/me/drive/root:/{}/{}:",
+        encode_path_segment(CHAT_FILES_FOLDER),
+        encode_path_segment(name)
+    );
+
+    let resp = if bytes.len() <= SIMPLE_UPLOAD_LIMIT {
+        let path = format!(
+            "{}/content?@microsoft.graph.conflictBehavior=rename",
+            item_path
+        );
+        client.graph_put_bytes(&path, bytes).await?
+    } else {
+        let body = serde_json::json!({
+            "item": { "@microsoft.graph.conflictBehavior": "rename" }
+        });
+        let session: UploadSession = client
+            .graph_post(&format!("{}/createUploadSession", item_path), &body)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse upload session")?;
+
+        let total = bytes.len();
+        let mut last = None;
+        for (i, chunk) in bytes.chunks(UPLOAD_CHUNK).enumerate() {
+            let resp = client
+                .upload_chunk(&session.upload_url, chunk.to_vec(), i * UPLOAD_CHUNK, total)
+                .await?;
+            last = Some(resp);
+        }
+        last.context("Upload session received no data")?
+    };
+
+    resp.json().await.context("Failed to parse uploaded item")
+}
+
+/// Give the other members of the conversation read access to an uploaded
+/// file, as Teams does, and return the URL they open it with. Nobody else
+/// can read it.
+async fn share_with_members(
+    client: &TeamsClient,
+    chat_id: &str,
+    item: &DriveItem,
+) -> Result<String> {
+    let own_id = me::whoami_data(client).await?.id;
+    let members = fetch_members(client, chat_id)
+        .await
+        .context("Failed to list who to share the file with")?;
+    let home_tenant = members
+        .iter()
+        .find(|m| m.user_id.as_deref() == Some(own_id.as_str()))
+        .and_then(|m| m.tenant_id.as_deref());
+
+    let mut recipients = Vec::new();
+    let mut unshared = Vec::new();
+    for member in members
+        .iter()
+        .filter(|m| m.user_id.as_deref() != Some(own_id.as_str()))
+    {
+        match drive_recipient(member, home_tenant) {
+            Some(recipient) => recipients.push(recipient),
+            None => unshared.push(member.display_name.as_deref().unwrap_or("(unknown)")),
+        }
+    }
+    if !unshared.is_empty() {
+        tracing::warn!(
+            "{} has no address to share with; they will not be able to open it: {}",
+            item.name,
+            unshared.join(", ")
+        );
+    }
+
+    if !recipients.is_empty() {
+        let body = serde_json::json!({
+            "recipients": recipients,
+            "roles": ["read"],
+            "requireSignIn": true,
+            "sendInvitation": false,
+        });
+        client
+            .graph_post(&format!("/me/drive/items/{}/invite", item.id), &body)
+            .await
+            .context("Failed to share the file with the chat members")?;
+    }
+    Ok(item.web_url.clone())
+}
+
+/// Graph `driveRecipient` for a member: their object in our directory, or
+/// their address for guests and federated users whose account lives
+/// elsewhere.
+fn drive_recipient(
+    member: &ConversationMember,
+    home_tenant: Option<&str>,
+) -> Option<serde_json::Value> {
+    let in_directory = match (member.tenant_id.as_deref(), home_tenant) {
+        (Some(theirs), Some(ours)) => theirs.eq_ignore_ascii_case(ours),
+        _ => true,
+    };
+    match member.user_id.as_deref() {
+        Some(id) if in_directory => Some(serde_json::json!({ "objectId": id })),
+        _ => member
+            .email
+            .as_deref()
+            .filter(|email| !email.is_empty())
+            .map(|email| serde_json::json!({ "email": email })),
+    }
+}
+
+/// `properties.files` entry describing an uploaded file.
+fn file_property(item: &DriveItem, share_url: &str) -> serde_json::Value {
+    let file_type = item
+        .name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    let base_url = item
+        .web_url
+        .find("/Documents/")
+        .map(|i| item.web_url[..i + 1].to_string())
+        .unwrap_or_default();
+    serde_json::json!({
+        "@type": "http://schema.skype.com/File",
+        "version": 2,
+        "id": item.id,
+        "itemid": item.id,
+        "baseUrl": base_url,
+        "type": file_type,
+        "fileType": file_type,
+        "title": item.name,
+        "fileName": item.name,
+        "state": "active",
+        "objectUrl": item.web_url,
+        "providerData": "",
+        "fileInfo": {
+            "itemId": null,
+            "fileUrl": item.web_url,
+            "siteUrl": base_url,
+            "serverRelativeUrl": "",
+            "shareUrl": share_url,
+            "shareId": null,
+        },
+        "fileChicletState": { "serviceName": "p2p", "state": "active" },
+    })
+}
+
+/// Percent-encode one URL path segment.
+fn encode_path_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for b in segment.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::attachments::{parse_files, AttachmentKind};
+
+    #[test]
+    fn members_are_invited_by_object_id_or_address() {
+        let member = |json: serde_json::Value| -> ConversationMember {
+            serde_json::from_value(json).unwrap()
+        };
+        let colleague = member(serde_json::json!({
+            "displayName": "Ada",
+            "userId": "abc",
+            "email": "ada@contoso.com",
+            "tenantId": "contoso",
+            "roles": [],
+        }));
+        let guest = member(serde_json::json!({
+            "displayName": "Grace (Guest)",
+            "userId": null,
+            "email": "grace@fabrikam.com",
+            "roles": ["guest"],
+        }));
+        let federated = member(serde_json::json!({
+            "displayName": "Linus",
+            "userId": "def",
+            "email": "linus@fabrikam.com",
+            "tenantId": "fabrikam",
+            "roles": [],
+        }));
+        let anonymous = member(serde_json::json!({ "displayName": "Visitor", "roles": [] }));
+
+        let home = Some("contoso");
+        assert_eq!(
+            drive_recipient(&colleague, home),
+            Some(serde_json::json!({ "objectId": "abc" }))
+        );
+        assert_eq!(
+            drive_recipient(&guest, home),
+            Some(serde_json::json!({ "email": "grace@fabrikam.com" }))
+        );
+        assert_eq!(
+            drive_recipient(&federated, home),
+            Some(serde_json::json!({ "email": "linus@fabrikam.com" }))
+        );
+        assert_eq!(drive_recipient(&anonymous, home), None);
+    }
+
+    #[test]
+    fn images_are_detected_by_extension() {
+        assert!(is_image("screenshot.PNG"));
+        assert!(is_image("photo.jpeg"));
+        assert!(!is_image("build.log"));
+        assert!(!is_image("png"));
+    }
+
+    #[test]
+    fn path_segments_are_percent_encoded() {
+        assert_eq!(
+            encode_path_segment("Microsoft Teams Chat Files"),
+            "Microsoft%20Teams%20Chat%20Files"
+        );
+        assert_eq!(encode_path_segment("a#b?.txt"), "a%23b%3F.txt");
+    }
+
+    #[test]
+    fn file_property_round_trips_through_parser() {
+        let item = DriveItem {
+            id: "01ABC".to_string(),
+            name: "fix.patch".to_string(),
+            web_url: "https://contoso-my.sharepoint.com/personal/a/Documents/Microsoft Teams Chat Files/fix.patch".to_string(),
+        };
+        let files = serde_json::Value::String(
+            serde_json::to_string(&[file_property(&item, "https://contoso/s/abc")]).unwrap(),
+        );
+        let parsed = parse_files(&files);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].name, "fix.patch");
+        assert_eq!(parsed[0].kind, AttachmentKind::File);
+        assert_eq!(parsed[0].url, "https://contoso/s/abc");
+    }
+}
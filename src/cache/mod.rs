@@ -0,0 +1,421 @@
+//! Local on-disk message cache
+//!
+//! Keeps native messages per conversation under the data directory
+//! (`~/.local/share/teams-cli/messages/` on Linux), together with the chat
+//! service paging links. Chats render instantly from the cache, are refreshed
+//! with `syncState` delta fetches, and remain readable offline.
+//!
+//! People's display names are cached alongside (see [`names`]).
+
+pub mod names;
+pub mod search;
+
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::OwnedMutexGuard;
+
+use crate::api::client::TeamsClient;
+use crate::api::{self, MessagePage, NativeMessage};
+
+/// On-disk format version. Bump when the cached record gains fields that
+/// old files would silently lack; mismatched files are discarded.
+const CACHE_VERSION: u32 = 6;
+
+/// Cached messages and paging links for one conversation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedConversation {
+    version: u32,
+    pub conversation_id: String,
+    /// Link returning messages newer than the newest cached one.
+    pub sync_state: Option<String>,
+    /// Link to the page before the oldest cached message.
+    pub backward_link: Option<String>,
+    /// Messages, oldest first.
+    pub messages: Vec<NativeMessage>,
+}
+
+impl CachedConversation {
+    fn new(conversation_id: &str) -> Self {
+        Self {
+            version: CACHE_VERSION,
+            conversation_id: conversation_id.to_string(),
+            sync_state: None,
+            backward_link: None,
+            messages: Vec::new(),
+        }
+    }
+
+    /// Insert or replace messages by ID, keeping chronological order.
+    ///
+    /// Edited and deleted messages arrive with their original ID, so the
+    /// newer copy replaces the cached one.
+    pub fn merge(&mut self, messages: Vec<NativeMessage>) {
+        for msg in messages {
+            let existing = msg
+                .id()
+                .and_then(|id| self.messages.iter().position(|m| m.id() == Some(id)));
+            match existing {
+                Some(idx) => self.messages[idx] = msg,
+                None => self.messages.push(msg),
+            }
+        }
+        self.messages
+            .sort_by(|a, b| a.arrival_time().cmp(&b.arrival_time()));
+    }
+
+    /// Merge a newest page of history fetched from scratch.
+    ///
+    /// Older cached history is kept. When the page does not reach it, the
+    /// page's own backward link is followed next, so scrolling back fills
+    /// the gap (already cached messages merge by ID).
+    fn merge_newest(&mut self, messages: Vec<NativeMessage>, backward_link: Option<String>) {
+        let overlaps = messages
+            .iter()
+            .filter_map(NativeMessage::id)
+            .any(|id| self.messages.iter().any(|m| m.id() == Some(id)));
+        if !overlaps {
+            self.backward_link = backward_link;
+        }
+        self.merge(messages);
+    }
+
+    /// Displayable page for the whole cached history.
+    pub fn page(&self) -> MessagePage {
+        MessagePage {
+            messages: self.messages.iter().filter_map(api::message_info).collect(),
+            backward_link: self.backward_link.clone(),
+        }
+    }
+}
+
+/// Per-conversation message store.
+///
+/// Updates load, change and rewrite a whole conversation file, so they are
+/// serialized per conversation: a sync and a backfill running side by side
+/// would otherwise overwrite each other's messages and links.
+pub struct MessageCache {
+    dir: PathBuf,
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl MessageCache {
+    /// Open the cache in the platform data directory.
+    pub fn open() -> Result<Self> {
+        Ok(Self::at(data_dir()?.join("messages")))
+    }
+
+    fn at(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait for exclusive use of a conversation's cache file.
+    async fn lock(&self, conversation_id: &str) -> OwnedMutexGuard<()> {
+        let lock = Arc::clone(
+            self.locks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(conversation_id.to_string())
+                .or_default(),
+        );
+        lock.lock_owned().await
+    }
+
+    /// File holding a conversation. IDs like `19:abc@thread.v2` are kept
+    /// readable; anything outside a conservative set (including `:`, which
+    /// Windows does not allow) becomes `_`. Clashing names are told apart by
+    /// the ID stored in the file.
+    fn path(&self, conversation_id: &str) -> PathBuf {
+        let name: String = conversation_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '.' | '@' | '-' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", name))
+    }
+
+    /// Load a conversation, or `None` if it is not cached (or was written
+    /// by an incompatible version).
+    pub fn load(&self, conversation_id: &str) -> Result<Option<CachedConversation>> {
+        let path = self.path(conversation_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path).context("Failed to read message cache")?;
+        let conv: CachedConversation =
+            serde_json::from_str(&content).context("Failed to parse message cache")?;
+        if conv.version != CACHE_VERSION || conv.conversation_id != conversation_id {
+            return Ok(None);
+        }
+        Ok(Some(conv))
+    }
+
+    /// Load every cached conversation, skipping unreadable files.
+    pub fn conversations(&self) -> Result<Vec<CachedConversation>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut convs = Vec::new();
+        for entry in fs::read_dir(&self.dir).context("Failed to list message cache")? {
+            let path = entry.context("Failed to list message cache")?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let parsed = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|c| Ok(serde_json::from_str::<CachedConversation>(&c)?));
+            match parsed {
+                Ok(conv) if conv.version == CACHE_VERSION => convs.push(conv),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Skipping {}: {:#}", path.display(), e),
+            }
+        }
+        Ok(convs)
+    }
+
+    /// Write a conversation (atomically, via a temp file and rename).
+    pub fn save(&self, conv: &CachedConversation) -> Result<()> {
+        fs::create_dir_all(&self.dir).context("Failed to create cache directory")?;
+
+        let content = serde_json::to_string(conv).context("Failed to serialize message cache")?;
+        write_private(&self.path(&conv.conversation_id), &content)
+    }
+
+    /// Load a conversation, logging and ignoring a corrupt cache file.
+    fn load_or_new(&self, conversation_id: &str) -> CachedConversation {
+        match self.load(conversation_id) {
+            Ok(Some(conv)) => conv,
+            Ok(None) => CachedConversation::new(conversation_id),
+            Err(e) => {
+                tracing::warn!("Ignoring message cache for {}: {:#}", conversation_id, e);
+                CachedConversation::new(conversation_id)
+            }
+        }
+    }
+
+    /// Save, logging failures: a cache write must never fail a fetch.
+    fn save_or_warn(&self, conv: &CachedConversation) {
+        if let Err(e) = self.save(conv) {
+            tracing::warn!("Failed to cache {}: {:#}", conv.conversation_id, e);
+        }
+    }
+}
+
+/// The platform data directory (`~/.local/share/teams-cli` on Linux).
+fn data_dir() -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "teams-cli", "teams-cli")
+        .context("Could not determine data directory")?;
+    Ok(proj_dirs.data_dir().to_path_buf())
+}
+
+/// Replace a cache file atomically, via a temp file and rename.
+fn write_private(path: &Path, content: &str) -> Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, content).context("Failed to write cache")?;
+
+    // Cached data is as private as the tokens in the config file.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = fs::Permissions::from_mode(0o600);
+        fs::set_permissions(&tmp, perms).context("Failed to set cache permissions")?;
+    }
+
+    fs::rename(&tmp, path).context("Failed to replace cache")?;
+    Ok(())
+}
+
+/// Bring a conversation's cache up to date and return its full history.
+///
+/// Uses the cached `syncState` link for a delta fetch when there is one,
+/// falling back to the newest page of `limit` messages otherwise. The
+/// returned flag is false when the delta contained nothing new.
+pub async fn sync_conversation(
+    client: &TeamsClient,
+    cache: &MessageCache,
+    chat_id: &str,
+    limit: usize,
+) -> Result<(MessagePage, bool)> {
+    // Held across the fetch: the delta link read here must be the one
+    // replaced when the result is saved.
+    let _guard = cache.lock(chat_id).await;
+    let mut conv = cache.load_or_new(chat_id);
+
+    if let Some(sync_url) = conv.sync_state.clone() {
+        match api::fetch_native_page(client, &sync_url).await {
+            Ok(delta) => {
+                let changed = !delta.messages.is_empty();
+                // A full delta page may have skipped messages in between;
+                // page back from it rather than leave a silent gap.
+                if delta.messages.len() >= limit {
+                    conv.merge_newest(delta.messages, delta.backward_link);
+                } else {
+                    conv.merge(delta.messages);
+                }
+                conv.sync_state = delta.sync_state.or(conv.sync_state);
+                if changed {
+                    cache.save_or_warn(&conv);
+                }
+                return Ok((conv.page(), changed));
+            }
+            Err(e) => {
+                tracing::debug!("Delta sync for {} failed, refetching: {:#}", chat_id, e);
+            }
+        }
+    }
+
+    let url = api::messages_url(client, chat_id, limit);
+    let page = api::fetch_native_page(client, &url).await?;
+    conv.merge_newest(page.messages, page.backward_link);
+    conv.sync_state = page.sync_state;
+    cache.save_or_warn(&conv);
+    Ok((conv.page(), true))
+}
+
+/// Fetch the page behind `backward_link`, add it to the cache and return it.
+pub async fn backfill_conversation(
+    client: &TeamsClient,
+    cache: &MessageCache,
+    chat_id: &str,
+    backward_link: &str,
+) -> Result<MessagePage> {
+    let page = api::fetch_native_page(client, backward_link).await?;
+
+    let _guard = cache.lock(chat_id).await;
+    let mut conv = cache.load_or_new(chat_id);
+    if conv.backward_link.as_deref() == Some(backward_link) {
+        conv.merge(page.messages.clone());
+        conv.backward_link = page.backward_link.clone();
+        cache.save_or_warn(&conv);
+    }
+
+    Ok(page.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(id: &str, time: &str, content: &str) -> NativeMessage {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "originalarrivaltime": time,
+            "imdisplayname": "Alice",
+            "messagetype": "RichText/Html",
+            "content": content,
+        }))
+        .unwrap()
+    }
+
+    fn contents(conv: &CachedConversation) -> Vec<String> {
+        conv.page()
+            .messages
+            .into_iter()
+            .map(|m| m.content)
+            .collect()
+    }
+
+    #[test]
+    fn merge_orders_chronologically() {
+        let mut conv = CachedConversation::new("19:a@thread.v2");
+        conv.merge(vec![
+            msg("3", "2024-01-01T10:03:00.000Z", "three"),
+            msg("1", "2024-01-01T10:01:00.000Z", "one"),
+        ]);
+        conv.merge(vec![msg("2", "2024-01-01T10:02:00.000Z", "two")]);
+        assert_eq!(contents(&conv), ["one", "two", "three"]);
+    }
+
+    #[test]
+    fn merge_replaces_edited_message() {
+        let mut conv = CachedConversation::new("19:a@thread.v2");
+        conv.merge(vec![msg("1", "2024-01-01T10:01:00.000Z", "draft")]);
+        conv.merge(vec![msg("1", "2024-01-01T10:01:00.000Z", "final")]);
+        assert_eq!(contents(&conv), ["final"]);
+    }
+
+    #[test]
+    fn refetched_page_keeps_older_history() {
+        let mut conv = CachedConversation::new("19:a@thread.v2");
+        conv.merge(vec![
+            msg("1", "2024-01-01T10:01:00.000Z", "one"),
+            msg("2", "2024-01-01T10:02:00.000Z", "two"),
+        ]);
+        conv.backward_link = Some("before-1".to_string());
+
+        conv.merge_newest(
+            vec![
+                msg("2", "2024-01-01T10:02:00.000Z", "two"),
+                msg("3", "2024-01-01T10:03:00.000Z", "three"),
+            ],
+            Some("before-2".to_string()),
+        );
+        assert_eq!(contents(&conv), ["one", "two", "three"]);
+        assert_eq!(conv.backward_link.as_deref(), Some("before-1"));
+
+        // A page that does not reach the cache leaves a gap to page back into.
+        conv.merge_newest(
+            vec![msg("9", "2024-01-01T10:09:00.000Z", "nine")],
+            Some("before-9".to_string()),
+        );
+        assert_eq!(contents(&conv), ["one", "two", "three", "nine"]);
+        assert_eq!(conv.backward_link.as_deref(), Some("before-9"));
+    }
+
+    #[test]
+    fn file_names_are_portable() {
+        let cache = MessageCache::at(PathBuf::from("cache"));
+        let path = cache.path("19:abc/def@thread.v2");
+        assert_eq!(
+            path.file_name().and_then(|n| n.to_str()),
+            Some("19_abc_def@thread.v2.json")
+        );
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("teams-cli-cache-{}", std::process::id()));
+        let cache = MessageCache::at(dir.clone());
+
+        let mut conv = CachedConversation::new("19:a@thread.v2");
+        conv.sync_state = Some("https://example.invalid/sync".to_string());
+        conv.merge(vec![msg("1", "2024-01-01T10:01:00.000Z", "hello")]);
+        cache.save(&conv).unwrap();
+
+        let loaded = cache.load("19:a@thread.v2").unwrap().unwrap();
+        assert_eq!(loaded.sync_state, conv.sync_state);
+        assert_eq!(contents(&loaded), ["hello"]);
+        assert!(cache.load("19:other@thread.v2").unwrap().is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn updates_are_serialized_per_conversation() {
+        let cache = MessageCache::at(std::env::temp_dir());
+        let held = cache.lock("19:a@thread.v2").await;
+
+        let wait = std::time::Duration::from_millis(20);
+        assert!(tokio::time::timeout(wait, cache.lock("19:a@thread.v2"))
+            .await
+            .is_err());
+        let _other = cache.lock("19:b@thread.v2").await;
+
+        drop(held);
+        let _again = cache.lock("19:a@thread.v2").await;
+    }
+}
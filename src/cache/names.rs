@@ -0,0 +1,103 @@
+//! Display names of people, by MRI.
+//!
+//! Chat lists only carry member MRIs, so naming untitled chats needs a
+//! directory lookup per person. Names rarely change; keeping them in
+//! `names.json` under the data directory means each person is looked up
+//! once rather than on every chat list.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// On-disk format version; mismatched files are discarded.
+const NAMES_VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize)]
+struct NamesFile {
+    version: u32,
+    names: HashMap<String, String>,
+}
+
+/// Persistent MRI to display name map.
+pub struct NameCache {
+    path: PathBuf,
+    names: HashMap<String, String>,
+    dirty: bool,
+}
+
+impl NameCache {
+    /// Open the cache in the platform data directory. A missing or
+    /// unreadable file gives an empty cache.
+    pub fn open() -> Result<Self> {
+        Ok(Self::load(super::data_dir()?.join("names.json")))
+    }
+
+    fn load(path: PathBuf) -> Self {
+        let names = match fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str::<NamesFile>(&content) {
+                Ok(file) if file.version == NAMES_VERSION => file.names,
+                Ok(_) => HashMap::new(),
+                Err(e) => {
+                    tracing::warn!("Ignoring name cache {}: {:#}", path.display(), e);
+                    HashMap::new()
+                }
+            },
+            Err(_) => HashMap::new(),
+        };
+        Self {
+            path,
+            names,
+            dirty: false,
+        }
+    }
+
+    pub fn get(&self, mri: &str) -> Option<&str> {
+        self.names.get(mri).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, mri: String, name: String) {
+        if self.names.get(&mri) != Some(&name) {
+            self.names.insert(mri, name);
+            self.dirty = true;
+        }
+    }
+
+    /// Write the cache if anything was added since it was opened.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).context("Failed to create cache directory")?;
+        }
+        let file = serde_json::json!({ "version": NAMES_VERSION, "names": self.names });
+        let content = serde_json::to_string(&file).context("Failed to serialize name cache")?;
+        super::write_private(&self.path, &content)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("teams-cli-names-{}", std::process::id()));
+        let path = dir.join("names.json");
+
+        let mut cache = NameCache::load(path.clone());
+        assert_eq!(cache.get("8:orgid:a"), None);
+        cache.insert("8:orgid:a".to_string(), "Alice".to_string());
+        cache.save().unwrap();
+
+        let loaded = NameCache::load(path);
+        assert_eq!(loaded.get("8:orgid:a"), Some("Alice"));
+        assert!(!loaded.dirty);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+//! Full-text search over the local message cache.
+//!
+//! [`SearchIndex`] loads every cached conversation once and answers queries
+//! in memory, so it is cheap enough to re-run on each keystroke in the TUI.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use super::MessageCache;
+use crate::api::{self, MessageInfo, SearchHit};
+
+/// Search terms and filters.
+#[derive(Debug, Default, Clone)]
+pub struct SearchQuery {
+    /// Whitespace-separated terms; every term must match sender or content.
+    pub text: String,
+    /// Sender name substring.
+    pub from: Option<String>,
+    /// Conversation ID.
+    pub conversation_id: Option<String>,
+    /// Only messages strictly before this time.
+    pub before: Option<DateTime<Utc>>,
+    /// Only messages at or after this time.
+    pub after: Option<DateTime<Utc>>,
+}
+
+impl SearchQuery {
+    /// Whether a hit passes the sender, conversation and date filters (the
+    /// text terms are left to whoever produced the hit).
+    pub fn matches_filters(&self, hit: &SearchHit) -> bool {
+        self.filters_match(
+            &hit.conversation_id,
+            &hit.message.sender.to_lowercase(),
+            parse_time(&hit.message.timestamp),
+        )
+    }
+
+    fn filters_match(
+        &self,
+        conversation_id: &str,
+        sender_lower: &str,
+        time: Option<DateTime<Utc>>,
+    ) -> bool {
+        if matches!(self.conversation_id.as_deref(), Some(id) if id != conversation_id) {
+            return false;
+        }
+        if let Some(ref from) = self.from {
+            if !sender_lower.contains(&from.to_lowercase()) {
+                return false;
+            }
+        }
+        match (self.before, time) {
+            (Some(before), Some(t)) if t >= before => return false,
+            (Some(_), None) => return false,
+            _ => {}
+        }
+        match (self.after, time) {
+            (Some(after), Some(t)) if t < after => return false,
+            (Some(_), None) => return false,
+            _ => {}
+        }
+        true
+    }
+}
+
+/// One indexed message with pre-lowercased fields.
+struct Entry {
+    conversation_id: String,
+    message: MessageInfo,
+    time: Option<DateTime<Utc>>,
+    sender_lower: String,
+    content_lower: String,
+}
+
+/// In-memory index over all cached conversations.
+#[derive(Default)]
+pub struct SearchIndex {
+    /// Entries, newest first.
+    entries: Vec<Entry>,
+}
+
+impl SearchIndex {
+    /// Load every cached conversation into an index.
+    pub fn load(cache: &MessageCache) -> Result<Self> {
+        let mut entries = Vec::new();
+        for conv in cache.conversations()? {
+            for msg in conv.messages.iter().filter_map(api::message_info) {
+                entries.push(Entry {
+                    conversation_id: conv.conversation_id.clone(),
+                    time: parse_time(&msg.timestamp),
+                    sender_lower: msg.sender.to_lowercase(),
+                    content_lower: msg.content.to_lowercase(),
+                    message: msg,
+                });
+            }
+        }
+        entries.sort_by_key(|e| std::cmp::Reverse(e.time));
+        Ok(Self { entries })
+    }
+
+    /// Matching messages, newest first, at most `limit`.
+    pub fn search(&self, query: &SearchQuery, limit: usize) -> Vec<SearchHit> {
+        let terms: Vec<String> = query
+            .text
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+
+        self.entries
+            .iter()
+            .filter(|e| query.filters_match(&e.conversation_id, &e.sender_lower, e.time))
+            .filter(|e| {
+                terms
+                    .iter()
+                    .all(|t| e.content_lower.contains(t) || e.sender_lower.contains(t))
+            })
+            .take(limit)
+            .map(|e| SearchHit {
+                conversation_id: e.conversation_id.clone(),
+                message: e.message.clone(),
+            })
+            .collect()
+    }
+}
+
+fn parse_time(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(conv: &str, sender: &str, time: &str, content: &str) -> Entry {
+        Entry {
+            conversation_id: conv.to_string(),
+            message: MessageInfo {
+                id: time.to_string(),
+                sender: sender.to_string(),
+                timestamp: time.to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            },
+            time: parse_time(time),
+            sender_lower: sender.to_lowercase(),
+            content_lower: content.to_lowercase(),
+        }
+    }
+
+    fn index() -> SearchIndex {
+        SearchIndex {
+            entries: vec![
+                entry("19:b", "Bob", "2024-03-01T09:00:00Z", "Deploy is done"),
+                entry("19:a", "Alice", "2024-02-01T09:00:00Z", "Deploy tomorrow?"),
+                entry("19:a", "Bob", "2024-01-01T09:00:00Z", "Happy new year"),
+            ],
+        }
+    }
+
+    fn contents(hits: Vec<SearchHit>) -> Vec<String> {
+        hits.into_iter().map(|h| h.message.content).collect()
+    }
+
+    #[test]
+    fn all_terms_must_match() {
+        let query = SearchQuery {
+            text: "deploy DONE".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(contents(index().search(&query, 10)), ["Deploy is done"]);
+    }
+
+    #[test]
+    fn filters_by_sender_and_conversation() {
+        let query = SearchQuery {
+            from: Some("bob".to_string()),
+            conversation_id: Some("19:a".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(contents(index().search(&query, 10)), ["Happy new year"]);
+    }
+
+    #[test]
+    fn filters_by_date_range() {
+        let query = SearchQuery {
+            after: parse_time("2024-01-15T00:00:00Z"),
+            before: parse_time("2024-02-15T00:00:00Z"),
+            ..Default::default()
+        };
+        assert_eq!(contents(index().search(&query, 10)), ["Deploy tomorrow?"]);
+    }
+}
@@ -3,6 +3,7 @@
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -27,6 +28,9 @@ pub struct Config {
     pub recorder_token: Option<StoredToken>,
     /// Regional endpoint URLs from authsvc response (JSON stored as string for TOML compat)
     pub region_gtms: Option<String>,
+    /// Conversation aliases: name -> thread ID (`teams-cli alias`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
 }
 
 impl Config {
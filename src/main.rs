@@ -4,14 +4,17 @@
 
 mod api;
 mod auth;
+mod cache;
 mod calling;
 mod config;
 mod models;
+mod output;
 mod trouter;
 mod tui;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use output::OutputFormat;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(Parser)]
@@ -24,6 +27,10 @@ struct Cli {
     /// Enable verbose logging
     #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// Output format for command results
+    #[arg(short, long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
 }
 
 #[derive(Subcommand)]
@@ -48,26 +55,157 @@ enum Commands {
         limit: usize,
     },
 
+    /// Start and manage chats
+    Chat {
+        #[command(subcommand)]
+        action: ChatAction,
+    },
+
+    /// Name chats for use in place of their thread IDs
+    Alias {
+        #[command(subcommand)]
+        action: Option<AliasAction>,
+    },
+
+    /// List chats and channels with unread messages
+    Unread {
+        /// Maximum number of recent conversations to check
+        #[arg(short, long, default_value = "50")]
+        limit: usize,
+    },
+
     /// Read messages from a chat
     Read {
-        /// Chat thread ID (from `chats` output)
+        /// Chat: thread ID, alias, email address, chat name or team/channel
         chat_id: String,
 
         /// Maximum number of messages to show
         #[arg(short, long, default_value = "20")]
         limit: usize,
+
+        /// Fetch the entire history (follows paging links)
+        #[arg(long, conflicts_with_all = ["limit", "since"])]
+        all: bool,
+
+        /// Fetch all messages since a date (YYYY-MM-DD) or RFC 3339 time
+        #[arg(long, value_parser = parse_since, conflicts_with = "limit")]
+        since: Option<chrono::DateTime<chrono::Utc>>,
     },
 
-    /// Send a message
+    /// Search message history (local cache and server)
+    Search {
+        /// Words that must all appear in the sender or message text
+        query: String,
+
+        /// Only messages from senders whose name contains this
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only messages in this chat (thread ID, alias, email address, name...)
+        #[arg(long = "in")]
+        in_chat: Option<String>,
+
+        /// Only messages before this date (YYYY-MM-DD) or RFC 3339 time
+        #[arg(long, value_parser = parse_since)]
+        before: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Only messages on or after this date (YYYY-MM-DD) or RFC 3339 time
+        #[arg(long, value_parser = parse_since)]
+        after: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Maximum number of matches to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+
+        /// Only search the local cache (no network)
+        #[arg(long)]
+        local: bool,
+    },
+
+    /// Send a message to a chat or team channel
     Send {
-        /// Chat thread ID (from `chats` output)
+        /// Chat: thread ID, alias, email address (starts a 1:1 chat if
+        /// there is none) or chat name
+        #[arg(
+            short,
+            long,
+            required_unless_present = "channel",
+            conflicts_with = "channel"
+        )]
+        to: Option<String>,
+
+        /// Team channel as <team>/<channel> (names from `teams` output) or a channel ID
         #[arg(short, long)]
-        to: String,
+        channel: Option<String>,
+
+        /// Reply to this message: quoted in chats, in its thread in channels
+        /// (give the thread's root message ID)
+        #[arg(long, conflicts_with_all = ["subject", "attach"])]
+        reply_to: Option<String>,
+
+        /// Subject line for a new channel post
+        #[arg(long, requires = "channel")]
+        subject: Option<String>,
+
+        /// Upload a file and send it (images are shown inline)
+        #[arg(long, requires = "to")]
+        attach: Option<std::path::PathBuf>,
+
+        /// Message content (optional with --attach)
+        #[arg(required_unless_present = "attach")]
+        message: Option<String>,
+    },
 
-        /// Message content
+    /// Edit one of your messages
+    Edit {
+        /// Chat: thread ID, alias, email address, chat name or team/channel
+        chat_id: String,
+
+        /// Message ID (from `read --output json`)
+        message_id: String,
+
+        /// New message content
         message: String,
     },
 
+    /// Delete one of your messages
+    Delete {
+        /// Chat: thread ID, alias, email address, chat name or team/channel
+        chat_id: String,
+
+        /// Message ID (from `read --output json`)
+        message_id: String,
+    },
+
+    /// Add or remove a reaction on a message
+    React {
+        /// Chat: thread ID, alias, email address, chat name or team/channel
+        chat_id: String,
+
+        /// Message ID (from `read --output json`)
+        message_id: String,
+
+        /// Reaction: like, heart, laugh, surprised, sad, angry (or the emoji)
+        emoji: String,
+
+        /// Remove your reaction instead of adding it
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// Download the files and images attached to a message
+    Download {
+        /// Chat: thread ID, alias, email address, chat name or team/channel
+        chat_id: String,
+
+        /// Message ID (from `read --output json`)
+        message_id: String,
+
+        /// Directory to save into (default: current directory)
+        #[arg(long)]
+        out: Option<std::path::PathBuf>,
+    },
+
     /// List joined teams and their channels
     Teams,
 
@@ -82,6 +220,10 @@ enum Commands {
         /// New status: available, busy, dnd, away, offline
         #[arg(short, long)]
         set: Option<String>,
+
+        /// Show this person's presence instead (email address or UPN)
+        #[arg(short, long, value_name = "EMAIL", conflicts_with = "set")]
+        user: Option<String>,
     },
 
     /// Place a test call to yourself (self-call)
@@ -98,7 +240,8 @@ enum Commands {
         #[arg(long)]
         echo: bool,
 
-        /// 1:1 chat thread ID to call (e.g., 19:guid1_guid2@unq.gbl.spaces)
+        /// 1:1 chat to call: thread ID (e.g., 19:guid1_guid2@unq.gbl.spaces),
+        /// alias or the other person's email address
         #[arg(long)]
         thread: Option<String>,
 
@@ -127,6 +270,82 @@ enum Commands {
     Tui,
 }
 
+#[derive(Subcommand)]
+enum ChatAction {
+    /// Start a 1:1 chat, or a group chat with several people
+    New {
+        /// Email address or UPN of a person to add (repeatable)
+        #[arg(long = "with", value_name = "EMAIL", required = true)]
+        with: Vec<String>,
+
+        /// Topic (group chats only)
+        #[arg(long)]
+        topic: Option<String>,
+    },
+
+    /// List the members of a chat and their roles
+    Members {
+        /// Chat: thread ID, alias, email address or chat name
+        chat_id: String,
+    },
+
+    /// Add people to a group chat
+    Add {
+        /// Chat: thread ID, alias, email address or chat name
+        chat_id: String,
+
+        /// Email address or UPN of a person to add (repeatable)
+        #[arg(long = "user", value_name = "EMAIL", required = true)]
+        users: Vec<String>,
+    },
+
+    /// Remove people from a group chat
+    Remove {
+        /// Chat: thread ID, alias, email address or chat name
+        chat_id: String,
+
+        /// Email address or UPN of a person to remove (repeatable)
+        #[arg(long = "user", value_name = "EMAIL", required = true)]
+        users: Vec<String>,
+    },
+
+    /// Change the topic of a group chat
+    Rename {
+        /// Chat: thread ID, alias, email address or chat name
+        chat_id: String,
+
+        /// New topic
+        topic: String,
+    },
+
+    /// Leave a group chat
+    Leave {
+        /// Chat: thread ID, alias, email address or chat name
+        chat_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AliasAction {
+    /// List aliases (the default)
+    List,
+
+    /// Save an alias for a chat or channel
+    Set {
+        /// Alias name
+        name: String,
+
+        /// Chat: thread ID, email address, chat name or team/channel
+        target: String,
+    },
+
+    /// Delete an alias
+    Remove {
+        /// Alias name
+        name: String,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -177,21 +396,157 @@ async fn main() -> Result<()> {
             auth::status().await?;
         }
         Commands::Teams => {
-            api::list_teams().await?;
+            api::list_teams(cli.output).await?;
         }
         Commands::Whoami => {
-            api::whoami().await?;
+            api::whoami(cli.output).await?;
         }
         Commands::Chats { limit } => {
             tracing::info!("Fetching chats...");
-            api::list_chats(limit).await?;
+            api::list_chats(limit, cli.output).await?;
+        }
+        Commands::Chat { action } => match action {
+            ChatAction::New { with, topic } => {
+                api::new_chat(&with, topic.as_deref()).await?;
+            }
+            ChatAction::Members { chat_id } => {
+                let chat_id = api::resolve_conversation(&chat_id).await?;
+                api::chat_members(&chat_id, cli.output).await?;
+            }
+            ChatAction::Add { chat_id, users } => {
+                let chat_id = api::resolve_conversation(&chat_id).await?;
+                api::add_chat_members(&chat_id, &users).await?;
+            }
+            ChatAction::Remove { chat_id, users } => {
+                let chat_id = api::resolve_conversation(&chat_id).await?;
+                api::remove_chat_members(&chat_id, &users).await?;
+            }
+            ChatAction::Rename { chat_id, topic } => {
+                let chat_id = api::resolve_conversation(&chat_id).await?;
+                api::rename_chat(&chat_id, &topic).await?;
+            }
+            ChatAction::Leave { chat_id } => {
+                let chat_id = api::resolve_conversation(&chat_id).await?;
+                api::leave_chat(&chat_id).await?;
+            }
+        },
+        Commands::Alias { action } => match action.unwrap_or(AliasAction::List) {
+            AliasAction::List => {
+                api::list_aliases(cli.output).await?;
+            }
+            AliasAction::Set { name, target } => {
+                api::set_alias(&name, &target).await?;
+            }
+            AliasAction::Remove { name } => {
+                api::remove_alias(&name).await?;
+            }
+        },
+        Commands::Unread { limit } => {
+            tracing::info!("Counting unread messages...");
+            api::list_unread(limit, cli.output).await?;
         }
-        Commands::Read { chat_id, limit } => {
-            api::read_messages(&chat_id, limit).await?;
+        Commands::Read {
+            chat_id,
+            limit,
+            all,
+            since,
+        } => {
+            let range = match (all, since) {
+                (true, _) => api::HistoryRange::All,
+                (false, Some(since)) => api::HistoryRange::Since(since),
+                (false, None) => api::HistoryRange::Latest(limit),
+            };
+            let chat_id = api::resolve_conversation(&chat_id).await?;
+            api::read_messages(&chat_id, range, cli.output).await?;
         }
-        Commands::Send { to, message } => {
+        Commands::Search {
+            query,
+            from,
+            in_chat,
+            before,
+            after,
+            limit,
+            local,
+        } => {
+            let conversation_id = match in_chat {
+                Some(chat) => Some(api::resolve_conversation(&chat).await?),
+                None => None,
+            };
+            let query = cache::search::SearchQuery {
+                text: query,
+                from,
+                conversation_id,
+                before,
+                after,
+            };
+            api::search_messages(&query, limit, local, cli.output).await?;
+        }
+        Commands::Send {
+            to,
+            channel,
+            reply_to,
+            subject,
+            attach,
+            message,
+        } => {
+            let to = match to {
+                Some(to) => Some(api::resolve_or_start_conversation(&to).await?),
+                None => None,
+            };
             tracing::info!("Sending message...");
-            api::send_message(&to, &message).await?;
+            match (to, channel, attach) {
+                (Some(to), _, Some(path)) => api::send_file(&to, &path, message.as_deref()).await?,
+                (Some(to), _, None) => {
+                    api::send_message(
+                        &to,
+                        message.as_deref().unwrap_or_default(),
+                        reply_to.as_deref(),
+                    )
+                    .await?
+                }
+                (None, Some(channel), _) => {
+                    api::send_channel_message(
+                        &channel,
+                        message.as_deref().unwrap_or_default(),
+                        subject.as_deref(),
+                        reply_to.as_deref(),
+                    )
+                    .await?
+                }
+                (None, None, _) => unreachable!("clap requires --to or --channel"),
+            }
+        }
+        Commands::Edit {
+            chat_id,
+            message_id,
+            message,
+        } => {
+            let chat_id = api::resolve_conversation(&chat_id).await?;
+            api::edit_message(&chat_id, &message_id, &message).await?;
+        }
+        Commands::Delete {
+            chat_id,
+            message_id,
+        } => {
+            let chat_id = api::resolve_conversation(&chat_id).await?;
+            api::delete_message(&chat_id, &message_id).await?;
+        }
+        Commands::React {
+            chat_id,
+            message_id,
+            emoji,
+            remove,
+        } => {
+            let chat_id = api::resolve_conversation(&chat_id).await?;
+            api::react(&chat_id, &message_id, &emoji, remove).await?;
+        }
+        Commands::Download {
+            chat_id,
+            message_id,
+            out,
+        } => {
+            let chat_id = api::resolve_conversation(&chat_id).await?;
+            api::download(&chat_id, &message_id, out.as_deref()).await?;
         }
         Commands::Trouter => {
             trouter::connect_and_run().await?;
@@ -205,6 +560,10 @@ async fn main() -> Result<()> {
             display,
             tone,
         } => {
+            let thread = match thread {
+                Some(thread) => Some(api::resolve_conversation(&thread).await?),
+                None => None,
+            };
             calling::call_test::run_call_test(
                 duration, record, echo, thread, camera, display, tone,
             )
@@ -218,13 +577,16 @@ async fn main() -> Result<()> {
         Commands::CamTest => {
             calling::camera::cam_test()?;
         }
-        Commands::Presence { set } => match set {
-            Some(status) => {
+        Commands::Presence { set, user } => match (set, user) {
+            (Some(status), _) => {
                 tracing::info!("Setting presence to {}...", status);
                 api::set_presence(&status).await?;
             }
-            None => {
-                api::get_presence().await?;
+            (None, Some(email)) => {
+                api::get_user_presence(&email, cli.output).await?;
+            }
+            (None, None) => {
+                api::get_presence(cli.output).await?;
             }
         },
         // TUI is handled above with early return.
@@ -233,3 +595,14 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Parse a `--since`/`--before`/`--after` value: a date (midnight UTC) or a
+/// full RFC 3339 time.
+fn parse_since(s: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    if let Ok(t) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&chrono::Utc));
+    }
+    let date = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("expected YYYY-MM-DD or RFC 3339 time, got '{}'", s))?;
+    Ok(date.and_time(chrono::NaiveTime::MIN).and_utc())
+}
@@ -0,0 +1,363 @@
+//! Output formatting for CLI subcommands
+//!
+//! Every subcommand that prints data routes it through [`print_list`] or
+//! [`print_one`], so `--output json|ndjson|table|tsv` works uniformly. The
+//! default `text` format keeps each command's hand-written layout.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::api::{
+    AliasInfo, ChatInfo, MemberInfo, MessageInfo, PresenceInfo, SearchHit, TeamInfo, UnreadInfo,
+    UserInfo,
+};
+
+/// Output format selected with the global `--output` flag.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text (default)
+    #[default]
+    Text,
+    /// A single JSON document (arrays for lists)
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Aligned columns with a header row
+    Table,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+/// A value that can be printed in every output format.
+///
+/// JSON formats use the `Serialize` impl; table and TSV use
+/// [`columns`](Record::columns) and [`rows`](Record::rows).
+pub trait Record: Serialize {
+    /// Column headers for table/TSV output.
+    fn columns() -> &'static [&'static str];
+
+    /// Table/TSV rows for this value (usually one; nested values may
+    /// flatten into several).
+    fn rows(&self) -> Vec<Vec<String>>;
+}
+
+/// Print a list of records, using `text` for the default format.
+pub fn print_list<T: Record>(
+    format: OutputFormat,
+    items: &[T],
+    text: impl FnOnce(&[T]),
+) -> Result<()> {
+    match format {
+        OutputFormat::Text => text(items),
+        OutputFormat::Json => println!("{}", to_json(&items)?),
+        OutputFormat::Ndjson => {
+            for item in items {
+                println!(
+                    "{}",
+                    serde_json::to_string(item).context("Failed to serialize output")?
+                );
+            }
+        }
+        OutputFormat::Table => print_table(T::columns(), items.iter().flat_map(Record::rows)),
+        OutputFormat::Tsv => print_tsv(T::columns(), items.iter().flat_map(Record::rows)),
+    }
+    Ok(())
+}
+
+/// Print a single record, using `text` for the default format.
+pub fn print_one<T: Record>(format: OutputFormat, item: &T, text: impl FnOnce(&T)) -> Result<()> {
+    match format {
+        OutputFormat::Text => text(item),
+        OutputFormat::Json => println!("{}", to_json(item)?),
+        OutputFormat::Ndjson => {
+            println!(
+                "{}",
+                serde_json::to_string(item).context("Failed to serialize output")?
+            )
+        }
+        OutputFormat::Table => print_table(T::columns(), item.rows()),
+        OutputFormat::Tsv => print_tsv(T::columns(), item.rows()),
+    }
+    Ok(())
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    serde_json::to_string_pretty(value).context("Failed to serialize output")
+}
+
+fn print_table(columns: &[&str], rows: impl IntoIterator<Item = Vec<String>>) {
+    for line in table_lines(columns, rows) {
+        println!("{}", line);
+    }
+}
+
+fn print_tsv(columns: &[&str], rows: impl IntoIterator<Item = Vec<String>>) {
+    for line in tsv_lines(columns, rows) {
+        println!("{}", line);
+    }
+}
+
+/// Render rows as space-padded columns. Cells are flattened to one line.
+fn table_lines(columns: &[&str], rows: impl IntoIterator<Item = Vec<String>>) -> Vec<String> {
+    let header: Vec<String> = columns.iter().map(|c| c.to_uppercase()).collect();
+    let rows: Vec<Vec<String>> = std::iter::once(header)
+        .chain(
+            rows.into_iter()
+                .map(|r| r.iter().map(|c| single_line(c)).collect()),
+        )
+        .collect();
+
+    let mut widths = vec![0; columns.len()];
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(unicode_width::UnicodeWidthStr::width(cell.as_str()));
+        }
+    }
+
+    rows.iter()
+        .map(|row| {
+            let mut line = String::new();
+            for (i, cell) in row.iter().enumerate() {
+                if i + 1 == row.len() {
+                    line.push_str(cell);
+                } else {
+                    let pad = widths[i] - unicode_width::UnicodeWidthStr::width(cell.as_str());
+                    line.push_str(cell);
+                    line.push_str(&" ".repeat(pad + 2));
+                }
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Render rows as tab-separated values. Tabs and newlines inside cells are
+/// replaced by spaces so every record stays on one line.
+fn tsv_lines(columns: &[&str], rows: impl IntoIterator<Item = Vec<String>>) -> Vec<String> {
+    std::iter::once(columns.join("\t"))
+        .chain(rows.into_iter().map(|row| {
+            row.iter()
+                .map(|c| single_line(c))
+                .collect::<Vec<_>>()
+                .join("\t")
+        }))
+        .collect()
+}
+
+fn single_line(cell: &str) -> String {
+    cell.replace(['\t', '\n', '\r'], " ")
+}
+
+fn opt(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Record implementations
+// ---------------------------------------------------------------------------
+
+impl Record for ChatInfo {
+    fn columns() -> &'static [&'static str] {
+        &[
+            "id",
+            "name",
+            "is_group",
+            "last_message_time",
+            "last_message_sender",
+            "last_message_preview",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.is_group.to_string(),
+            opt(&self.last_message_time),
+            opt(&self.last_message_sender),
+            opt(&self.last_message_preview),
+        ]]
+    }
+}
+
+impl Record for MessageInfo {
+    fn columns() -> &'static [&'static str] {
+        &["id", "timestamp", "sender", "content"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.id.clone(),
+            self.timestamp.clone(),
+            self.sender.clone(),
+            self.content.clone(),
+        ]]
+    }
+}
+
+impl Record for TeamInfo {
+    fn columns() -> &'static [&'static str] {
+        &["team_id", "team_name", "channel_id", "channel_name"]
+    }
+
+    /// One row per channel (or a single row for a team without channels).
+    fn rows(&self) -> Vec<Vec<String>> {
+        if self.channels.is_empty() {
+            return vec![vec![
+                self.id.clone(),
+                self.name.clone(),
+                String::new(),
+                String::new(),
+            ]];
+        }
+        self.channels
+            .iter()
+            .map(|ch| {
+                vec![
+                    self.id.clone(),
+                    self.name.clone(),
+                    ch.id.clone(),
+                    ch.name.clone(),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl Record for UserInfo {
+    fn columns() -> &'static [&'static str] {
+        &["id", "display_name", "mail"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.id.clone(),
+            self.display_name.clone(),
+            opt(&self.mail),
+        ]]
+    }
+}
+
+impl Record for PresenceInfo {
+    fn columns() -> &'static [&'static str] {
+        &["id", "availability", "activity"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.id.clone(),
+            self.availability.clone(),
+            self.activity.clone(),
+        ]]
+    }
+}
+
+impl Record for MemberInfo {
+    fn columns() -> &'static [&'static str] {
+        &["mri", "display_name", "email", "role"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.mri.clone(),
+            self.display_name.clone(),
+            opt(&self.email),
+            self.role.as_str().to_string(),
+        ]]
+    }
+}
+
+impl Record for UnreadInfo {
+    fn columns() -> &'static [&'static str] {
+        &["id", "name", "unread", "more"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.unread.to_string(),
+            self.more.to_string(),
+        ]]
+    }
+}
+
+impl Record for AliasInfo {
+    fn columns() -> &'static [&'static str] {
+        &["name", "id"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![vec![self.name.clone(), self.id.clone()]]
+    }
+}
+
+impl Record for SearchHit {
+    fn columns() -> &'static [&'static str] {
+        &["conversation_id", "id", "timestamp", "sender", "content"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.conversation_id.clone(),
+            self.message.id.clone(),
+            self.message.timestamp.clone(),
+            self.message.sender.clone(),
+            self.message.content.clone(),
+        ]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_pads_columns_to_widest_cell() {
+        let lines = table_lines(
+            &["id", "name"],
+            vec![
+                vec!["1".to_string(), "Alice".to_string()],
+                vec!["1234".to_string(), "Bob".to_string()],
+            ],
+        );
+        assert_eq!(lines, ["ID    NAME", "1     Alice", "1234  Bob"]);
+    }
+
+    #[test]
+    fn tsv_keeps_records_on_one_line() {
+        let lines = tsv_lines(
+            &["id", "content"],
+            vec![vec!["1".to_string(), "two\nlines\tand tab".to_string()]],
+        );
+        assert_eq!(lines, ["id\tcontent", "1\ttwo lines and tab"]);
+    }
+
+    #[test]
+    fn search_hit_serializes_with_stable_names() {
+        let hit = SearchHit {
+            conversation_id: "19:a@thread.v2".to_string(),
+            message: MessageInfo {
+                id: "1".to_string(),
+                sender: "Alice".to_string(),
+                timestamp: "2024-01-01T00:00:00Z".to_string(),
+                content: "hi".to_string(),
+                ..Default::default()
+            },
+        };
+        let value = serde_json::to_value(&hit).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "conversation_id": "19:a@thread.v2",
+                "message": {
+                    "id": "1",
+                    "sender": "Alice",
+                    "timestamp": "2024-01-01T00:00:00Z",
+                    "content": "hi",
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Embeddable Trouter client with reconnection and event fan-out.
+//!
+//! [`TrouterClient::start`] spawns a background task that owns the
+//! negotiate / connect / heartbeat / re-register cycle and reconnects with
+//! exponential backoff. Subscribers receive decoded [`TrouterEvent`]s (or raw
+//! frames) over broadcast channels and can watch [`ConnectionState`] changes.
+//! Presence changes are pushed for the users given to
+//! [`TrouterClient::watch_presence`], resubscribed on every connect.
+
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+
+use super::events::{self, TrouterEvent};
+use super::{presence, registrar, session, websocket};
+use crate::config::Config;
+
+/// Capacity of the event and raw-frame broadcast channels.
+///
+/// Slow subscribers that fall further behind than this see `Lagged` errors
+/// rather than stalling the connection.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Connection state of a [`TrouterClient`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Negotiating the first session.
+    Connecting,
+    /// WebSocket connected and handshake received.
+    Connected,
+    /// Disconnected; the next attempt starts after `backoff`.
+    Reconnecting { attempt: u32, backoff: Duration },
+    /// Shut down (cancelled). Terminal state.
+    Stopped,
+}
+
+/// Reason the inner connection loop exited.
+enum DisconnectReason {
+    /// Clean shutdown (cancelled). Do not reconnect.
+    Shutdown,
+    /// Error or server-initiated close. Should reconnect.
+    Error(anyhow::Error),
+}
+
+/// State shared between the handle and the background task.
+struct Shared {
+    events: broadcast::Sender<TrouterEvent>,
+    frames: broadcast::Sender<Arc<str>>,
+    state: watch::Sender<ConnectionState>,
+    /// MRIs whose presence changes we subscribe to.
+    presence: watch::Sender<Vec<String>>,
+    cancel: CancellationToken,
+}
+
+impl Shared {
+    fn set_state(&self, state: ConnectionState) {
+        self.state.send_replace(state);
+    }
+
+    /// Fan out one received frame: raw to frame subscribers, decoded to
+    /// event subscribers. Send errors just mean nobody is subscribed.
+    fn deliver(&self, frame: String) {
+        if let Some(event) = events::decode_frame(&frame) {
+            let _ = self.events.send(event);
+        }
+        let _ = self.frames.send(Arc::from(frame));
+    }
+}
+
+/// Handle to a background Trouter connection.
+pub struct TrouterClient {
+    shared: Arc<Shared>,
+    task: JoinHandle<()>,
+}
+
+impl TrouterClient {
+    /// Spawn the background connection task.
+    ///
+    /// Tokens are reloaded from config on every (re)connect attempt.
+    pub fn start() -> Self {
+        let (events, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let (frames, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let (state, _) = watch::channel(ConnectionState::Connecting);
+        let (presence, _) = watch::channel(Vec::new());
+        let shared = Arc::new(Shared {
+            events,
+            frames,
+            state,
+            presence,
+            cancel: CancellationToken::new(),
+        });
+
+        let task = tokio::spawn(run(Arc::clone(&shared)));
+        Self { shared, task }
+    }
+
+    /// Subscribe to decoded events received from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<TrouterEvent> {
+        self.shared.events.subscribe()
+    }
+
+    /// Subscribe to every raw socket.io text frame received from now on.
+    pub fn subscribe_frames(&self) -> broadcast::Receiver<Arc<str>> {
+        self.shared.frames.subscribe()
+    }
+
+    /// Watch connection state changes.
+    pub fn state(&self) -> watch::Receiver<ConnectionState> {
+        self.shared.state.subscribe()
+    }
+
+    /// Receive presence changes for exactly these users (MRIs) from now on.
+    pub fn watch_presence(&self, mris: Vec<String>) {
+        self.shared.presence.send_if_modified(|current| {
+            let changed = *current != mris;
+            *current = mris;
+            changed
+        });
+    }
+
+    /// Cancel the connection and wait for the background task to finish.
+    pub async fn shutdown(self) {
+        self.shared.cancel.cancel();
+        if let Err(e) = self.task.await {
+            tracing::warn!("Trouter task panicked: {}", e);
+        }
+    }
+}
+
+/// Reconnection loop: runs sessions until cancelled.
+///
+/// On transient errors or server-initiated disconnects, reconnects with
+/// exponential backoff (1s, 2s, 4s, ... capped at 64s). A session that stayed
+/// up past the stability threshold resets the backoff.
+async fn run(shared: Arc<Shared>) {
+    let mut backoff = 1u64;
+    let mut attempt = 0u32;
+
+    loop {
+        let delay = match connect_and_run_inner(&shared).await {
+            Ok(DisconnectReason::Shutdown) => break,
+            Ok(DisconnectReason::Error(e)) => {
+                // Connection was stable (>60s), reset backoff before reconnecting.
+                backoff = 1;
+                attempt = 1;
+                tracing::warn!(
+                    "Trouter disconnected after stable session: {:#}. Reconnecting in 1s...",
+                    e,
+                );
+                1
+            }
+            Err(e) => {
+                attempt += 1;
+                tracing::warn!(
+                    "Trouter disconnected: {:#}. Reconnecting in {}s...",
+                    e,
+                    backoff
+                );
+                let delay = backoff;
+                backoff = (backoff * 2).min(64);
+                delay
+            }
+        };
+
+        shared.set_state(ConnectionState::Reconnecting {
+            attempt,
+            backoff: Duration::from_secs(delay),
+        });
+
+        tokio::select! {
+            _ = time::sleep(Duration::from_secs(delay)) => {}
+            _ = shared.cancel.cancelled() => break,
+        }
+    }
+
+    shared.set_state(ConnectionState::Stopped);
+}
+
+/// Run one full Trouter session: negotiate, connect, event loop.
+///
+/// Returns `DisconnectReason::Shutdown` once the cancellation token fires,
+/// or `DisconnectReason::Error` when the connection should be retried.
+async fn connect_and_run_inner(shared: &Shared) -> Result<DisconnectReason> {
+    // Reload config each attempt so we pick up refreshed tokens.
+    let config = Config::load().context("Failed to load config")?;
+
+    let skype_token = config
+        .get_skype_token()
+        .context("No skype token found. Run `teams-cli login` first.")?;
+    anyhow::ensure!(
+        !skype_token.is_expired(),
+        "Skype token expired. Run `teams-cli login` to refresh."
+    );
+
+    let skype_token_str = &skype_token.token;
+    let http = reqwest::Client::new();
+
+    // 1. Negotiate session (returns session info + epid)
+    let (session, epid) = session::negotiate(&http, skype_token_str).await?;
+
+    // 2. Get socket.io session ID (authenticated via X-Skypetoken header)
+    let session_id = session::get_session_id(&http, &session, skype_token_str, &epid).await?;
+
+    // 3. Connect WebSocket (auth is via session ID in URL, no headers needed)
+    let mut ws = websocket::TrouterSocket::connect(&session, &session_id, &epid).await?;
+
+    // 4. Wait for handshake frame (1::)
+    let frame = ws
+        .recv_frame()
+        .await?
+        .context("Connection closed before handshake")?;
+
+    if !frame.starts_with("1::") {
+        tracing::warn!("Expected 1:: handshake, got: {}", frame);
+    } else {
+        tracing::info!("Received handshake frame");
+    }
+
+    // 5. Register with registrar
+    let registrar_ttl_secs: u64 = 86400;
+    if let Some(ref reg_url) = session.registrar_url {
+        if let Err(e) = registrar::register(&http, skype_token_str, reg_url, &session.surl).await {
+            tracing::warn!("Initial registrar registration failed: {:#}", e);
+        }
+    }
+
+    // Subscriptions belong to the endpoint, so renew them for this session.
+    let mut presence_rx = shared.presence.subscribe();
+    subscribe_presence(
+        &http,
+        skype_token_str,
+        &session.surl,
+        &epid,
+        presence_rx.borrow_and_update().clone(),
+    );
+
+    // 6. Event loop: recv frames, send heartbeat, re-register before TTL,
+    //    force reconnect after session max age.
+    let connected_at = Instant::now();
+    let mut heartbeat = time::interval(Duration::from_secs(30));
+    heartbeat.tick().await; // skip first immediate tick
+
+    // Re-register 30s before TTL expires.
+    let re_register_interval = Duration::from_secs(registrar_ttl_secs.saturating_sub(30));
+    let mut re_register_deadline = Box::pin(time::sleep(re_register_interval));
+
+    // Force full reconnect after 1 hour to refresh the session.
+    // The session TTL is typically ~589000s but rotating more frequently
+    // keeps tokens and registrations fresh.
+    let session_max_age = Duration::from_secs(3600);
+    let mut session_deadline = Box::pin(time::sleep(session_max_age));
+
+    // Stability threshold: reset backoff after 60s of successful connection.
+    // We communicate this via the return value — the caller checks timing.
+    let stability_threshold = Duration::from_secs(60);
+
+    tracing::info!("Trouter connected");
+    shared.set_state(ConnectionState::Connected);
+
+    let disconnect_reason = loop {
+        tokio::select! {
+            frame = ws.recv_frame() => {
+                match frame {
+                    Ok(Some(text)) => shared.deliver(text),
+                    Ok(None) => {
+                        break DisconnectReason::Error(anyhow::anyhow!("WebSocket closed by server"));
+                    }
+                    Err(e) => {
+                        break DisconnectReason::Error(e.context("WebSocket recv error"));
+                    }
+                }
+            }
+            _ = heartbeat.tick() => {
+                if let Err(e) = ws.send_text("2::").await {
+                    break DisconnectReason::Error(e.context("Heartbeat send failed"));
+                }
+            }
+            _ = &mut re_register_deadline => {
+                tracing::info!("Re-registering with registrar (TTL refresh)");
+                if let Some(ref reg_url) = session.registrar_url {
+                    let http2 = http.clone();
+                    let tok = skype_token_str.to_string();
+                    let surl = session.surl.clone();
+                    let reg = reg_url.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = registrar::register(&http2, &tok, &reg, &surl).await {
+                            tracing::warn!("Re-registration failed: {:#}", e);
+                        }
+                    });
+                }
+                // Reset the timer for another cycle.
+                re_register_deadline = Box::pin(time::sleep(re_register_interval));
+            }
+            changed = presence_rx.changed() => {
+                if changed.is_ok() {
+                    let mris = presence_rx.borrow_and_update().clone();
+                    subscribe_presence(&http, skype_token_str, &session.surl, &epid, mris);
+                }
+            }
+            _ = &mut session_deadline => {
+                tracing::info!("Session max age reached (1h), forcing reconnect for fresh session");
+                break DisconnectReason::Error(anyhow::anyhow!("Session max age reached"));
+            }
+            _ = shared.cancel.cancelled() => {
+                tracing::info!("Trouter shutting down");
+                break DisconnectReason::Shutdown;
+            }
+        }
+    };
+
+    // If we were connected long enough, signal stability so caller resets backoff.
+    // We do this by returning Ok (the caller pattern-matches on it).
+    if connected_at.elapsed() >= stability_threshold {
+        // Reset backoff indirectly: caller sees Ok and resets.
+        // But we still need to convey the reason.
+        // Use Ok for both shutdown and stable-error cases.
+        return Ok(disconnect_reason);
+    }
+
+    match disconnect_reason {
+        DisconnectReason::Shutdown => Ok(DisconnectReason::Shutdown),
+        DisconnectReason::Error(e) => Err(e),
+    }
+}
+
+/// Subscribe to presence changes of `mris` in the background, with the
+/// session's Skype token. Failures are logged: presence just stops updating.
+fn subscribe_presence(
+    http: &reqwest::Client,
+    skype_token: &str,
+    surl: &str,
+    epid: &str,
+    mris: Vec<String>,
+) {
+    if mris.is_empty() {
+        return;
+    }
+    let http = http.clone();
+    let token = skype_token.to_string();
+    let surl = surl.to_string();
+    let epid = epid.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = presence::subscribe(&http, &token, &surl, &epid, &mris).await {
+            tracing::warn!("Presence subscription failed: {:#}", e);
+        }
+    });
+}
@@ -0,0 +1,514 @@
+//! Decoding of Trouter push frames into typed events.
+//!
+//! Notifications arrive as HTTP-like requests tunnelled over the socket.io
+//! connection, either directly in `3:::` frames or wrapped in a `5:` event
+//! frame (`{"name": ..., "args": [request]}`). The request `url` identifies
+//! the registered endpoint (messaging, presence, calling) and its `body` is
+//! usually stringified JSON.
+
+use serde::Deserialize;
+
+use crate::api::{self, NativeMessage};
+use crate::calling::{self, CallNotification};
+
+/// An event decoded from a Trouter frame.
+#[derive(Debug, Clone)]
+pub enum TrouterEvent {
+    /// A new message was posted to a conversation.
+    NewMessage {
+        conversation_id: String,
+        message: api::MessageInfo,
+    },
+    /// An existing message was edited; `message` carries the new content.
+    MessageEdited {
+        conversation_id: String,
+        message: api::MessageInfo,
+    },
+    /// A message was deleted.
+    MessageDeleted {
+        conversation_id: String,
+        message_id: String,
+    },
+    /// Someone started (`stopped == false`) or stopped typing.
+    Typing {
+        conversation_id: String,
+        /// Display name if known, otherwise the sender MRI.
+        user: String,
+        stopped: bool,
+    },
+    /// A conversation's consumption horizon (read position) moved.
+    ReadReceipt {
+        conversation_id: String,
+        /// Raw `consumptionhorizon` value: `"{arrival_ms};{arrival_ms};{msg_id}"`.
+        horizon: String,
+    },
+    /// One or more users changed presence.
+    PresenceChanged(Vec<PresenceUpdate>),
+    /// Thread metadata (topic, members, ...) changed.
+    ThreadUpdate {
+        conversation_id: String,
+        /// New topic, when the update carries one.
+        topic: Option<String>,
+    },
+    /// Incoming call invitation.
+    CallInvite(Box<CallNotification>),
+    /// A call was ended by the remote side.
+    CallEnded { call_id: Option<String> },
+    /// A well-formed notification we don't decode (yet).
+    Unknown {
+        /// Request URL of the notification, if any.
+        url: Option<String>,
+        body: serde_json::Value,
+    },
+}
+
+/// Presence change for a single user.
+#[derive(Debug, Clone)]
+pub struct PresenceUpdate {
+    /// User MRI (e.g. `8:orgid:{guid}`).
+    pub mri: String,
+    /// Availability, e.g. "Available", "Busy", "Away", "Offline".
+    pub availability: String,
+    /// Activity, e.g. "InACall", when provided.
+    pub activity: Option<String>,
+}
+
+/// HTTP-like request carried inside a Trouter frame.
+#[derive(Debug, Deserialize)]
+struct TrouterRequest {
+    url: Option<String>,
+    body: Option<serde_json::Value>,
+}
+
+/// Chat service notification carried in the messaging request body.
+#[derive(Debug, Deserialize)]
+struct EventMessage {
+    #[serde(rename = "resourceType")]
+    resource_type: Option<String>,
+    #[serde(rename = "resourceLink")]
+    resource_link: Option<String>,
+    resource: Option<serde_json::Value>,
+}
+
+/// Decode a raw socket.io frame into an event.
+///
+/// Returns `None` for control frames (handshake, heartbeat, acks) and for
+/// payloads that are not notification requests. Notifications that are not
+/// recognised become [`TrouterEvent::Unknown`]; notifications that are
+/// recognised but carry nothing displayable (e.g. system messages) yield `None`.
+pub fn decode_frame(frame: &str) -> Option<TrouterEvent> {
+    let request = frame_request(frame)?;
+    let body = parse_body(request.body?)?;
+    decode_request(request.url.as_deref(), body)
+}
+
+/// Route a notification by endpoint URL and body shape.
+fn decode_request(url: Option<&str>, body: serde_json::Value) -> Option<TrouterEvent> {
+    let url_str = url.unwrap_or("");
+
+    if url_str.contains("NGCallManagerWin") || body.get("callInvitation").is_some() {
+        if let Some(event) = decode_call(&body) {
+            return Some(event);
+        }
+    }
+
+    if let Some(updates) = decode_unified_presence(&body) {
+        return Some(TrouterEvent::PresenceChanged(updates));
+    }
+
+    if body.get("resourceType").is_some() {
+        let event: EventMessage = serde_json::from_value(body.clone()).ok()?;
+        return decode_event_message(event, url, body);
+    }
+
+    Some(TrouterEvent::Unknown {
+        url: url.map(String::from),
+        body,
+    })
+}
+
+/// Decode a chat service `EventMessage`.
+fn decode_event_message(
+    event: EventMessage,
+    url: Option<&str>,
+    body: serde_json::Value,
+) -> Option<TrouterEvent> {
+    let link_conversation = event
+        .resource_link
+        .as_deref()
+        .and_then(conversation_id_from_link);
+
+    match event.resource_type.as_deref().unwrap_or("") {
+        "NewMessage" | "MessageUpdate" => {
+            let is_update = event.resource_type.as_deref() == Some("MessageUpdate");
+            let msg: NativeMessage = serde_json::from_value(event.resource?).ok()?;
+            decode_message(&msg, link_conversation, is_update)
+        }
+        "ConversationUpdate" => {
+            let resource = event.resource?;
+            let conversation_id = resource
+                .get("id")
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .or(link_conversation)?;
+            match resource
+                .pointer("/properties/consumptionhorizon")
+                .and_then(|v| v.as_str())
+            {
+                Some(horizon) => Some(TrouterEvent::ReadReceipt {
+                    conversation_id,
+                    horizon: horizon.to_string(),
+                }),
+                None => Some(TrouterEvent::Unknown {
+                    url: url.map(String::from),
+                    body,
+                }),
+            }
+        }
+        "ThreadUpdate" => {
+            let resource = event.resource?;
+            let conversation_id = resource
+                .get("id")
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .or(link_conversation)?;
+            let topic = resource
+                .pointer("/properties/topic")
+                .and_then(|v| v.as_str())
+                .map(String::from);
+            Some(TrouterEvent::ThreadUpdate {
+                conversation_id,
+                topic,
+            })
+        }
+        "UserPresence" | "EndpointPresence" => {
+            let resource = event.resource?;
+            let mri = event
+                .resource_link
+                .as_deref()
+                .and_then(contact_mri_from_link)?;
+            let availability = resource
+                .get("availability")
+                .or_else(|| resource.get("status"))
+                .and_then(|v| v.as_str())?
+                .to_string();
+            let activity = resource
+                .get("activity")
+                .and_then(|v| v.as_str())
+                .map(String::from);
+            Some(TrouterEvent::PresenceChanged(vec![PresenceUpdate {
+                mri,
+                availability,
+                activity,
+            }]))
+        }
+        _ => Some(TrouterEvent::Unknown {
+            url: url.map(String::from),
+            body,
+        }),
+    }
+}
+
+/// Decode a `NewMessage`/`MessageUpdate` resource by its message type.
+fn decode_message(
+    msg: &NativeMessage,
+    link_conversation: Option<String>,
+    is_update: bool,
+) -> Option<TrouterEvent> {
+    let conversation_id = msg
+        .conversation_id()
+        .map(String::from)
+        .or(link_conversation)?;
+
+    let msgtype = msg.message_type().unwrap_or("");
+    if msgtype == "Control/Typing" || msgtype == "Control/ClearTyping" {
+        return Some(TrouterEvent::Typing {
+            conversation_id,
+            user: msg.sender_name().unwrap_or("?").to_string(),
+            stopped: msgtype == "Control/ClearTyping",
+        });
+    }
+    if msgtype.starts_with("ThreadActivity/") {
+        return Some(TrouterEvent::ThreadUpdate {
+            conversation_id,
+            topic: None,
+        });
+    }
+
+    if msg.is_deleted() {
+        return Some(TrouterEvent::MessageDeleted {
+            conversation_id,
+            message_id: msg.id()?.to_string(),
+        });
+    }
+
+    let message = api::message_info(msg)?;
+    if msg.is_edited() || is_update {
+        Some(TrouterEvent::MessageEdited {
+            conversation_id,
+            message,
+        })
+    } else {
+        Some(TrouterEvent::NewMessage {
+            conversation_id,
+            message,
+        })
+    }
+}
+
+/// Decode an NGC calling notification.
+fn decode_call(body: &serde_json::Value) -> Option<TrouterEvent> {
+    if body.get("callInvitation").is_some() {
+        let notification = calling::parse_call_notification(&body.to_string())?;
+        return Some(TrouterEvent::CallInvite(Box::new(notification)));
+    }
+    if body.get("callEnd").is_some() {
+        let call_id = body
+            .pointer("/debugContent/callId")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        return Some(TrouterEvent::CallEnded { call_id });
+    }
+    None
+}
+
+/// Decode a unified presence service push: `{"presence": [{"mri", "presence": {...}}]}`.
+fn decode_unified_presence(body: &serde_json::Value) -> Option<Vec<PresenceUpdate>> {
+    let entries = body.get("presence")?.as_array()?;
+    let updates: Vec<PresenceUpdate> = entries
+        .iter()
+        .filter_map(|entry| {
+            let mri = entry.get("mri")?.as_str()?.to_string();
+            let presence = entry.get("presence")?;
+            Some(PresenceUpdate {
+                mri,
+                availability: presence.get("availability")?.as_str()?.to_string(),
+                activity: presence
+                    .get("activity")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+            })
+        })
+        .collect();
+    (!updates.is_empty()).then_some(updates)
+}
+
+/// Extract the notification request from a `3:::` request frame or `5:` event frame.
+fn frame_request(frame: &str) -> Option<TrouterRequest> {
+    let json_str = if let Some(rest) = frame.strip_prefix("3:::") {
+        rest
+    } else if let Some(rest) = frame.strip_prefix("5:") {
+        // Socket.IO v1 event frame: 5:ACK_ID:ENDPOINT:JSON
+        rest.find("::").map(|pos| &rest[pos + 2..])?
+    } else {
+        return None;
+    };
+    let payload: serde_json::Value = serde_json::from_str(json_str).ok()?;
+    let request = payload
+        .get("args")
+        .and_then(|args| args.get(0))
+        .unwrap_or(&payload);
+    serde_json::from_value(request.clone()).ok()
+}
+
+/// Parse a request body, which is normally stringified JSON.
+fn parse_body(body: serde_json::Value) -> Option<serde_json::Value> {
+    match body {
+        serde_json::Value::String(s) => serde_json::from_str(&s).ok(),
+        body @ serde_json::Value::Object(_) => Some(body),
+        _ => None,
+    }
+}
+
+/// Extract the conversation ID from a `.../conversations/{id}/...` link,
+/// dropping the `;messageid=` suffix of channel thread links.
+fn conversation_id_from_link(link: &str) -> Option<String> {
+    path_segment_after(link, "/conversations/").map(|id| api::split_thread_id(&id).0.to_string())
+}
+
+/// Extract the contact MRI from a `.../contacts/{mri}/presenceDocs/...` link.
+fn contact_mri_from_link(link: &str) -> Option<String> {
+    path_segment_after(link, "/contacts/")
+}
+
+/// Return the path segment immediately following `marker`.
+fn path_segment_after(link: &str, marker: &str) -> Option<String> {
+    let rest = &link[link.find(marker)? + marker.len()..];
+    let segment = rest.split(['/', '?']).next()?;
+    (!segment.is_empty()).then(|| segment.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_new_message() {
+        let frame = include_str!("fixtures/new_message.txt");
+        match decode_frame(frame) {
+            Some(TrouterEvent::NewMessage {
+                conversation_id,
+                message,
+            }) => {
+                assert_eq!(conversation_id, "19:abc123@thread.v2");
+                assert_eq!(message.id, "1700000000123");
+                assert_eq!(message.sender, "Alice Example");
+                assert_eq!(message.content, "Hello & welcome");
+            }
+            other => panic!("expected NewMessage, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_channel_reply() {
+        let frame = include_str!("fixtures/channel_reply.txt");
+        match decode_frame(frame) {
+            Some(TrouterEvent::NewMessage {
+                conversation_id,
+                message,
+            }) => {
+                assert_eq!(conversation_id, "19:chan456@thread.tacv2");
+                assert_eq!(message.parent_id.as_deref(), Some("1700000000100"));
+            }
+            other => panic!("expected NewMessage, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_edited_message() {
+        let frame = include_str!("fixtures/edited_message.txt");
+        match decode_frame(frame) {
+            Some(TrouterEvent::MessageEdited {
+                conversation_id,
+                message,
+            }) => {
+                assert_eq!(conversation_id, "19:abc123@thread.v2");
+                assert_eq!(message.content, "Hello (fixed)");
+            }
+            other => panic!("expected MessageEdited, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_deleted_message() {
+        let frame = include_str!("fixtures/deleted_message.txt");
+        match decode_frame(frame) {
+            Some(TrouterEvent::MessageDeleted {
+                conversation_id,
+                message_id,
+            }) => {
+                assert_eq!(conversation_id, "19:abc123@thread.v2");
+                assert_eq!(message_id, "1700000000123");
+            }
+            other => panic!("expected MessageDeleted, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_typing() {
+        let frame = include_str!("fixtures/typing.txt");
+        match decode_frame(frame) {
+            Some(TrouterEvent::Typing {
+                conversation_id,
+                user,
+                stopped,
+            }) => {
+                assert_eq!(conversation_id, "19:abc123@thread.v2");
+                assert_eq!(user, "Alice Example");
+                assert!(!stopped);
+            }
+            other => panic!("expected Typing, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_read_receipt() {
+        let frame = include_str!("fixtures/read_receipt.txt");
+        match decode_frame(frame) {
+            Some(TrouterEvent::ReadReceipt {
+                conversation_id,
+                horizon,
+            }) => {
+                assert_eq!(conversation_id, "19:abc123@thread.v2");
+                assert_eq!(horizon, "1700000000123;1700000000456;1700000000123");
+            }
+            other => panic!("expected ReadReceipt, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_unified_presence() {
+        let frame = include_str!("fixtures/presence.txt");
+        match decode_frame(frame) {
+            Some(TrouterEvent::PresenceChanged(updates)) => {
+                assert_eq!(updates.len(), 2);
+                assert_eq!(
+                    updates[0].mri,
+                    "8:orgid:11111111-1111-1111-1111-111111111111"
+                );
+                assert_eq!(updates[0].availability, "Busy");
+                assert_eq!(updates[0].activity.as_deref(), Some("InACall"));
+                assert_eq!(updates[1].availability, "Away");
+            }
+            other => panic!("expected PresenceChanged, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_thread_update() {
+        let frame = include_str!("fixtures/thread_update.txt");
+        match decode_frame(frame) {
+            Some(TrouterEvent::ThreadUpdate {
+                conversation_id,
+                topic,
+            }) => {
+                assert_eq!(conversation_id, "19:abc123@thread.v2");
+                assert_eq!(topic.as_deref(), Some("Release planning"));
+            }
+            other => panic!("expected ThreadUpdate, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_call_invite() {
+        let frame = include_str!("fixtures/call_invite.txt");
+        match decode_frame(frame) {
+            Some(TrouterEvent::CallInvite(notification)) => {
+                let from = notification.participants.unwrap().from.unwrap();
+                assert_eq!(from.display_name.as_deref(), Some("Bob Example"));
+            }
+            other => panic!("expected CallInvite, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_call_ended() {
+        let frame = include_str!("fixtures/call_ended.txt");
+        match decode_frame(frame) {
+            Some(TrouterEvent::CallEnded { call_id }) => {
+                assert_eq!(call_id.as_deref(), Some("call-1234"));
+            }
+            other => panic!("expected CallEnded, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_unknown_notification() {
+        let frame = r#"3:::{"id":7,"method":"POST","url":"/v4/f/abc/other","body":"{\"foo\":1}"}"#;
+        match decode_frame(frame) {
+            Some(TrouterEvent::Unknown { url, body }) => {
+                assert_eq!(url.as_deref(), Some("/v4/f/abc/other"));
+                assert_eq!(body["foo"], 1);
+            }
+            other => panic!("expected Unknown, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_control_frames_ignored() {
+        assert!(decode_frame("1::").is_none());
+        assert!(decode_frame("2::").is_none());
+        assert!(decode_frame("6:12::").is_none());
+        assert!(decode_frame("5:3::not json").is_none());
+    }
+}
@@ -0,0 +1,51 @@
+//! Presence subscriptions — asks the unified presence service to push
+//! changes for a set of users to our Trouter endpoint
+
+use anyhow::{Context, Result};
+
+const SUBSCRIPTIONS_URL: &str = "https://presence.teams.microsoft.com/v1/pubsub/subscriptions";
+
+/// Path suffix under our Trouter URL that presence pushes are sent to.
+const PRESENCE_PATH: &str = "TeamsUnifiedPresenceService";
+
+/// Subscribe our endpoint to presence changes of `mris`, replacing any
+/// earlier subscriptions.
+pub async fn subscribe(
+    http: &reqwest::Client,
+    skype_token: &str,
+    trouter_surl: &str,
+    epid: &str,
+    mris: &[String],
+) -> Result<()> {
+    let subscriptions: Vec<serde_json::Value> = mris
+        .iter()
+        .map(|mri| serde_json::json!({ "mri": mri, "source": "ups" }))
+        .collect();
+    let payload = serde_json::json!({
+        "trouterUri": format!("{}{}", trouter_surl, PRESENCE_PATH),
+        "shouldPurgePreviousSubscriptions": true,
+        "subscriptionsToAdd": subscriptions,
+        "subscriptionsToRemove": [],
+    });
+
+    tracing::info!("Subscribing to presence of {} users", mris.len());
+
+    let resp = http
+        .post(format!("{}/{}", SUBSCRIPTIONS_URL, epid))
+        // Like the chat service, presence.teams.microsoft.com takes the
+        // Skype token as a bearer; AAD tokens for api.spaces.skype.com or
+        // Graph are not issued for its audience.
+        .bearer_auth(skype_token)
+        .header("x-ms-endpoint-id", epid)
+        .json(&payload)
+        .send()
+        .await
+        .context("Presence subscription POST failed")?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("Presence subscription returned {}: {}", status, body);
+    }
+    Ok(())
+}
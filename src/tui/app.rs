@@ -1,18 +1,30 @@
 //! TUI Application state and main event loop
 
+use std::collections::HashMap;
+
 use anyhow::Result;
 use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers};
 use ratatui::DefaultTerminal;
 use tokio_stream::StreamExt;
 
 use super::backend::{Backend, BackendCommand, BackendResponse};
-use super::compose::ComposeState;
+use super::compose::{self, ComposeState, Format};
 use super::debug_log::DebugLogState;
+use super::images::ImagePreviews;
 use super::log_capture::LogBuffer;
-use super::messages::MessagesState;
+use super::members::{MemberAction, MemberPanel};
+use super::messages::{Message, MessagesState};
+use super::people::PeoplePicker;
+use super::prompt::FilePrompt;
+use super::reactions::ReactionPicker;
 use super::search::SearchState;
 use super::sidebar::SidebarState;
 use super::ui;
+use crate::api;
+use crate::trouter::ConnectionState;
+
+/// Shortest query (in characters) sent to server-side search.
+const MIN_REMOTE_QUERY_LEN: usize = 3;
 
 /// Active pane in the TUI
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
@@ -33,6 +45,17 @@ impl Pane {
     }
 }
 
+/// Characters of the message shown in the delete confirmation.
+const DELETE_PREVIEW_CHARS: usize = 60;
+
+/// A delete awaiting confirmation in the confirm dialog.
+pub struct PendingDelete {
+    pub chat_id: String,
+    pub message_id: String,
+    /// Start of the message text, shown in the dialog.
+    pub preview: String,
+}
+
 /// Application state
 pub struct App {
     /// Whether the app should exit
@@ -41,27 +64,47 @@ pub struct App {
     pub is_online: bool,
     /// Current user name
     pub user_name: String,
+    /// Current user's Entra ID (AAD) object ID, empty until loaded.
+    pub user_id: String,
     /// Current channel name
     pub channel_name: String,
-    /// Member count
-    #[allow(dead_code)]
-    pub member_count: u32,
     /// Connection state description
     pub connection_state: String,
+    /// Trouter push connection state (live updates).
+    pub push_state: ConnectionState,
     /// Active pane
     pub active_pane: Pane,
     /// Sidebar state (teams/channels/chats + navigation)
     pub sidebar: SidebarState,
     /// Messages pane state
     pub messages: MessagesState,
+    /// Inline previews of image attachments.
+    pub previews: ImagePreviews,
     /// Compose box state
     pub compose: ComposeState,
     /// Whether the help popup is visible
     pub show_help: bool,
+    /// Message delete waiting for y/n in the confirm dialog.
+    pub pending_delete: Option<PendingDelete>,
+    /// Open reaction picker, if any.
+    pub reaction_picker: Option<ReactionPicker>,
+    /// Open "attach file" prompt, if any.
+    pub file_prompt: Option<FilePrompt>,
+    /// Open new-chat people picker, if any.
+    pub people_picker: Option<PeoplePicker>,
+    /// Open member panel beside the messages, if any.
+    pub member_panel: Option<MemberPanel>,
     /// Global search overlay state
     pub search: SearchState,
     /// The chat/channel ID currently being viewed.
     pub current_chat_id: Option<String>,
+    /// Members of the current conversation (mention autocomplete and the
+    /// member panel).
+    pub members: Vec<api::MemberInfo>,
+    /// ID of the newest message read in each conversation, by conversation ID.
+    pub read_horizons: HashMap<String, u64>,
+    /// Unread counts for conversations not in the sidebar yet.
+    pub pending_unread: HashMap<String, u32>,
     /// Status message shown in the status bar (errors, info).
     pub status_message: Option<String>,
     /// Whether the status message is an error.
@@ -77,16 +120,26 @@ impl App {
             should_exit: false,
             is_online: false,
             user_name: "Loading...".to_string(),
+            user_id: String::new(),
             channel_name: "".to_string(),
-            member_count: 0,
             connection_state: "Connecting...".to_string(),
+            push_state: ConnectionState::Connecting,
             active_pane: Pane::default(),
             sidebar: SidebarState::default(),
             messages: MessagesState::default(),
+            previews: ImagePreviews::default(),
             compose: ComposeState::default(),
             show_help: false,
+            pending_delete: None,
+            reaction_picker: None,
+            file_prompt: None,
+            people_picker: None,
+            member_panel: None,
             search: SearchState::default(),
             current_chat_id: None,
+            members: Vec::new(),
+            read_horizons: HashMap::new(),
+            pending_unread: HashMap::new(),
             status_message: None,
             status_is_error: false,
             debug_log: DebugLogState::new(log_buffer),
@@ -129,9 +182,35 @@ impl App {
             // Clear status message on any keypress.
             self.status_message = None;
 
+            // The delete confirmation takes every key until answered.
+            if self.pending_delete.is_some() {
+                self.handle_confirm_delete_key(key_event, backend);
+                return;
+            }
+
+            if self.reaction_picker.is_some() {
+                self.handle_reaction_picker_key(key_event, backend);
+                return;
+            }
+
+            if self.file_prompt.is_some() {
+                self.handle_file_prompt_key(key_event, backend);
+                return;
+            }
+
+            if self.people_picker.is_some() {
+                self.handle_people_picker_key(key_event, backend);
+                return;
+            }
+
+            if self.member_panel.is_some() {
+                self.handle_member_panel_key(key_event, backend);
+                return;
+            }
+
             // When search overlay is active, route all keys to search handler.
             if self.search.active {
-                self.handle_search_key(key_event);
+                self.handle_search_key(key_event, backend);
                 return;
             }
 
@@ -140,6 +219,15 @@ impl App {
                 && key_event.modifiers.contains(KeyModifiers::CONTROL)
             {
                 self.search.activate();
+                backend.send(BackendCommand::LoadSearchIndex);
+                return;
+            }
+
+            // Ctrl+N starts a new chat from any mode.
+            if key_event.code == KeyCode::Char('n')
+                && key_event.modifiers.contains(KeyModifiers::CONTROL)
+            {
+                self.people_picker = Some(PeoplePicker::default());
                 return;
             }
 
@@ -215,6 +303,7 @@ impl App {
             }
             // Messages pane keys
             KeyCode::Up | KeyCode::Char('k') if self.active_pane == Pane::Messages => {
+                self.load_older_if_at_top(backend);
                 self.messages.select_previous();
             }
             KeyCode::Down | KeyCode::Char('j') if self.active_pane == Pane::Messages => {
@@ -223,6 +312,32 @@ impl App {
             KeyCode::Enter if self.active_pane == Pane::Messages => {
                 self.messages.toggle_thread();
             }
+            KeyCode::Char('r') if self.active_pane == Pane::Messages => {
+                self.start_reply();
+            }
+            KeyCode::Char('e') if self.active_pane == Pane::Messages => {
+                self.start_edit();
+            }
+            KeyCode::Char('d') if self.active_pane == Pane::Messages => {
+                self.confirm_delete();
+            }
+            KeyCode::Char('+') if self.active_pane == Pane::Messages => {
+                self.open_reaction_picker();
+            }
+            KeyCode::Char('s') if self.active_pane == Pane::Messages => {
+                self.save_attachments(backend);
+            }
+            KeyCode::Char('m') if self.current_chat_id.is_some() => {
+                self.member_panel = Some(MemberPanel::default());
+            }
+            KeyCode::Char('@') if self.active_pane == Pane::Messages => {
+                self.active_pane = Pane::Compose;
+                self.compose.move_end();
+                if !self.compose.input.is_empty() && !self.compose.input.ends_with(' ') {
+                    self.compose.insert_char(' ');
+                }
+                self.compose.insert_char('@');
+            }
             // Help popup toggle (available from any non-compose pane)
             KeyCode::Char('?') => {
                 self.show_help = !self.show_help;
@@ -250,17 +365,416 @@ impl App {
             super::sidebar::SidebarItem::Channel(_, _) | super::sidebar::SidebarItem::Chat(_) => {
                 if let Some(id) = self.sidebar.selected_item_id() {
                     let name = self.sidebar.selected_item_name().unwrap_or_default();
-                    self.current_chat_id = Some(id.clone());
-                    self.channel_name = name.clone();
-                    self.messages.loading = true;
-                    self.messages.channel_header = name;
-                    self.messages.messages.clear();
-                    backend.send(BackendCommand::LoadMessages {
-                        chat_id: id,
-                        limit: 50,
+                    self.open_conversation(id, name, backend);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Make a chat or channel the current conversation and load its messages.
+    fn open_conversation(&mut self, id: String, name: String, backend: &Backend) {
+        self.sidebar.clear_unread(&id);
+        self.current_chat_id = Some(id.clone());
+        self.channel_name = name.clone();
+        self.messages.loading = true;
+        self.messages.channel_header = name;
+        self.messages.messages.clear();
+        self.messages.older_link = None;
+        self.messages.pending_select = None;
+        self.messages.read_horizon = self.read_horizons.get(&id).copied();
+        self.compose.cancel_mode();
+        self.members.clear();
+        if let Some(ref mut panel) = self.member_panel {
+            *panel = MemberPanel::default();
+        }
+        backend.send(BackendCommand::LoadMembers {
+            chat_id: id.clone(),
+        });
+        backend.send(BackendCommand::LoadMessages {
+            chat_id: id,
+            limit: 50,
+        });
+    }
+
+    /// Members offered by the mention popup, if it is open.
+    pub fn mention_candidates(&self) -> Vec<&api::MemberInfo> {
+        match self.compose.mention_query() {
+            Some(query) => {
+                let own_mri = self.messages.user_mri.as_deref();
+                compose::mention_candidates(&self.members, &query, own_mri)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Handle a key while the mention popup is open. Returns false for keys
+    /// the compose box should handle as usual.
+    fn handle_mention_key(&mut self, code: KeyCode) -> bool {
+        let candidates = self.mention_candidates();
+        let (Some(mut mention), false) = (self.compose.mention, candidates.is_empty()) else {
+            return false;
+        };
+        let count = candidates.len();
+        let selected = mention.selected.min(count - 1);
+        let chosen = candidates[selected].display_name.clone();
+        match code {
+            KeyCode::Up => mention.selected = selected.checked_sub(1).unwrap_or(count - 1),
+            KeyCode::Down => mention.selected = (selected + 1) % count,
+            KeyCode::Tab | KeyCode::Enter => {
+                self.compose.accept_mention(&chosen);
+                return true;
+            }
+            KeyCode::Esc => {
+                self.compose.mention = None;
+                return true;
+            }
+            _ => return false,
+        }
+        self.compose.mention = Some(mention);
+        true
+    }
+
+    /// Request the previous page of history when scrolling past the oldest
+    /// loaded message.
+    fn load_older_if_at_top(&mut self, backend: &Backend) {
+        let (Some(chat_id), Some(link)) = (&self.current_chat_id, self.messages.older_page_link())
+        else {
+            return;
+        };
+        backend.send(BackendCommand::LoadOlderMessages {
+            chat_id: chat_id.clone(),
+            backward_link: link.to_string(),
+        });
+        self.messages.loading_older = true;
+    }
+
+    /// Whether the current conversation is a team channel.
+    fn in_channel(&self) -> bool {
+        match self.current_chat_id {
+            Some(ref id) => self.sidebar.is_channel(id),
+            None => false,
+        }
+    }
+
+    /// Start replying to the selected message in the compose box: chats
+    /// quote it, channels reply in its thread.
+    fn start_reply(&mut self) {
+        let in_channel = self.in_channel();
+        if let Some(msg) = self.messages.selected_message() {
+            let message_id = match msg.parent_id {
+                // A reply whose root is not loaded is shown top-level.
+                Some(ref root) if in_channel => root.clone(),
+                _ => msg.id.clone(),
+            };
+            let quote = api::QuotedMessage::new(
+                &message_id,
+                &msg.sender,
+                msg.sender_id.as_deref(),
+                &msg.rich,
+            );
+            self.compose.cancel_mode();
+            self.compose.reply_to = Some(quote);
+            self.active_pane = Pane::Compose;
+        }
+    }
+
+    /// Whether a message was sent by the logged-in user. Falls back to the
+    /// display name for messages without a sender MRI.
+    fn is_own_message(&self, msg: &Message) -> bool {
+        match msg.sender_id {
+            Some(ref mri) => !self.user_id.is_empty() && *mri == api::user_mri(&self.user_id),
+            None => msg.sender == self.user_name,
+        }
+    }
+
+    /// The selected message, if it is one of ours; otherwise report why not.
+    fn selected_own_message(&mut self, action: &str) -> Option<Message> {
+        let msg = self.messages.selected_message()?.clone();
+        if !self.is_own_message(&msg) {
+            self.set_error(format!("You can only {} your own messages", action));
+            return None;
+        }
+        Some(msg)
+    }
+
+    /// Load the selected message into the compose box for editing.
+    fn start_edit(&mut self) {
+        if let Some(msg) = self.selected_own_message("edit") {
+            let quote = msg.rich.reply().and_then(api::QuotedMessage::from_reply);
+            let text = msg.rich.without_reply().render(api::TextFlavor::Markdown);
+            self.compose.start_edit(&msg.id, &text, quote);
+            self.active_pane = Pane::Compose;
+        }
+    }
+
+    /// Ask for confirmation before deleting the selected message.
+    fn confirm_delete(&mut self) {
+        let Some(chat_id) = self.current_chat_id.clone() else {
+            return;
+        };
+        if let Some(msg) = self.selected_own_message("delete") {
+            self.pending_delete = Some(PendingDelete {
+                chat_id,
+                message_id: msg.id,
+                preview: msg
+                    .content
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .chars()
+                    .take(DELETE_PREVIEW_CHARS)
+                    .collect(),
+            });
+        }
+    }
+
+    /// Handle keys while the delete confirmation is shown.
+    fn handle_confirm_delete_key(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        backend: &Backend,
+    ) {
+        match key_event.code {
+            KeyCode::Char('y') | KeyCode::Enter => {
+                if let Some(pending) = self.pending_delete.take() {
+                    backend.send(BackendCommand::DeleteMessage {
+                        chat_id: pending.chat_id,
+                        message_id: pending.message_id,
                     });
                 }
             }
+            KeyCode::Char('n') | KeyCode::Esc => {
+                self.pending_delete = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Download the selected message's attachments.
+    fn save_attachments(&mut self, backend: &Backend) {
+        let Some(msg) = self.messages.selected_message() else {
+            return;
+        };
+        if msg.attachments.is_empty() {
+            self.set_error("This message has no attachments".to_string());
+            return;
+        }
+        let count = msg.attachments.len();
+        backend.send(BackendCommand::SaveAttachments {
+            attachments: msg.attachments.clone(),
+        });
+        self.status_message = Some(format!("Downloading {} attachment(s)...", count));
+        self.status_is_error = false;
+    }
+
+    /// Open the reaction picker for the selected message.
+    fn open_reaction_picker(&mut self) {
+        let (Some(chat_id), Some(msg)) = (&self.current_chat_id, self.messages.selected_message())
+        else {
+            return;
+        };
+        let me = api::user_mri(&self.user_id);
+        let mine = msg
+            .reactions
+            .iter()
+            .filter(|r| r.users.contains(&me))
+            .map(|r| r.key.clone())
+            .collect();
+        self.reaction_picker = Some(ReactionPicker::new(chat_id.clone(), msg.id.clone(), mine));
+    }
+
+    /// Handle keys while the reaction picker is open.
+    fn handle_reaction_picker_key(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        backend: &Backend,
+    ) {
+        let Some(picker) = self.reaction_picker.as_mut() else {
+            return;
+        };
+        let apply = match key_event.code {
+            KeyCode::Left | KeyCode::Char('h') => {
+                picker.move_left();
+                false
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                picker.move_right();
+                false
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() => {
+                picker.select_number(c.to_digit(10).unwrap_or(0) as usize)
+            }
+            KeyCode::Enter => true,
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.reaction_picker = None;
+                false
+            }
+            _ => false,
+        };
+        if apply {
+            if let Some(picker) = self.reaction_picker.take() {
+                let (key, add) = picker.choice();
+                backend.send(BackendCommand::SetReaction {
+                    chat_id: picker.chat_id,
+                    message_id: picker.message_id,
+                    key: key.to_string(),
+                    add,
+                });
+            }
+        }
+    }
+
+    /// Open the file prompt for the current chat.
+    fn open_file_prompt(&mut self) {
+        let Some(chat_id) = self.current_chat_id.clone() else {
+            self.set_error("No chat selected. Select a chat first.".to_string());
+            return;
+        };
+        if self.sidebar.is_channel(&chat_id) {
+            self.set_error("Files can only be attached in chats".to_string());
+            return;
+        }
+        self.file_prompt = Some(FilePrompt::new(chat_id));
+    }
+
+    /// Handle keys while the file prompt is open.
+    fn handle_file_prompt_key(&mut self, key_event: crossterm::event::KeyEvent, backend: &Backend) {
+        let Some(prompt) = self.file_prompt.as_mut() else {
+            return;
+        };
+        match key_event.code {
+            KeyCode::Esc => self.file_prompt = None,
+            KeyCode::Backspace => prompt.backspace(),
+            KeyCode::Enter => {
+                let Some(path) = prompt.path() else {
+                    return;
+                };
+                let chat_id = prompt.chat_id.clone();
+                self.file_prompt = None;
+                // Compose text, if any, goes out as the file's caption
+                // (unless it is a pending reply or edit).
+                let message = if self.compose.has_mode() {
+                    None
+                } else {
+                    self.compose.send()
+                };
+                backend.send(BackendCommand::SendFile {
+                    chat_id,
+                    path,
+                    message,
+                });
+                self.status_message = Some("Uploading...".to_string());
+                self.status_is_error = false;
+            }
+            KeyCode::Char(c)
+                if key_event.modifiers.is_empty() || key_event.modifiers == KeyModifiers::SHIFT =>
+            {
+                prompt.insert_char(c);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_people_picker_key(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        backend: &Backend,
+    ) {
+        let Some(picker) = self.people_picker.as_mut() else {
+            return;
+        };
+        let searched = picker.query.clone();
+        match key_event.code {
+            KeyCode::Esc => {
+                self.people_picker = None;
+                return;
+            }
+            KeyCode::Up => picker.move_up(),
+            KeyCode::Down => picker.move_down(),
+            KeyCode::Backspace => picker.backspace(),
+            KeyCode::Enter if picker.query.trim().is_empty() => {
+                if picker.chosen.is_empty() {
+                    return;
+                }
+                let people = std::mem::take(&mut picker.chosen);
+                let add_to = picker.add_to.take();
+                self.people_picker = None;
+                self.status_is_error = false;
+                match add_to {
+                    Some(chat_id) => {
+                        backend.send(BackendCommand::AddMembers { chat_id, people });
+                        self.status_message = Some("Adding people...".to_string());
+                    }
+                    None => {
+                        backend.send(BackendCommand::CreateChat { people });
+                        self.status_message = Some("Starting chat...".to_string());
+                    }
+                }
+                return;
+            }
+            KeyCode::Enter | KeyCode::Tab => {
+                picker.choose_selected();
+            }
+            KeyCode::Char(c)
+                if key_event.modifiers.is_empty() || key_event.modifiers == KeyModifiers::SHIFT =>
+            {
+                picker.insert_char(c);
+            }
+            _ => {}
+        }
+        let query = picker.query.trim().to_string();
+        if picker.query != searched {
+            picker.results.clear();
+            picker.selected = 0;
+            picker.searching = !query.is_empty();
+            if !query.is_empty() {
+                backend.send(BackendCommand::SearchPeople { query });
+            }
+        }
+    }
+
+    fn handle_member_panel_key(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        backend: &Backend,
+    ) {
+        let (Some(panel), Some(chat_id)) = (self.member_panel.as_mut(), &self.current_chat_id)
+        else {
+            self.member_panel = None;
+            return;
+        };
+
+        if let Some(action) = panel.confirm.take() {
+            if matches!(key_event.code, KeyCode::Char('y') | KeyCode::Enter) {
+                let chat_id = chat_id.clone();
+                self.status_is_error = false;
+                match action {
+                    MemberAction::Remove { mri, name } => {
+                        self.status_message = Some(format!("Removing {}...", name));
+                        backend.send(BackendCommand::RemoveMember { chat_id, mri, name });
+                    }
+                    MemberAction::Leave => {
+                        self.status_message = Some("Leaving chat...".to_string());
+                        backend.send(BackendCommand::LeaveChat { chat_id });
+                    }
+                }
+            }
+            return;
+        }
+
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('m') | KeyCode::Char('q') => self.member_panel = None,
+            KeyCode::Up | KeyCode::Char('k') => panel.move_up(),
+            KeyCode::Down | KeyCode::Char('j') => panel.move_down(self.members.len()),
+            KeyCode::Char('a') => {
+                self.people_picker = Some(PeoplePicker::adding_to(chat_id.clone()));
+            }
+            KeyCode::Char('x') => {
+                let own_mri = self.messages.user_mri.as_deref();
+                panel.confirm_remove(&self.members, own_mri);
+            }
+            KeyCode::Char('L') => panel.confirm = Some(MemberAction::Leave),
             _ => {}
         }
     }
@@ -270,6 +784,10 @@ impl App {
         let modifiers = key_event.modifiers;
         let code = key_event.code;
 
+        if modifiers.is_empty() && self.handle_mention_key(code) {
+            return;
+        }
+
         match (code, modifiers) {
             // Tab always cycles pane focus.
             (KeyCode::Tab, _) => {
@@ -279,6 +797,10 @@ impl App {
             (KeyCode::BackTab, _) => {
                 self.prev_pane();
             }
+            // Esc cancels a pending reply or edit first.
+            (KeyCode::Esc, _) if self.compose.has_mode() => {
+                self.compose.cancel_mode();
+            }
             // Esc leaves compose and goes to Messages pane.
             (KeyCode::Esc, _) => {
                 self.active_pane = Pane::Messages;
@@ -289,12 +811,37 @@ impl App {
             }
             // Enter sends the message.
             (KeyCode::Enter, _) => {
+                let in_channel = self.in_channel();
                 if let Some(text) = self.compose.send() {
                     if let Some(ref chat_id) = self.current_chat_id {
-                        backend.send(BackendCommand::SendMessage {
-                            chat_id: chat_id.clone(),
-                            message: text,
-                        });
+                        let mentions = api::find_mentions(&self.members, &text);
+                        let quote = self.compose.reply_to.take();
+                        if let Some(message_id) = self.compose.editing.take() {
+                            backend.send(BackendCommand::EditMessage {
+                                chat_id: chat_id.clone(),
+                                message_id,
+                                message: text,
+                                mentions,
+                                quote,
+                            });
+                            return;
+                        }
+                        match quote {
+                            Some(reply) if in_channel => {
+                                backend.send(BackendCommand::SendChannelReply {
+                                    channel_id: chat_id.clone(),
+                                    parent_id: reply.message_id,
+                                    message: text,
+                                    mentions,
+                                })
+                            }
+                            quote => backend.send(BackendCommand::SendMessage {
+                                chat_id: chat_id.clone(),
+                                message: text,
+                                mentions,
+                                quote,
+                            }),
+                        }
                     } else {
                         self.status_message =
                             Some("No chat selected. Select a channel or chat first.".to_string());
@@ -302,10 +849,27 @@ impl App {
                     }
                 }
             }
+            // Ctrl+P attaches a file.
+            (KeyCode::Char('p'), m) if m.contains(KeyModifiers::CONTROL) => {
+                self.open_file_prompt();
+            }
             // Ctrl+U clears the compose box.
             (KeyCode::Char('u'), m) if m.contains(KeyModifiers::CONTROL) => {
                 self.compose.clear();
             }
+            // Alt+letter inserts Markdown formatting (toolbar buttons).
+            (KeyCode::Char(c), m) if m.contains(KeyModifiers::ALT) => {
+                let format = match c.to_ascii_lowercase() {
+                    'b' => Format::Bold,
+                    'i' => Format::Italic,
+                    'u' => Format::Underline,
+                    's' => Format::Strike,
+                    'c' => Format::Code,
+                    'k' => Format::Link,
+                    _ => return,
+                };
+                self.compose.apply_format(format);
+            }
             // Backspace deletes character before cursor.
             (KeyCode::Backspace, _) => {
                 self.compose.backspace();
@@ -339,7 +903,7 @@ impl App {
     }
 
     /// Handle key events when the search overlay is active.
-    fn handle_search_key(&mut self, key_event: crossterm::event::KeyEvent) {
+    fn handle_search_key(&mut self, key_event: crossterm::event::KeyEvent, backend: &Backend) {
         let code = key_event.code;
         let modifiers = key_event.modifiers;
 
@@ -358,17 +922,17 @@ impl App {
             }
             // Enter selects the current result.
             (KeyCode::Enter, _) => {
-                self.apply_search_selection();
+                self.apply_search_selection(backend);
             }
             // Backspace deletes character before cursor.
             (KeyCode::Backspace, _) => {
                 self.search.backspace();
-                self.search.update_results(&self.sidebar, &self.messages);
+                self.on_search_query_changed(backend);
             }
             // Delete removes character at cursor.
             (KeyCode::Delete, _) => {
                 self.search.delete_at_cursor();
-                self.search.update_results(&self.sidebar, &self.messages);
+                self.on_search_query_changed(backend);
             }
             // Left/Right move cursor.
             (KeyCode::Left, _) => {
@@ -388,15 +952,30 @@ impl App {
                 // Only insert if no modifiers or just shift (for uppercase).
                 if m.is_empty() || m == KeyModifiers::SHIFT {
                     self.search.insert_char(c);
-                    self.search.update_results(&self.sidebar, &self.messages);
+                    self.on_search_query_changed(backend);
                 }
             }
             _ => {}
         }
     }
 
+    /// Refresh local results and schedule a server-side search for the query.
+    fn on_search_query_changed(&mut self, backend: &Backend) {
+        self.search.update_results(
+            &self.sidebar,
+            &self.messages,
+            self.current_chat_id.as_deref(),
+        );
+        let query = self.search.query.trim();
+        if query.chars().count() >= MIN_REMOTE_QUERY_LEN {
+            backend.send(BackendCommand::SearchRemote {
+                query: query.to_string(),
+            });
+        }
+    }
+
     /// Apply the currently selected search result: navigate to the matching item.
-    fn apply_search_selection(&mut self) {
+    fn apply_search_selection(&mut self, backend: &Backend) {
         use super::search::SearchResultKind;
 
         let result = match self.search.selected_result() {
@@ -438,10 +1017,22 @@ impl App {
                 }
                 self.active_pane = Pane::Sidebar;
             }
-            SearchResultKind::Message(msg_idx) => {
-                // Select the message in the messages pane.
-                if msg_idx < self.messages.messages.len() {
-                    self.messages.selected = msg_idx;
+            SearchResultKind::Message {
+                chat_id,
+                message_id,
+            } => {
+                if self.current_chat_id.as_deref() == Some(&chat_id) {
+                    self.messages.select_by_id(&message_id);
+                } else {
+                    // Open the other conversation and select the message once loaded.
+                    let name = if self.sidebar.select_by_id(&chat_id) {
+                        self.sidebar.selected_item_name()
+                    } else {
+                        None
+                    };
+                    let name = name.unwrap_or_else(|| chat_id.clone());
+                    self.open_conversation(chat_id, name, backend);
+                    self.messages.pending_select = Some(message_id);
                 }
                 self.active_pane = Pane::Messages;
             }
@@ -455,6 +1046,7 @@ impl App {
         match response {
             BackendResponse::Teams(Ok(teams)) => {
                 self.sidebar.update_teams(teams);
+                self.apply_pending_unread();
                 self.sidebar.loading = false;
                 self.close_stale_search();
                 // If this is the first data load and we have teams, select the first
@@ -469,8 +1061,13 @@ impl App {
             }
             BackendResponse::Chats(Ok(chats)) => {
                 self.sidebar.update_chats(chats);
+                self.apply_pending_unread();
                 self.sidebar.loading = false;
                 self.close_stale_search();
+                let mris = self.sidebar.partners();
+                if !mris.is_empty() {
+                    backend.send(BackendCommand::LoadPresences { mris });
+                }
             }
             BackendResponse::Chats(Err(e)) => {
                 self.set_error(format!("Failed to load chats: {:#}", e));
@@ -483,6 +1080,8 @@ impl App {
                         Ok(msgs) => {
                             let header = self.messages.channel_header.clone();
                             self.messages.update_messages(&header, msgs);
+                            self.request_image_previews(backend);
+                            self.mark_read(backend);
                             self.close_stale_search();
                         }
                         Err(e) => {
@@ -492,6 +1091,20 @@ impl App {
                     }
                 }
             }
+            BackendResponse::OlderMessages { chat_id, result } => {
+                if self.current_chat_id.as_deref() == Some(&chat_id) {
+                    match result {
+                        Ok(page) => {
+                            self.messages.prepend_messages(page);
+                            self.request_image_previews(backend);
+                        }
+                        Err(e) => {
+                            self.messages.loading_older = false;
+                            self.set_error(format!("Failed to load older messages: {:#}", e));
+                        }
+                    }
+                }
+            }
             BackendResponse::MessageSent(Ok(())) => {
                 self.status_message = Some("Message sent".to_string());
                 self.status_is_error = false;
@@ -506,8 +1119,97 @@ impl App {
             BackendResponse::MessageSent(Err(e)) => {
                 self.set_error(format!("Failed to send message: {:#}", e));
             }
+            BackendResponse::MessageEditSent(Ok(())) => {
+                self.status_message = Some("Message edited".to_string());
+                self.status_is_error = false;
+                if let Some(ref chat_id) = self.current_chat_id {
+                    backend.send(BackendCommand::LoadMessages {
+                        chat_id: chat_id.clone(),
+                        limit: 50,
+                    });
+                }
+            }
+            BackendResponse::MessageEditSent(Err(e)) => {
+                self.set_error(format!("Failed to edit message: {:#}", e));
+            }
+            BackendResponse::MessageDeleteSent {
+                chat_id,
+                message_id,
+                result: Ok(()),
+            } => {
+                self.status_message = Some("Message deleted".to_string());
+                self.status_is_error = false;
+                if self.current_chat_id.as_deref() == Some(&chat_id) {
+                    self.messages.remove_message(&message_id);
+                }
+            }
+            BackendResponse::MessageDeleteSent { result: Err(e), .. } => {
+                self.set_error(format!("Failed to delete message: {:#}", e));
+            }
+            BackendResponse::AttachmentsSaved(Ok(paths)) => {
+                let msg = match paths.as_slice() {
+                    [path] => format!("Saved {}", path.display()),
+                    _ => match paths.first().and_then(|p| p.parent()) {
+                        Some(dir) => format!("Saved {} files to {}", paths.len(), dir.display()),
+                        None => format!("Saved {} files", paths.len()),
+                    },
+                };
+                self.status_message = Some(msg);
+                self.status_is_error = false;
+            }
+            BackendResponse::AttachmentsSaved(Err(e)) => {
+                self.set_error(format!("Failed to save attachment: {:#}", e));
+            }
+            BackendResponse::ReactionSent(Ok(())) => {
+                if let Some(ref chat_id) = self.current_chat_id {
+                    backend.send(BackendCommand::LoadMessages {
+                        chat_id: chat_id.clone(),
+                        limit: 50,
+                    });
+                }
+            }
+            BackendResponse::ReactionSent(Err(e)) => {
+                self.set_error(format!("Failed to update reaction: {:#}", e));
+            }
+            BackendResponse::ImagePreview { url, result } => {
+                self.previews.insert(url, result);
+            }
+            BackendResponse::Members { chat_id, result } => match result {
+                Ok(members) if self.current_chat_id.as_deref() == Some(&chat_id) => {
+                    self.members = members;
+                }
+                Ok(_) => {}
+                Err(e) if self.member_panel.is_some() => {
+                    self.set_error(format!("Failed to load members: {:#}", e));
+                }
+                // Only mention autocomplete depends on this.
+                Err(e) => tracing::debug!("Failed to load members of {}: {:#}", chat_id, e),
+            },
+            BackendResponse::MembersChanged { chat_id, result } => match result {
+                Ok(done) => {
+                    self.status_message = Some(done);
+                    self.status_is_error = false;
+                    if self.current_chat_id.as_deref() == Some(&chat_id) {
+                        backend.send(BackendCommand::LoadMembers { chat_id });
+                    }
+                }
+                Err(e) => self.set_error(format!("{:#}", e)),
+            },
+            BackendResponse::LeftChat { chat_id, result } => match result {
+                Ok(()) => {
+                    self.status_message = Some("Left the chat.".to_string());
+                    self.status_is_error = false;
+                    if self.current_chat_id.as_deref() == Some(&chat_id) {
+                        self.close_conversation();
+                    }
+                    backend.send(BackendCommand::LoadChats { limit: 50 });
+                }
+                Err(e) => self.set_error(format!("Failed to leave chat: {:#}", e)),
+            },
             BackendResponse::UserInfo(Ok(info)) => {
                 self.user_name = info.display_name;
+                self.messages.user_mri = Some(api::user_mri(&info.id));
+                self.user_id = info.id;
             }
             BackendResponse::UserInfo(Err(e)) => {
                 self.set_error(format!("Failed to load user info: {:#}", e));
@@ -528,6 +1230,105 @@ impl App {
                 self.connection_state = "Connected".to_string();
                 self.is_online = true;
             }
+            BackendResponse::UserPresence(Ok(updates)) => {
+                for update in updates {
+                    self.sidebar.set_presence(&update.mri, &update.availability);
+                }
+            }
+            BackendResponse::UserPresence(Err(e)) => {
+                // Without presence the sidebar just shows no dots.
+                tracing::debug!("Failed to load chat partners' presence: {:#}", e);
+            }
+            BackendResponse::NewMessage { chat_id, message } => {
+                if self.current_chat_id.as_deref() == Some(&chat_id) {
+                    self.messages.push_message(message);
+                    self.request_image_previews(backend);
+                    self.mark_read(backend);
+                } else if message.sender != self.user_name && !self.sidebar.mark_unread(&chat_id) {
+                    // Unknown conversation (e.g. someone started a new chat): refresh the list.
+                    backend.send(BackendCommand::LoadChats { limit: 50 });
+                }
+            }
+            BackendResponse::MessageEdited { chat_id, message } => {
+                if self.current_chat_id.as_deref() == Some(&chat_id) {
+                    self.messages.edit_message(message);
+                    self.request_image_previews(backend);
+                }
+            }
+            BackendResponse::MessageDeleted {
+                chat_id,
+                message_id,
+            } => {
+                if self.current_chat_id.as_deref() == Some(&chat_id) {
+                    self.messages.remove_message(&message_id);
+                }
+            }
+            BackendResponse::Unread(Ok(conversations)) => {
+                for conv in conversations {
+                    let horizon = self.read_horizons.entry(conv.id.clone()).or_default();
+                    *horizon = (*horizon).max(conv.last_read);
+                    if self.current_chat_id.as_deref() == Some(&conv.id) {
+                        continue;
+                    }
+                    if !self.sidebar.set_unread(&conv.id, conv.unread) && conv.unread > 0 {
+                        self.pending_unread.insert(conv.id, conv.unread);
+                    }
+                }
+            }
+            BackendResponse::Unread(Err(e)) => {
+                tracing::warn!("Failed to load unread counts: {:#}", e);
+            }
+            BackendResponse::SearchIndex(Ok(index)) => {
+                if self.search.active {
+                    self.search.index = Some(index);
+                    self.search.update_results(
+                        &self.sidebar,
+                        &self.messages,
+                        self.current_chat_id.as_deref(),
+                    );
+                }
+            }
+            BackendResponse::SearchIndex(Err(e)) => {
+                tracing::warn!("Failed to load search index: {:#}", e);
+            }
+            BackendResponse::RemoteSearch { query, result } => {
+                if self.search.active && self.search.query.trim() == query {
+                    match result {
+                        Ok(hits) => {
+                            self.search.remote_hits = hits;
+                            self.search.remote_query = query;
+                            self.search.update_results(
+                                &self.sidebar,
+                                &self.messages,
+                                self.current_chat_id.as_deref(),
+                            );
+                        }
+                        Err(e) => tracing::warn!("Server search failed: {:#}", e),
+                    }
+                }
+            }
+            BackendResponse::People { query, result } => {
+                if let Some(ref mut picker) = self.people_picker {
+                    match result {
+                        Ok(people) => picker.set_results(&query, people),
+                        Err(e) => {
+                            picker.searching = false;
+                            self.set_error(format!("People search failed: {:#}", e));
+                        }
+                    }
+                }
+            }
+            BackendResponse::ChatCreated { name, result } => match result {
+                Ok(id) => {
+                    backend.send(BackendCommand::LoadChats { limit: 50 });
+                    self.open_conversation(id, name, backend);
+                    self.active_pane = Pane::Compose;
+                }
+                Err(e) => self.set_error(format!("Failed to start chat: {:#}", e)),
+            },
+            BackendResponse::PushState(state) => {
+                self.push_state = state;
+            }
             BackendResponse::ClientError(msg) => {
                 self.connection_state = "Not authenticated".to_string();
                 self.is_online = false;
@@ -537,6 +1338,68 @@ impl App {
         }
     }
 
+    /// Stop showing the current conversation (e.g. after leaving it).
+    fn close_conversation(&mut self) {
+        self.current_chat_id = None;
+        self.channel_name.clear();
+        self.messages.messages.clear();
+        self.messages.channel_header.clear();
+        self.messages.older_link = None;
+        self.messages.pending_select = None;
+        self.compose.cancel_mode();
+        self.members.clear();
+        self.member_panel = None;
+        self.active_pane = Pane::Sidebar;
+    }
+
+    /// Badge sidebar items that have appeared since unread counts arrived.
+    fn apply_pending_unread(&mut self) {
+        let sidebar = &mut self.sidebar;
+        self.pending_unread
+            .retain(|id, count| !sidebar.set_unread(id, *count));
+    }
+
+    /// Move our consumption horizon to the newest message of the current
+    /// conversation, if it is past the last one marked read.
+    fn mark_read(&mut self, backend: &Backend) {
+        let Some(ref chat_id) = self.current_chat_id else {
+            return;
+        };
+        let Some((order, newest)) = self.messages.newest_message() else {
+            return;
+        };
+        let horizon = self.read_horizons.entry(chat_id.clone()).or_default();
+        if order > *horizon {
+            *horizon = order;
+            backend.send(BackendCommand::MarkRead {
+                chat_id: chat_id.clone(),
+                message_id: newest.id.clone(),
+                client_message_id: newest.client_message_id.clone(),
+            });
+        }
+    }
+
+    /// Fetch previews for image attachments that have not been requested yet.
+    fn request_image_previews(&mut self, backend: &Backend) {
+        let picker = self.previews.picker();
+        let urls: Vec<String> = self
+            .messages
+            .messages
+            .iter()
+            .flat_map(|m| std::iter::once(m).chain(&m.replies))
+            .flat_map(|m| &m.attachments)
+            .filter(|a| a.kind == api::AttachmentKind::Image)
+            // Fetching sends our Skype token: never to a host the sender chose.
+            .filter(|a| api::client::is_ams_domain_url(&a.url))
+            .map(|a| a.url.clone())
+            .collect();
+        for url in urls {
+            if self.previews.request(&url) {
+                backend.send(BackendCommand::LoadImagePreview { url, picker });
+            }
+        }
+    }
+
     /// Close the search overlay if it's open.
     ///
     /// Called when backend data arrives to prevent stale search result indices
@@ -554,7 +1417,7 @@ impl App {
     }
 
     /// Render the UI
-    pub fn render(&self, frame: &mut ratatui::Frame) {
+    pub fn render(&mut self, frame: &mut ratatui::Frame) {
         ui::render(frame, self);
     }
 }
@@ -579,6 +1442,8 @@ pub async fn run(log_buffer: LogBuffer) -> Result<()> {
 
 async fn run_app(terminal: &mut DefaultTerminal, log_buffer: LogBuffer) -> Result<()> {
     let mut app = App::new(log_buffer);
+    // Query graphics support before the event stream takes over stdin.
+    app.previews = ImagePreviews::detect();
     let mut backend = Backend::start();
     let mut events = EventStream::new();
 
@@ -587,6 +1452,7 @@ async fn run_app(terminal: &mut DefaultTerminal, log_buffer: LogBuffer) -> Resul
     backend.send(BackendCommand::LoadChats { limit: 50 });
     backend.send(BackendCommand::LoadUserInfo);
     backend.send(BackendCommand::LoadPresence);
+    backend.send(BackendCommand::LoadUnread);
 
     while !app.should_exit {
         // Drain log buffer before rendering to keep it from growing unbounded.
@@ -3,22 +3,147 @@
 //! Uses an mpsc channel pair. The TUI sends `BackendCommand` values, and a
 //! background tokio task executes them and sends `BackendResponse` values back.
 
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::Result;
-use tokio::sync::mpsc;
+use anyhow::{Context, Result};
+use ratatui_image::{picker::Picker, protocol::Protocol};
+use tokio::sync::{broadcast, mpsc, watch};
+
+use super::images;
 
 use crate::api;
 use crate::api::client::TeamsClient;
+use crate::cache::search::SearchIndex;
+use crate::cache::{self, MessageCache};
+use crate::trouter::events::{PresenceUpdate, TrouterEvent};
+use crate::trouter::{ConnectionState, TrouterClient};
+
+/// Quiet period before a server-side search is sent.
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(400);
+
+/// Maximum server-side hits requested per search.
+const REMOTE_SEARCH_LIMIT: usize = 25;
+
+/// Maximum directory matches requested for the people picker.
+const PEOPLE_SEARCH_LIMIT: usize = 8;
+
+/// Recent conversations checked for unread messages.
+const UNREAD_CONVERSATIONS: usize = 100;
 
 /// Commands sent from the TUI event loop to the async backend.
 pub enum BackendCommand {
     LoadTeams,
-    LoadChats { limit: usize },
-    LoadMessages { chat_id: String, limit: usize },
-    SendMessage { chat_id: String, message: String },
+    LoadChats {
+        limit: usize,
+    },
+    LoadMessages {
+        chat_id: String,
+        limit: usize,
+    },
+    /// Fetch the page before the oldest loaded message.
+    LoadOlderMessages {
+        chat_id: String,
+        backward_link: String,
+    },
+    SendMessage {
+        chat_id: String,
+        message: String,
+        mentions: Vec<api::Mention>,
+        /// Message quoted above the text when replying in a chat.
+        quote: Option<api::QuotedMessage>,
+    },
+    /// Reply to a channel thread rooted at `parent_id`.
+    SendChannelReply {
+        channel_id: String,
+        parent_id: String,
+        message: String,
+        mentions: Vec<api::Mention>,
+    },
+    EditMessage {
+        chat_id: String,
+        message_id: String,
+        message: String,
+        mentions: Vec<api::Mention>,
+        /// Quote of an edited reply, kept above the new text.
+        quote: Option<api::QuotedMessage>,
+    },
+    DeleteMessage {
+        chat_id: String,
+        message_id: String,
+    },
+    /// Upload a file and send it to a chat, with optional caption text.
+    SendFile {
+        chat_id: String,
+        path: PathBuf,
+        message: Option<String>,
+    },
+    /// Download attachments into the user's download directory.
+    SaveAttachments {
+        attachments: Vec<api::AttachmentInfo>,
+    },
+    /// Fetch an image attachment and encode it for `picker`.
+    LoadImagePreview {
+        url: String,
+        picker: Picker,
+    },
+    /// Add (`add = true`) or remove our reaction on a message.
+    SetReaction {
+        chat_id: String,
+        message_id: String,
+        key: String,
+        add: bool,
+    },
+    /// Fetch the members of a chat or channel (mention autocomplete).
+    LoadMembers {
+        chat_id: String,
+    },
     LoadUserInfo,
     LoadPresence,
+    /// Fetch the presence of these users (MRIs) and follow their changes
+    /// over Trouter.
+    LoadPresences {
+        mris: Vec<String>,
+    },
+    /// Count unread messages from the consumption horizons.
+    LoadUnread,
+    /// Move our consumption horizon to `message_id` (fire and forget).
+    MarkRead {
+        chat_id: String,
+        message_id: String,
+        client_message_id: Option<String>,
+    },
+    /// Index the local message cache for the search overlay.
+    LoadSearchIndex,
+    /// Server-side message search (debounced: superseded queries are dropped).
+    SearchRemote {
+        query: String,
+    },
+    /// Directory search for the people picker (debounced like `SearchRemote`).
+    SearchPeople {
+        query: String,
+    },
+    /// Start a chat with `people` (a 1:1 chat for one person).
+    CreateChat {
+        people: Vec<api::PersonInfo>,
+    },
+    /// Add `people` to a group chat.
+    AddMembers {
+        chat_id: String,
+        people: Vec<api::PersonInfo>,
+    },
+    /// Remove the member `mri` (shown as `name`) from a group chat.
+    RemoveMember {
+        chat_id: String,
+        mri: String,
+        name: String,
+    },
+    /// Leave a group chat.
+    LeaveChat {
+        chat_id: String,
+    },
 }
 
 /// Responses from the async backend to the TUI.
@@ -27,11 +152,86 @@ pub enum BackendResponse {
     Chats(Result<Vec<api::ChatInfo>>),
     Messages {
         chat_id: String,
-        result: Result<Vec<api::MessageInfo>>,
+        result: Result<api::MessagePage>,
+    },
+    /// An older page of history for a conversation (scroll-up backfill).
+    OlderMessages {
+        chat_id: String,
+        result: Result<api::MessagePage>,
     },
     MessageSent(Result<()>),
+    /// Result of editing one of our messages.
+    MessageEditSent(Result<()>),
+    /// Result of deleting one of our messages.
+    MessageDeleteSent {
+        chat_id: String,
+        message_id: String,
+        result: Result<()>,
+    },
+    /// Paths of saved attachments.
+    AttachmentsSaved(Result<Vec<PathBuf>>),
+    /// Result of adding or removing a reaction.
+    ReactionSent(Result<()>),
+    /// An encoded inline preview for the image at `url`.
+    ImagePreview {
+        url: String,
+        result: Result<Protocol>,
+    },
+    Members {
+        chat_id: String,
+        result: Result<Vec<api::MemberInfo>>,
+    },
     UserInfo(Result<api::UserInfo>),
     Presence(Result<api::PresenceInfo>),
+    /// Presence of other users, fetched or pushed via Trouter.
+    UserPresence(Result<Vec<PresenceUpdate>>),
+    /// Read state of recent chats and channels.
+    Unread(Result<Vec<api::UnreadInfo>>),
+    /// A message was pushed to a conversation via Trouter.
+    NewMessage {
+        chat_id: String,
+        message: api::MessageInfo,
+    },
+    /// A message was edited (pushed via Trouter).
+    MessageEdited {
+        chat_id: String,
+        message: api::MessageInfo,
+    },
+    /// A message was deleted (pushed via Trouter).
+    MessageDeleted {
+        chat_id: String,
+        message_id: String,
+    },
+    /// Search index over cached history.
+    SearchIndex(Result<SearchIndex>),
+    /// Server-side search hits for `query`.
+    RemoteSearch {
+        query: String,
+        result: Result<Vec<api::SearchHit>>,
+    },
+    /// Directory matches for the people picker's `query`.
+    People {
+        query: String,
+        result: Result<Vec<api::PersonInfo>>,
+    },
+    /// A new chat was created (or an existing 1:1 found), titled `name`.
+    ChatCreated {
+        name: String,
+        result: Result<String>,
+    },
+    /// Members were added to or removed from `chat_id`; `Ok` describes the
+    /// change.
+    MembersChanged {
+        chat_id: String,
+        result: Result<String>,
+    },
+    /// Result of leaving `chat_id`.
+    LeftChat {
+        chat_id: String,
+        result: Result<()>,
+    },
+    /// The Trouter push connection changed state.
+    PushState(ConnectionState),
     /// Initial client creation failed (auth issue).
     ClientError(String),
 }
@@ -89,9 +289,30 @@ async fn backend_loop(
         }
     };
 
+    // The message cache is an optimisation; run without it if unavailable.
+    let cache = match MessageCache::open() {
+        Ok(c) => Some(Arc::new(c)),
+        Err(e) => {
+            tracing::warn!("Message cache unavailable: {:#}", e);
+            None
+        }
+    };
+
+    // Keep a Trouter session running for live message delivery.
+    let (presence_tx, presence_rx) = watch::channel(Vec::new());
+    tokio::spawn(push_loop(resp_tx.clone(), presence_rx));
+
+    // Generation counters for debouncing server-side search requests.
+    let search_generation = Arc::new(AtomicU64::new(0));
+    let people_generation = Arc::new(AtomicU64::new(0));
+
     while let Some(cmd) = cmd_rx.recv().await {
         let client = Arc::clone(&client);
+        let cache = cache.clone();
         let resp_tx = resp_tx.clone();
+        let search_generation = Arc::clone(&search_generation);
+        let people_generation = Arc::clone(&people_generation);
+        let presence_tx = presence_tx.clone();
 
         // Spawn each command as a separate task so we don't block the loop.
         tokio::spawn(async move {
@@ -104,14 +325,142 @@ async fn backend_loop(
                     let result = api::list_chats_data(&client, limit).await;
                     let _ = resp_tx.send(BackendResponse::Chats(result));
                 }
-                BackendCommand::LoadMessages { chat_id, limit } => {
-                    let result = api::read_messages_data(&client, &chat_id, limit).await;
-                    let _ = resp_tx.send(BackendResponse::Messages { chat_id, result });
+                BackendCommand::LoadMessages { chat_id, limit } => match cache {
+                    Some(cache) => {
+                        load_messages_cached(&client, &cache, chat_id, limit, &resp_tx).await;
+                    }
+                    None => {
+                        let result = api::read_messages_page(&client, &chat_id, limit).await;
+                        let _ = resp_tx.send(BackendResponse::Messages { chat_id, result });
+                    }
+                },
+                BackendCommand::LoadOlderMessages {
+                    chat_id,
+                    backward_link,
+                } => {
+                    let result = match cache {
+                        Some(cache) => {
+                            cache::backfill_conversation(&client, &cache, &chat_id, &backward_link)
+                                .await
+                        }
+                        None => api::read_older_messages(&client, &backward_link).await,
+                    };
+                    let _ = resp_tx.send(BackendResponse::OlderMessages { chat_id, result });
+                }
+                BackendCommand::SendMessage {
+                    chat_id,
+                    message,
+                    mentions,
+                    quote,
+                } => {
+                    let result = api::send_message_with_client(
+                        &client,
+                        &chat_id,
+                        &message,
+                        &mentions,
+                        quote.as_ref(),
+                    )
+                    .await;
+                    let _ = resp_tx.send(BackendResponse::MessageSent(result));
+                }
+                BackendCommand::SendChannelReply {
+                    channel_id,
+                    parent_id,
+                    message,
+                    mentions,
+                } => {
+                    let result = api::reply_to_channel_message_with_client(
+                        &client,
+                        &channel_id,
+                        &parent_id,
+                        &message,
+                        &mentions,
+                    )
+                    .await;
+                    let _ = resp_tx.send(BackendResponse::MessageSent(result));
+                }
+                BackendCommand::EditMessage {
+                    chat_id,
+                    message_id,
+                    message,
+                    mentions,
+                    quote,
+                } => {
+                    let result = api::edit_message_with_client(
+                        &client,
+                        &chat_id,
+                        &message_id,
+                        &message,
+                        &mentions,
+                        quote.as_ref(),
+                    )
+                    .await;
+                    let _ = resp_tx.send(BackendResponse::MessageEditSent(result));
+                }
+                BackendCommand::DeleteMessage {
+                    chat_id,
+                    message_id,
+                } => {
+                    let result =
+                        api::delete_message_with_client(&client, &chat_id, &message_id).await;
+                    let _ = resp_tx.send(BackendResponse::MessageDeleteSent {
+                        chat_id,
+                        message_id,
+                        result,
+                    });
                 }
-                BackendCommand::SendMessage { chat_id, message } => {
-                    let result = api::send_message_with_client(&client, &chat_id, &message).await;
+                BackendCommand::SendFile {
+                    chat_id,
+                    path,
+                    message,
+                } => {
+                    let result =
+                        api::send_file_with_client(&client, &chat_id, &path, message.as_deref())
+                            .await;
                     let _ = resp_tx.send(BackendResponse::MessageSent(result));
                 }
+                BackendCommand::SaveAttachments { attachments } => {
+                    let dir = api::default_download_dir();
+                    let mut saved = Vec::new();
+                    let mut result = Ok(());
+                    for att in &attachments {
+                        match api::download_attachment_with_client(&client, att, &dir).await {
+                            Ok(path) => saved.push(path),
+                            Err(e) => {
+                                result = Err(e);
+                                break;
+                            }
+                        }
+                    }
+                    let _ = resp_tx.send(BackendResponse::AttachmentsSaved(result.map(|()| saved)));
+                }
+                BackendCommand::LoadImagePreview { url, picker } => {
+                    let result = match api::image_preview_with_client(&client, &url).await {
+                        Ok(bytes) => tokio::task::spawn_blocking(move || {
+                            images::encode_preview(picker, &bytes)
+                        })
+                        .await
+                        .context("Image preview task failed")
+                        .and_then(|r| r),
+                        Err(e) => Err(e),
+                    };
+                    let _ = resp_tx.send(BackendResponse::ImagePreview { url, result });
+                }
+                BackendCommand::SetReaction {
+                    chat_id,
+                    message_id,
+                    key,
+                    add,
+                } => {
+                    let result =
+                        api::set_reaction_with_client(&client, &chat_id, &message_id, &key, add)
+                            .await;
+                    let _ = resp_tx.send(BackendResponse::ReactionSent(result));
+                }
+                BackendCommand::LoadMembers { chat_id } => {
+                    let result = api::list_members_with_client(&client, &chat_id).await;
+                    let _ = resp_tx.send(BackendResponse::Members { chat_id, result });
+                }
                 BackendCommand::LoadUserInfo => {
                     let result = api::whoami_data(&client).await;
                     let _ = resp_tx.send(BackendResponse::UserInfo(result));
@@ -120,7 +469,217 @@ async fn backend_loop(
                     let result = api::get_presence_data(&client).await;
                     let _ = resp_tx.send(BackendResponse::Presence(result));
                 }
+                BackendCommand::LoadPresences { mris } => {
+                    presence_tx.send_replace(mris.clone());
+                    let ids: Vec<&str> = mris
+                        .iter()
+                        .filter_map(|mri| mri.strip_prefix("8:orgid:"))
+                        .collect();
+                    let result =
+                        api::get_presences_with_client(&client, &ids)
+                            .await
+                            .map(|presences| {
+                                presences
+                                    .into_iter()
+                                    .map(|p| PresenceUpdate {
+                                        mri: api::user_mri(&p.id),
+                                        availability: p.availability,
+                                        activity: Some(p.activity),
+                                    })
+                                    .collect()
+                            });
+                    let _ = resp_tx.send(BackendResponse::UserPresence(result));
+                }
+                BackendCommand::LoadUnread => {
+                    let result = api::list_unread_data(&client, UNREAD_CONVERSATIONS).await;
+                    let _ = resp_tx.send(BackendResponse::Unread(result));
+                }
+                BackendCommand::MarkRead {
+                    chat_id,
+                    message_id,
+                    client_message_id,
+                } => {
+                    let result = api::mark_read_with_client(
+                        &client,
+                        &chat_id,
+                        &message_id,
+                        client_message_id.as_deref(),
+                    )
+                    .await;
+                    if let Err(e) = result {
+                        tracing::warn!("Failed to mark {} read: {:#}", chat_id, e);
+                    }
+                }
+                BackendCommand::LoadSearchIndex => {
+                    let result = match cache {
+                        Some(cache) => {
+                            tokio::task::spawn_blocking(move || SearchIndex::load(&cache))
+                                .await
+                                .unwrap_or_else(|e| Err(anyhow::anyhow!(e)))
+                        }
+                        None => Ok(SearchIndex::default()),
+                    };
+                    let _ = resp_tx.send(BackendResponse::SearchIndex(result));
+                }
+                BackendCommand::SearchRemote { query } => {
+                    // Wait for typing to pause; drop this request if a newer one arrived.
+                    let generation = search_generation.fetch_add(1, Ordering::SeqCst) + 1;
+                    tokio::time::sleep(SEARCH_DEBOUNCE).await;
+                    if search_generation.load(Ordering::SeqCst) != generation {
+                        return;
+                    }
+                    let result =
+                        api::search_messages_data(&client, &query, REMOTE_SEARCH_LIMIT).await;
+                    let _ = resp_tx.send(BackendResponse::RemoteSearch { query, result });
+                }
+                BackendCommand::SearchPeople { query } => {
+                    let generation = people_generation.fetch_add(1, Ordering::SeqCst) + 1;
+                    tokio::time::sleep(SEARCH_DEBOUNCE).await;
+                    if people_generation.load(Ordering::SeqCst) != generation {
+                        return;
+                    }
+                    let result =
+                        api::search_people_with_client(&client, &query, PEOPLE_SEARCH_LIMIT).await;
+                    let _ = resp_tx.send(BackendResponse::People { query, result });
+                }
+                BackendCommand::CreateChat { people } => {
+                    let names: Vec<&str> = people.iter().map(|p| p.display_name.as_str()).collect();
+                    let name = names.join(", ");
+                    let result = api::create_chat_with_client(&client, &people, None).await;
+                    let _ = resp_tx.send(BackendResponse::ChatCreated { name, result });
+                }
+                BackendCommand::AddMembers { chat_id, people } => {
+                    let names: Vec<&str> = people.iter().map(|p| p.display_name.as_str()).collect();
+                    let done = format!("Added {}.", names.join(", "));
+                    let result = api::add_members_with_client(&client, &chat_id, &people)
+                        .await
+                        .map(|()| done);
+                    let _ = resp_tx.send(BackendResponse::MembersChanged { chat_id, result });
+                }
+                BackendCommand::RemoveMember { chat_id, mri, name } => {
+                    let result = api::remove_member_with_client(&client, &chat_id, &mri)
+                        .await
+                        .map(|()| format!("Removed {}.", name));
+                    let _ = resp_tx.send(BackendResponse::MembersChanged { chat_id, result });
+                }
+                BackendCommand::LeaveChat { chat_id } => {
+                    let result = api::leave_chat_with_client(&client, &chat_id).await;
+                    let _ = resp_tx.send(BackendResponse::LeftChat { chat_id, result });
+                }
             }
         });
     }
 }
+
+/// Answer `LoadMessages` from the cache first, then with the synced history
+/// once the delta fetch completes (only if it brought anything new).
+async fn load_messages_cached(
+    client: &TeamsClient,
+    cache: &MessageCache,
+    chat_id: String,
+    limit: usize,
+    resp_tx: &mpsc::UnboundedSender<BackendResponse>,
+) {
+    let cached = match cache.load(&chat_id) {
+        Ok(Some(conv)) if !conv.messages.is_empty() => {
+            let _ = resp_tx.send(BackendResponse::Messages {
+                chat_id: chat_id.clone(),
+                result: Ok(conv.page()),
+            });
+            true
+        }
+        Ok(_) => false,
+        Err(e) => {
+            tracing::warn!("Failed to read message cache: {:#}", e);
+            false
+        }
+    };
+
+    let result = match cache::sync_conversation(client, cache, &chat_id, limit).await {
+        Ok((_, false)) if cached => return,
+        Ok((page, _)) => Ok(page),
+        Err(e) if cached => Err(e.context("offline, showing cached messages")),
+        Err(e) => Err(e),
+    };
+    let _ = resp_tx.send(BackendResponse::Messages { chat_id, result });
+}
+
+/// Run a Trouter client and forward decoded chat events and connection
+/// state changes to the TUI, following the presence of the users sent on
+/// `presence_rx`.
+///
+/// Exits (shutting the client down) when the TUI drops its response receiver.
+async fn push_loop(
+    resp_tx: mpsc::UnboundedSender<BackendResponse>,
+    mut presence_rx: watch::Receiver<Vec<String>>,
+) {
+    let client = TrouterClient::start();
+    let mut events = client.subscribe();
+    let mut state = client.state();
+
+    loop {
+        let response = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => match push_response(event) {
+                    Some(response) => response,
+                    None => continue,
+                },
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("Dropped {} Trouter events", n);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            changed = state.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                BackendResponse::PushState(state.borrow_and_update().clone())
+            }
+            Ok(()) = presence_rx.changed() => {
+                client.watch_presence(presence_rx.borrow_and_update().clone());
+                continue;
+            }
+            _ = resp_tx.closed() => break,
+        };
+        if resp_tx.send(response).is_err() {
+            break;
+        }
+    }
+
+    client.shutdown().await;
+    tracing::debug!("Trouter client stopped");
+}
+
+/// Map a Trouter event to the TUI response it drives, if any.
+fn push_response(event: TrouterEvent) -> Option<BackendResponse> {
+    let response = match event {
+        TrouterEvent::NewMessage {
+            conversation_id,
+            message,
+        } => BackendResponse::NewMessage {
+            chat_id: conversation_id,
+            message,
+        },
+        TrouterEvent::MessageEdited {
+            conversation_id,
+            message,
+        } => BackendResponse::MessageEdited {
+            chat_id: conversation_id,
+            message,
+        },
+        TrouterEvent::MessageDeleted {
+            conversation_id,
+            message_id,
+        } => BackendResponse::MessageDeleted {
+            chat_id: conversation_id,
+            message_id,
+        },
+        TrouterEvent::PresenceChanged(updates) => BackendResponse::UserPresence(Ok(updates)),
+        other => {
+            tracing::debug!("Unhandled Trouter event: {:?}", other);
+            return None;
+        }
+    };
+    Some(response)
+}
@@ -5,9 +5,55 @@ use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, Paragraph, Widget},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Widget},
     Frame,
 };
+use unicode_width::UnicodeWidthStr;
+
+use crate::api::{MemberInfo, QuotedMessage};
+
+/// Most members listed in the mention popup.
+const MAX_MENTION_CANDIDATES: usize = 6;
+
+/// Characters of the replied-to message shown in the compose title.
+const TITLE_PREVIEW_CHARS: usize = 30;
+
+/// Longest text after an `@` still treated as a mention being typed.
+const MAX_MENTION_QUERY_CHARS: usize = 40;
+
+/// Markdown formatting inserted by the toolbar shortcuts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Bold,
+    Italic,
+    Underline,
+    Strike,
+    Code,
+    Link,
+}
+
+impl Format {
+    /// Opening and closing Markdown markers.
+    fn markers(self) -> (&'static str, &'static str) {
+        match self {
+            Format::Bold => ("**", "**"),
+            Format::Italic => ("_", "_"),
+            Format::Underline => ("++", "++"),
+            Format::Strike => ("~~", "~~"),
+            Format::Code => ("`", "`"),
+            Format::Link => ("[", "]()"),
+        }
+    }
+}
+
+/// An `@` mention being typed.
+#[derive(Debug, Clone, Copy)]
+pub struct MentionQuery {
+    /// Character offset of the `@`.
+    pub start: usize,
+    /// Highlighted entry in the candidate list.
+    pub selected: usize,
+}
 
 /// State for the compose box.
 #[derive(Default)]
@@ -16,6 +62,12 @@ pub struct ComposeState {
     pub input: String,
     /// Cursor position (character offset into `input`).
     pub cursor_pos: usize,
+    /// Set while replying to a channel thread instead of starting a new post.
+    pub reply_to: Option<QuotedMessage>,
+    /// ID of the message being edited; sending replaces its content.
+    pub editing: Option<String>,
+    /// Set after typing `@`, while the mention popup may be shown.
+    pub mention: Option<MentionQuery>,
 }
 
 impl ComposeState {
@@ -24,6 +76,44 @@ impl ComposeState {
         let byte_pos = self.char_to_byte(self.cursor_pos);
         self.input.insert(byte_pos, c);
         self.cursor_pos += 1;
+
+        if c == '@' {
+            let prev = self.input.chars().nth(self.cursor_pos.wrapping_sub(2));
+            if self.cursor_pos == 1 || prev.is_some_and(char::is_whitespace) {
+                self.mention = Some(MentionQuery {
+                    start: self.cursor_pos - 1,
+                    selected: 0,
+                });
+            }
+        }
+    }
+
+    /// Text typed after the `@` of the mention in progress, if the cursor
+    /// is still inside it.
+    pub fn mention_query(&self) -> Option<String> {
+        let mention = self.mention?;
+        if self.cursor_pos <= mention.start || self.input.chars().nth(mention.start) != Some('@') {
+            return None;
+        }
+        let query: String = self
+            .input
+            .chars()
+            .skip(mention.start + 1)
+            .take(self.cursor_pos - mention.start - 1)
+            .collect();
+        (!query.contains('\n') && query.chars().count() <= MAX_MENTION_QUERY_CHARS).then_some(query)
+    }
+
+    /// Replace the mention in progress with `@name `.
+    pub fn accept_mention(&mut self, name: &str) {
+        let Some(mention) = self.mention.take() else {
+            return;
+        };
+        let start = self.char_to_byte(mention.start);
+        let end = self.char_to_byte(self.cursor_pos);
+        let text = format!("@{} ", name);
+        self.input.replace_range(start..end, &text);
+        self.cursor_pos = mention.start + text.chars().count();
     }
 
     /// Insert a newline at the current cursor position.
@@ -76,10 +166,62 @@ impl ComposeState {
         self.cursor_pos = self.input.chars().count();
     }
 
+    /// Apply Markdown formatting at the cursor. A word ending at the cursor
+    /// is wrapped in the markers and the cursor moves past them (for links,
+    /// into the `()` for the URL); otherwise an empty pair is inserted with
+    /// the cursor between them.
+    pub fn apply_format(&mut self, format: Format) {
+        let (open, close) = format.markers();
+        let chars: Vec<char> = self.input.chars().collect();
+        let word_start = chars[..self.cursor_pos]
+            .iter()
+            .rposition(|c| c.is_whitespace())
+            .map_or(0, |i| i + 1);
+
+        let open_at = self.char_to_byte(word_start);
+        self.input.insert_str(open_at, open);
+        let close_at = self.char_to_byte(self.cursor_pos + open.chars().count());
+        self.input.insert_str(close_at, close);
+
+        let wrapped = word_start < self.cursor_pos;
+        self.cursor_pos += open.chars().count();
+        if wrapped {
+            self.cursor_pos += match format {
+                // Step over "](" into the URL slot.
+                Format::Link => 2,
+                _ => close.chars().count(),
+            };
+        }
+    }
+
+    /// Load an existing message into the box for editing, keeping the
+    /// quote of a chat reply.
+    pub fn start_edit(&mut self, message_id: &str, content: &str, quote: Option<QuotedMessage>) {
+        self.reply_to = quote;
+        self.editing = Some(message_id.to_string());
+        self.input = content.to_string();
+        self.move_end();
+    }
+
+    /// Leave reply/edit mode. Edited text is discarded, since it only
+    /// makes sense for the message it was loaded from.
+    pub fn cancel_mode(&mut self) {
+        self.reply_to = None;
+        if self.editing.take().is_some() {
+            self.clear();
+        }
+    }
+
+    /// Whether a reply or edit is in progress.
+    pub fn has_mode(&self) -> bool {
+        self.reply_to.is_some() || self.editing.is_some()
+    }
+
     /// Clear all input text (Ctrl+U).
     pub fn clear(&mut self) {
         self.input.clear();
         self.cursor_pos = 0;
+        self.mention = None;
     }
 
     /// "Send" the message: return the current text and clear the box.
@@ -89,8 +231,7 @@ impl ComposeState {
         if text.is_empty() {
             return None;
         }
-        self.input.clear();
-        self.cursor_pos = 0;
+        self.clear();
         Some(text)
     }
 
@@ -115,7 +256,8 @@ pub const COMPOSE_HEIGHT: u16 = 4;
 ///
 /// The compose box consists of:
 ///   - Top border
-///   - Toolbar line: B  I  U  ~  link  clip  :)  ... >
+///   - Toolbar line: B  I  U  ~  </>  link  clip  :)  ... >
+///     (Alt+B/I/U/S/C/K insert the formatting)
 ///   - Input line: placeholder or typed text
 ///   - Bottom border
 ///
@@ -139,10 +281,31 @@ pub fn render(
         BorderType::Plain
     };
 
-    let block = Block::default()
+    let mut block = Block::default()
         .borders(Borders::ALL)
         .border_type(border_type)
         .border_style(border_style);
+    let mode_title = match (&state.reply_to, &state.editing) {
+        (Some(reply), Some(_)) => Some(format!(
+            " Editing reply to {} (Esc to cancel) ",
+            reply.sender
+        )),
+        (Some(reply), None) => {
+            let mut preview: String = reply.preview.chars().take(TITLE_PREVIEW_CHARS).collect();
+            if preview.len() < reply.preview.len() {
+                preview.push('\u{2026}');
+            }
+            Some(format!(
+                " Reply to {}: \"{}\" (Esc to cancel) ",
+                reply.sender, preview
+            ))
+        }
+        (None, Some(_)) => Some(" Editing message (Esc to cancel) ".to_string()),
+        (None, None) => None,
+    };
+    if let Some(title) = mode_title {
+        block = block.title(Span::styled(title, Style::default().fg(Color::Cyan)));
+    }
 
     let inner = block.inner(area);
 
@@ -200,7 +363,7 @@ fn render_toolbar(area: Rect, buf: &mut Buffer, focused: bool) {
     let w = area.width as usize;
 
     // Left side: formatting icons
-    let left_items = " \u{1D401}  \u{1D43C}  U  ~  \u{1F517}  \u{1F4CE}  \u{1F60A}";
+    let left_items = " \u{1D401}  \u{1D43C}  U  ~  </>  \u{1F517}  \u{1F4CE}  \u{1F60A}";
     // Right side: camera, mic, send
     let right_items = "\u{1F4F7}  \u{1F3A4}  \u{27A4}";
 
@@ -218,8 +381,8 @@ fn render_toolbar(area: Rect, buf: &mut Buffer, focused: bool) {
         Style::default().fg(Color::DarkGray)
     };
 
-    let left_w = unicode_width::UnicodeWidthStr::width(left_items);
-    let right_w = unicode_width::UnicodeWidthStr::width(right_items) + 1; // include trailing space
+    let left_w = left_items.width();
+    let right_w = right_items.width() + 1; // include trailing space
     let padding = w.saturating_sub(left_w + right_w);
 
     let line = Line::from(vec![
@@ -240,7 +403,10 @@ fn render_input(area: Rect, buf: &mut Buffer, state: &ComposeState, channel_name
 
     if state.input.is_empty() {
         // Show placeholder text.
-        let placeholder = format!(" Type a message to {}...", channel_name);
+        let placeholder = match state.reply_to {
+            Some(_) => " Type a reply...".to_string(),
+            None => format!(" Type a message to {}...", channel_name),
+        };
         let style = Style::default().fg(Color::DarkGray);
         let truncated: String = placeholder.chars().take(w).collect();
         let line = Line::from(Span::styled(truncated, style));
@@ -256,6 +422,86 @@ fn render_input(area: Rect, buf: &mut Buffer, state: &ComposeState, channel_name
     }
 }
 
+/// Members matching a mention query: names or email addresses with a word
+/// starting with it (case-insensitive), excluding ourselves.
+pub fn mention_candidates<'a>(
+    members: &'a [MemberInfo],
+    query: &str,
+    own_mri: Option<&str>,
+) -> Vec<&'a MemberInfo> {
+    let query = query.trim().to_lowercase();
+    members
+        .iter()
+        .filter(|m| Some(m.mri.as_str()) != own_mri)
+        .filter(|m| {
+            let name = m.display_name.to_lowercase();
+            name.starts_with(&query)
+                || name.split_whitespace().any(|w| w.starts_with(&query))
+                || m.email
+                    .as_deref()
+                    .is_some_and(|e| e.to_lowercase().starts_with(&query))
+        })
+        .take(MAX_MENTION_CANDIDATES)
+        .collect()
+}
+
+/// Render the mention popup just above the compose box.
+pub fn render_mention_popup(
+    frame: &mut Frame,
+    compose_area: Rect,
+    candidates: &[&MemberInfo],
+    selected: usize,
+) {
+    let width = candidates
+        .iter()
+        .map(|m| m.display_name.width() + m.email.as_deref().map_or(0, |e| e.width() + 2))
+        .max()
+        .unwrap_or(0)
+        .clamp(24, 60) as u16
+        + 4;
+    let height = candidates.len() as u16 + 2;
+    let width = width.min(compose_area.width);
+    let y = compose_area.y.saturating_sub(height);
+    let area = Rect::new(compose_area.x + 1, y, width, height.min(compose_area.y));
+    if area.height < 3 {
+        return;
+    }
+
+    frame.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(Span::styled(
+            " Mention (Tab) ",
+            Style::default().fg(Color::Cyan),
+        ));
+
+    let lines: Vec<Line> = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let highlight = if i == selected {
+                Style::default().bg(Color::Rgb(55, 55, 70))
+            } else {
+                Style::default()
+            };
+            let mut spans = vec![Span::styled(
+                format!(" {}", m.display_name),
+                highlight.fg(Color::White).add_modifier(Modifier::BOLD),
+            )];
+            if let Some(ref email) = m.email {
+                spans.push(Span::styled(
+                    format!("  {}", email),
+                    highlight.fg(Color::DarkGray),
+                ));
+            }
+            Line::from(spans).style(highlight)
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
 /// Information about what text to display and where the cursor is.
 struct DisplayText {
     /// The visible portion of text to render.
@@ -321,3 +567,82 @@ fn compose_display_text(input: &str, cursor_pos: usize, width: usize) -> Display
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(input: &str, cursor_pos: usize) -> ComposeState {
+        ComposeState {
+            input: input.to_string(),
+            cursor_pos,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn format_wraps_word_before_cursor() {
+        let mut s = state("make this bold", 9);
+        s.apply_format(Format::Bold);
+        assert_eq!(s.input, "make **this** bold");
+        assert_eq!(s.cursor_pos, 13);
+    }
+
+    #[test]
+    fn format_inserts_empty_pair() {
+        let mut s = state("say ", 4);
+        s.apply_format(Format::Strike);
+        assert_eq!(s.input, "say ~~~~");
+        assert_eq!(s.cursor_pos, 6);
+
+        let mut s = state("see docs", 8);
+        s.apply_format(Format::Link);
+        assert_eq!(s.input, "see [docs]()");
+        assert_eq!(s.cursor_pos, 11);
+    }
+
+    #[test]
+    fn mention_query_and_accept() {
+        let mut s = ComposeState::default();
+        for c in "hi @al".chars() {
+            s.insert_char(c);
+        }
+        assert_eq!(s.mention_query().as_deref(), Some("al"));
+
+        s.accept_mention("Alice Smith");
+        assert_eq!(s.input, "hi @Alice Smith ");
+        assert_eq!(s.cursor_pos, 16);
+        assert!(s.mention_query().is_none());
+
+        // An @ inside a word (an email address) starts no mention.
+        for c in "bob@x".chars() {
+            s.insert_char(c);
+        }
+        assert!(s.mention_query().is_none());
+    }
+
+    #[test]
+    fn mention_candidates_match_word_starts() {
+        let member = |mri: &str, name: &str, email: Option<&str>| MemberInfo {
+            mri: mri.to_string(),
+            display_name: name.to_string(),
+            email: email.map(String::from),
+            role: crate::api::MemberRole::Member,
+        };
+        let members = [
+            member("8:orgid:a", "Alice Smith", Some("alice@x.test")),
+            member("8:orgid:b", "Bob Smithers", None),
+            member("8:orgid:me", "Sam Me", None),
+        ];
+        let names = |query: &str| -> Vec<String> {
+            mention_candidates(&members, query, Some("8:orgid:me"))
+                .iter()
+                .map(|m| m.display_name.clone())
+                .collect()
+        };
+        assert_eq!(names("smi"), ["Alice Smith", "Bob Smithers"]);
+        assert_eq!(names("ALI"), ["Alice Smith"]);
+        assert!(names("ith").is_empty());
+        assert_eq!(names("s"), ["Alice Smith", "Bob Smithers"]);
+    }
+}
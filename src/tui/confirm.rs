@@ -0,0 +1,51 @@
+//! Confirmation dialog: a small centered y/n popup.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use super::help::centered_rect;
+
+/// Popup width in columns (clamped to the terminal).
+const POPUP_WIDTH: u16 = 50;
+
+/// Popup height: border + detail (2 lines) + blank + keys + border.
+const POPUP_HEIGHT: u16 = 6;
+
+/// Render a confirmation popup with a title and a detail line.
+pub fn render_confirm_popup(frame: &mut Frame, title: &str, detail: &str) {
+    let area = frame.area();
+    let popup_w = POPUP_WIDTH.min(area.width.saturating_sub(2));
+    let popup_h = POPUP_HEIGHT.min(area.height.saturating_sub(2));
+    let popup_area = centered_rect(popup_w, popup_h, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Red))
+        .title(Span::styled(
+            format!(" {} ", title),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+
+    let lines = vec![
+        Line::from(Span::styled(
+            format!("\"{}\"", detail),
+            Style::default().fg(Color::White),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("y", Style::default().fg(Color::Yellow)),
+            Span::raw(" / Enter: confirm   "),
+            Span::styled("n", Style::default().fg(Color::Yellow)),
+            Span::raw(" / Esc: cancel"),
+        ]),
+    ];
+
+    let para = Paragraph::new(lines).block(block).wrap(Wrap { trim: true });
+    frame.render_widget(para, popup_area);
+}
@@ -10,7 +10,7 @@ use ratatui::{
 
 /// Popup dimensions.
 const POPUP_WIDTH: u16 = 84;
-const POPUP_HEIGHT: u16 = 30;
+const POPUP_HEIGHT: u16 = 33;
 
 /// A shortcut entry: key binding and its description.
 struct Shortcut {
@@ -125,6 +125,14 @@ const MESSAGING: Category = Category {
             key: "Ctrl+U",
             desc: "Clear compose box",
         },
+        Shortcut {
+            key: "Alt+B/I/U/S",
+            desc: "Bold/italic/underline/strike",
+        },
+        Shortcut {
+            key: "Alt+C/K",
+            desc: "Inline code / link",
+        },
     ],
 };
 
@@ -147,10 +155,18 @@ const ACTIONS: Category = Category {
             key: "+",
             desc: "Add reaction",
         },
+        Shortcut {
+            key: "s",
+            desc: "Save attachments",
+        },
         Shortcut {
             key: "@",
             desc: "Mention user",
         },
+        Shortcut {
+            key: "m",
+            desc: "Members (add/remove/leave)",
+        },
         Shortcut {
             key: "Ctrl+P",
             desc: "Attach file",
@@ -289,7 +305,7 @@ fn build_column_lines<'a>(categories: &[&Category]) -> Vec<Line<'a>> {
 }
 
 /// Return a centered sub-rect of the given size within `area`.
-fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+pub(super) fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
     let x = area.x + area.width.saturating_sub(width) / 2;
     let y = area.y + area.height.saturating_sub(height) / 2;
     Rect::new(x, y, width, height)
@@ -0,0 +1,123 @@
+//! Inline image previews for the messages pane.
+//!
+//! The graphics protocol is picked once at startup: kitty, sixel or iTerm2
+//! when the terminal answers the capability query, half-block characters
+//! otherwise. Thumbnails are fetched, decoded and encoded by the backend;
+//! the messages pane reserves rows for them and draws them on top.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+use ratatui_image::{picker::Picker, protocol::Protocol, Image, Resize};
+
+/// Largest preview, in terminal cells.
+pub const PREVIEW_MAX_COLS: u16 = 48;
+pub const PREVIEW_MAX_ROWS: u16 = 12;
+
+/// Cell size assumed when the terminal does not report one.
+const FALLBACK_FONT_SIZE: (u16, u16) = (8, 16);
+
+enum Preview {
+    Loading,
+    Ready(Protocol),
+    Failed,
+}
+
+/// Encoded previews keyed by image URL.
+pub struct ImagePreviews {
+    picker: Picker,
+    previews: HashMap<String, Preview>,
+}
+
+impl Default for ImagePreviews {
+    /// Half-block previews, for use before the terminal has been queried.
+    fn default() -> Self {
+        Self::new(halfblocks_picker())
+    }
+}
+
+impl ImagePreviews {
+    pub fn new(picker: Picker) -> Self {
+        Self {
+            picker,
+            previews: HashMap::new(),
+        }
+    }
+
+    /// Query the terminal for its graphics protocol and cell size.
+    ///
+    /// Must run after entering the alternate screen and before the event
+    /// stream starts reading stdin.
+    pub fn detect() -> Self {
+        let picker = Picker::from_query_stdio().unwrap_or_else(|e| {
+            tracing::debug!("Terminal graphics query failed: {}", e);
+            halfblocks_picker()
+        });
+        tracing::debug!("Image previews use {:?}", picker.protocol_type());
+        Self::new(picker)
+    }
+
+    pub fn picker(&self) -> Picker {
+        self.picker
+    }
+
+    /// Mark `url` as loading. Returns false if it was already requested.
+    pub fn request(&mut self, url: &str) -> bool {
+        if self.previews.contains_key(url) {
+            return false;
+        }
+        self.previews.insert(url.to_string(), Preview::Loading);
+        true
+    }
+
+    /// Store a fetched preview (failures are remembered so they are not
+    /// retried on every reload).
+    pub fn insert(&mut self, url: String, result: Result<Protocol>) {
+        let preview = match result {
+            Ok(protocol) => Preview::Ready(protocol),
+            Err(e) => {
+                tracing::debug!("No preview for {}: {:#}", url, e);
+                Preview::Failed
+            }
+        };
+        self.previews.insert(url, preview);
+    }
+
+    /// Size in cells of a ready preview.
+    pub fn size(&self, url: &str) -> Option<(u16, u16)> {
+        match self.previews.get(url) {
+            Some(Preview::Ready(protocol)) => {
+                let area = protocol.area();
+                Some((area.width, area.height))
+            }
+            _ => None,
+        }
+    }
+
+    /// Draw a ready preview into `area`.
+    pub fn render(&mut self, url: &str, area: Rect, buf: &mut Buffer) {
+        if let Some(Preview::Ready(protocol)) = self.previews.get_mut(url) {
+            Image::new(protocol).render(area, buf);
+        }
+    }
+}
+
+/// Decode image bytes and encode them for `picker`'s protocol, fitted to the
+/// preview bounds. CPU-bound; run it off the UI thread.
+pub fn encode_preview(picker: Picker, bytes: &[u8]) -> Result<Protocol> {
+    let image = image::load_from_memory(bytes).context("Failed to decode image")?;
+    picker
+        .new_protocol(
+            image,
+            Rect::new(0, 0, PREVIEW_MAX_COLS, PREVIEW_MAX_ROWS),
+            Resize::Fit(None),
+        )
+        .context("Failed to encode image")
+}
+
+fn halfblocks_picker() -> Picker {
+    let mut picker = Picker::from_fontsize(FALLBACK_FONT_SIZE);
+    picker.set_protocol_type(ratatui_image::picker::ProtocolType::Halfblocks);
+    picker
+}
@@ -0,0 +1,185 @@
+//! Member panel: who is in the current conversation, with add, remove and
+//! leave for group chats.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, Widget},
+};
+use unicode_width::UnicodeWidthChar;
+
+use crate::api;
+
+/// Panel width in columns, taken from the messages pane.
+pub const PANEL_WIDTH: u16 = 32;
+
+/// A change waiting for y/n in the panel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemberAction {
+    Remove { mri: String, name: String },
+    Leave,
+}
+
+/// State for the open member panel.
+#[derive(Default)]
+pub struct MemberPanel {
+    /// Index into the conversation's members.
+    pub selected: usize,
+    /// Action waiting for confirmation.
+    pub confirm: Option<MemberAction>,
+}
+
+impl MemberPanel {
+    pub fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self, count: usize) {
+        if self.selected + 1 < count {
+            self.selected += 1;
+        }
+    }
+
+    /// Ask to remove the selected member; removing ourselves is leaving.
+    pub fn confirm_remove(&mut self, members: &[api::MemberInfo], own_mri: Option<&str>) {
+        let Some(member) = members.get(self.selected) else {
+            return;
+        };
+        self.confirm = Some(if Some(member.mri.as_str()) == own_mri {
+            MemberAction::Leave
+        } else {
+            MemberAction::Remove {
+                mri: member.mri.clone(),
+                name: member.display_name.clone(),
+            }
+        });
+    }
+}
+
+/// Render the panel into `area` (the right edge of the messages pane).
+pub fn render(
+    area: Rect,
+    buf: &mut Buffer,
+    panel: &MemberPanel,
+    members: &[api::MemberInfo],
+    own_mri: Option<&str>,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Double)
+        .border_style(Style::default().fg(Color::Yellow))
+        .title(Span::styled(
+            format!(" {} members ", members.len()),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+    let inner = block.inner(area);
+    block.render(area, buf);
+    if inner.height < 2 {
+        return;
+    }
+
+    // Keep the selection in view above the two hint lines.
+    let list_height = inner.height.saturating_sub(2) as usize;
+    let first = panel.selected.saturating_sub(list_height.saturating_sub(1));
+    let mut lines: Vec<Line> = members
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(list_height)
+        .map(|(i, m)| {
+            let highlight = if i == panel.selected {
+                Style::default().bg(Color::Rgb(55, 55, 70))
+            } else {
+                Style::default()
+            };
+            let own = Some(m.mri.as_str()) == own_mri;
+            let mut spans = vec![Span::styled(
+                format!(" {}", m.display_name),
+                highlight.fg(if own { Color::Cyan } else { Color::White }),
+            )];
+            let tag = match m.role {
+                api::MemberRole::Owner => Some(("owner", Color::Yellow)),
+                api::MemberRole::Guest => Some(("guest", Color::Magenta)),
+                api::MemberRole::Member => None,
+            };
+            if let Some((tag, color)) = tag {
+                spans.push(Span::styled(format!(" {}", tag), highlight.fg(color)));
+            }
+            Line::from(spans).style(highlight)
+        })
+        .collect();
+    while lines.len() < list_height {
+        lines.push(Line::from(""));
+    }
+
+    let hint_style = Style::default().fg(Color::Gray);
+    match panel.confirm {
+        Some(ref action) => {
+            let question = match action {
+                MemberAction::Remove { name, .. } => format!(" Remove {}?", name),
+                MemberAction::Leave => " Leave this chat?".to_string(),
+            };
+            lines.push(Line::from(Span::styled(
+                truncate(&question, inner.width as usize),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )));
+            lines.push(Line::from(Span::styled(" y: yes  n: no", hint_style)));
+        }
+        None => {
+            lines.push(Line::from(Span::styled(" a: add  x: remove", hint_style)));
+            lines.push(Line::from(Span::styled(
+                " L: leave  Esc: close",
+                hint_style,
+            )));
+        }
+    }
+    Paragraph::new(lines).render(inner, buf);
+}
+
+/// Cut `text` to `width` columns.
+fn truncate(text: &str, width: usize) -> String {
+    let mut used = 0;
+    text.chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= width
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(mri: &str) -> api::MemberInfo {
+        api::MemberInfo {
+            mri: mri.to_string(),
+            display_name: mri.to_uppercase(),
+            email: None,
+            role: api::MemberRole::Member,
+        }
+    }
+
+    #[test]
+    fn removing_ourselves_is_leaving() {
+        let members = [member("8:orgid:me"), member("8:orgid:bob")];
+        let mut panel = MemberPanel::default();
+        panel.confirm_remove(&members, Some("8:orgid:me"));
+        assert_eq!(panel.confirm, Some(MemberAction::Leave));
+
+        panel.move_down(members.len());
+        panel.move_down(members.len());
+        panel.confirm_remove(&members, Some("8:orgid:me"));
+        assert_eq!(
+            panel.confirm,
+            Some(MemberAction::Remove {
+                mri: "8:orgid:bob".to_string(),
+                name: "8:ORGID:BOB".to_string(),
+            })
+        );
+    }
+}
@@ -9,6 +9,10 @@ use ratatui::{
     widgets::{Block, BorderType, Borders, Paragraph, Widget},
 };
 
+use unicode_width::UnicodeWidthStr;
+
+use super::images::ImagePreviews;
+use super::richtext;
 use crate::api;
 
 /// Default header shown when no channel is selected.
@@ -21,28 +25,44 @@ const DEFAULT_HEADER: &str = "Select a channel or chat";
 /// A reaction on a message (e.g., thumbs-up x3).
 #[derive(Clone)]
 pub struct Reaction {
+    /// Chat service reaction key: "like", "heart", ...
+    pub key: String,
     /// ASCII-safe label: "+1", "<3", "eyes", etc.
     pub label: String,
     /// How many people reacted with this.
     pub count: u32,
+    /// MRIs of the users who reacted.
+    pub users: Vec<String>,
 }
 
-/// A file attachment on a message.
-#[derive(Clone)]
-pub struct Attachment {
-    /// Display filename.
-    pub name: String,
+impl From<api::ReactionInfo> for Reaction {
+    fn from(r: api::ReactionInfo) -> Self {
+        Reaction {
+            label: api::reaction_label(&r.key).to_string(),
+            count: r.users.len() as u32,
+            key: r.key,
+            users: r.users,
+        }
+    }
 }
 
 /// A single chat message.
 #[derive(Clone)]
 pub struct Message {
+    /// Server-assigned message ID.
+    pub id: String,
+    /// ID given by the sending client, when known.
+    pub client_message_id: Option<String>,
     /// Sender display name.
     pub sender: String,
+    /// Sender MRI, when known (used to recognise our own messages).
+    pub sender_id: Option<String>,
     /// Timestamp string (e.g., "9:15 AM today").
     pub timestamp: String,
-    /// Message body lines.
+    /// Message body as plain text (editing, previews).
     pub content: String,
+    /// Formatted message body.
+    pub rich: api::RichText,
     /// Reactions below the message.
     pub reactions: Vec<Reaction>,
     /// Number of thread replies (0 = no thread).
@@ -50,7 +70,11 @@ pub struct Message {
     /// Inline thread replies (shown when expanded).
     pub replies: Vec<Message>,
     /// File attachments.
-    pub attachments: Vec<Attachment>,
+    pub attachments: Vec<api::AttachmentInfo>,
+    /// Root post ID when this is a channel thread reply.
+    pub parent_id: Option<String>,
+    /// People @mentioned in the message.
+    pub mentions: Vec<api::Mention>,
 }
 
 /// State for the messages pane.
@@ -67,6 +91,17 @@ pub struct MessagesState {
     pub expanded_threads: Vec<bool>,
     /// Whether messages are being loaded.
     pub loading: bool,
+    /// Link to the page of history before the oldest loaded message.
+    pub older_link: Option<String>,
+    /// Whether an older page is being fetched.
+    pub loading_older: bool,
+    /// Message to select once the conversation finishes loading (search jump).
+    pub pending_select: Option<String>,
+    /// MRI of the logged-in user (marks our own messages and reactions).
+    pub user_mri: Option<String>,
+    /// ID of the newest message read before the conversation was opened;
+    /// a divider marks the first newer message from someone else.
+    pub read_horizon: Option<u64>,
 }
 
 impl Default for MessagesState {
@@ -78,34 +113,291 @@ impl Default for MessagesState {
             scroll_offset: 0,
             selected: 0,
             loading: false,
+            older_link: None,
+            loading_older: false,
+            pending_select: None,
+            user_mri: None,
+            read_horizon: None,
+        }
+    }
+}
+
+impl From<api::MessageInfo> for Message {
+    fn from(m: api::MessageInfo) -> Self {
+        Message {
+            id: m.id,
+            client_message_id: m.client_message_id,
+            sender: m.sender,
+            sender_id: m.sender_id,
+            timestamp: m.timestamp,
+            rich: if m.rich.is_empty() {
+                api::RichText::plain(&m.content)
+            } else {
+                m.rich
+            },
+            content: m.content,
+            reactions: m.reactions.into_iter().map(Reaction::from).collect(),
+            reply_count: 0,
+            replies: Vec::new(),
+            attachments: m.attachments,
+            parent_id: m.parent_id,
+            mentions: m.mentions,
+        }
+    }
+}
+
+/// Move channel thread replies under their root post.
+///
+/// `messages` must be in chronological order. Replies whose root is not
+/// loaded (yet) stay top-level, so they can be attached once an older page
+/// brings the root in.
+fn thread_messages(messages: impl IntoIterator<Item = Message>) -> Vec<Message> {
+    let mut threaded: Vec<Message> = Vec::new();
+    for msg in messages {
+        let root = msg
+            .parent_id
+            .as_deref()
+            .and_then(|pid| threaded.iter_mut().find(|m| m.id == pid));
+        match root {
+            Some(root) => {
+                root.replies.push(msg);
+                root.reply_count = root.replies.len() as u32;
+            }
+            None => threaded.push(msg),
         }
     }
+    threaded
 }
 
 impl MessagesState {
     /// Update messages from API response.
-    pub fn update_messages(&mut self, header: &str, api_messages: Vec<api::MessageInfo>) {
+    ///
+    /// Messages are cleared when a conversation is opened, so anything
+    /// already shown means this is a refresh of the same conversation
+    /// (e.g. the synced history following the cached one). A refresh keeps
+    /// the selection, the scroll position and any older history loaded by
+    /// scrolling back that the new page does not reach.
+    pub fn update_messages(&mut self, header: &str, page: api::MessagePage) {
+        let refresh = !self.messages.is_empty();
+        // Keep a scrolled-back selection.
+        let keep = self
+            .messages
+            .get(self.selected)
+            .filter(|_| self.selected + 1 < self.messages.len())
+            .map(|m| m.id.clone());
+        let expanded = self.expanded_ids();
         self.channel_header = header.to_string();
-        self.messages = api_messages
+
+        let fresh: Vec<Message> = page.messages.into_iter().map(Message::from).collect();
+        let mut older_link = page.backward_link;
+        let oldest = fresh.iter().find_map(|m| api::message_order(&m.id));
+        let mut earlier: Vec<Message> = std::mem::take(&mut self.messages)
             .into_iter()
-            .map(|m| Message {
-                sender: m.sender,
-                timestamp: m.timestamp,
-                content: m.content,
-                reactions: Vec::new(),
-                reply_count: 0,
-                replies: Vec::new(),
-                attachments: Vec::new(),
+            .flat_map(|mut m| {
+                let replies = std::mem::take(&mut m.replies);
+                std::iter::once(m).chain(replies)
+            })
+            .filter(|m| match (api::message_order(&m.id), oldest) {
+                (Some(order), Some(oldest)) => order < oldest,
+                _ => false,
             })
             .collect();
+        if !earlier.is_empty() {
+            earlier.sort_by_key(|m| api::message_order(&m.id));
+            older_link = self.older_link.take();
+        }
+        self.messages = thread_messages(earlier.into_iter().chain(fresh));
+        self.older_link = older_link;
+        self.loading_older = false;
         let count = self.messages.len();
-        self.expanded_threads = vec![true; count];
-        self.scroll_offset = 0;
-        // Select the last (newest) message so the view starts at the bottom.
+        self.restore_expanded(&expanded);
+        if !refresh {
+            self.scroll_offset = 0;
+        }
+        // Select the last (newest) message so the view starts at the bottom,
+        // unless a search result asked for a specific one.
         self.selected = count.saturating_sub(1);
+        if let Some(id) = self.pending_select.take().or(keep) {
+            self.select_by_id(&id);
+        }
         self.loading = false;
     }
 
+    /// Prepend an older page of history, keeping the selection on the same
+    /// message.
+    ///
+    /// Replies shown top-level because their root was not loaded are moved
+    /// into the root's thread when it arrives.
+    pub fn prepend_messages(&mut self, page: api::MessagePage) {
+        let older: Vec<Message> = page
+            .messages
+            .into_iter()
+            .filter(|m| m.id.is_empty() || self.find_mut(&m.id).is_none())
+            .map(Message::from)
+            .collect();
+        let selected_id = self.selected_message().map(|m| m.id.clone());
+        let expanded = self.expanded_ids();
+        let current = std::mem::take(&mut self.messages);
+        self.messages = thread_messages(older.into_iter().chain(current));
+        self.restore_expanded(&expanded);
+        self.selected = selected_id
+            .and_then(|id| self.messages.iter().position(|m| m.id == id))
+            .unwrap_or(0);
+        self.older_link = page.backward_link;
+        self.loading_older = false;
+    }
+
+    /// IDs of messages whose thread is currently expanded.
+    fn expanded_ids(&self) -> Vec<String> {
+        self.messages
+            .iter()
+            .zip(&self.expanded_threads)
+            .filter(|(_, expanded)| **expanded)
+            .map(|(m, _)| m.id.clone())
+            .collect()
+    }
+
+    /// Rebuild `expanded_threads` for the current messages. Threads start
+    /// collapsed unless they were expanded before.
+    fn restore_expanded(&mut self, expanded: &[String]) {
+        self.expanded_threads = self
+            .messages
+            .iter()
+            .map(|m| m.replies.is_empty() || expanded.contains(&m.id))
+            .collect();
+    }
+
+    /// Link for the next older page, if the selection is at the top of the
+    /// loaded history and no fetch is already in flight.
+    pub fn older_page_link(&self) -> Option<&str> {
+        if self.selected == 0 && !self.loading_older {
+            self.older_link.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// Append a live (pushed) message.
+    ///
+    /// If a message with the same ID is already shown (e.g. our own message
+    /// after a reload), it is replaced instead. When the newest message was
+    /// selected, the selection follows the new message to the bottom.
+    pub fn push_message(&mut self, api_message: api::MessageInfo) {
+        if let Some(existing) = self.find_mut(&api_message.id) {
+            let updated = Message::from(api_message);
+            existing.content = updated.content;
+            existing.rich = updated.rich;
+            existing.mentions = updated.mentions;
+            return;
+        }
+        if let Some(root) = api_message
+            .parent_id
+            .as_deref()
+            .and_then(|pid| self.messages.iter_mut().find(|m| m.id == pid))
+        {
+            root.replies.push(Message::from(api_message));
+            root.reply_count = root.replies.len() as u32;
+            return;
+        }
+        let follow = self.messages.is_empty() || self.selected + 1 == self.messages.len();
+        self.messages.push(Message::from(api_message));
+        self.expanded_threads.push(true);
+        if follow {
+            self.selected = self.messages.len() - 1;
+        }
+    }
+
+    /// Replace the content and reactions of an updated message. Returns
+    /// false if not shown.
+    pub fn edit_message(&mut self, api_message: api::MessageInfo) -> bool {
+        match self.find_mut(&api_message.id) {
+            Some(existing) => {
+                let updated = Message::from(api_message);
+                existing.content = updated.content;
+                existing.rich = updated.rich;
+                existing.reactions = updated.reactions;
+                existing.mentions = updated.mentions;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a deleted message (top-level or reply). Returns false if not
+    /// shown.
+    pub fn remove_message(&mut self, message_id: &str) -> bool {
+        let Some(idx) = self.messages.iter().position(|m| m.id == message_id) else {
+            for root in &mut self.messages {
+                if let Some(pos) = root.replies.iter().position(|r| r.id == message_id) {
+                    root.replies.remove(pos);
+                    root.reply_count = root.replies.len() as u32;
+                    return true;
+                }
+            }
+            return false;
+        };
+        self.messages.remove(idx);
+        if idx < self.expanded_threads.len() {
+            self.expanded_threads.remove(idx);
+        }
+        if self.selected >= self.messages.len() {
+            self.selected = self.messages.len().saturating_sub(1);
+        }
+        true
+    }
+
+    /// Find a message (top-level or reply) by ID.
+    fn find_mut(&mut self, message_id: &str) -> Option<&mut Message> {
+        if message_id.is_empty() {
+            return None;
+        }
+        self.messages.iter_mut().find_map(|m| {
+            if m.id == message_id {
+                Some(m)
+            } else {
+                m.replies.iter_mut().find(|r| r.id == message_id)
+            }
+        })
+    }
+
+    /// Select the message with the given ID, or the thread containing it
+    /// (expanded) if it is a reply. Returns false if not shown.
+    pub fn select_by_id(&mut self, message_id: &str) -> bool {
+        if let Some(idx) = self.messages.iter().position(|m| m.id == message_id) {
+            self.selected = idx;
+            return true;
+        }
+        let thread = self
+            .messages
+            .iter()
+            .position(|m| m.replies.iter().any(|r| r.id == message_id));
+        match thread {
+            Some(idx) => {
+                self.selected = idx;
+                if let Some(expanded) = self.expanded_threads.get_mut(idx) {
+                    *expanded = true;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Numeric order of the newest loaded message, replies included, and
+    /// the message.
+    pub fn newest_message(&self) -> Option<(u64, &Message)> {
+        self.messages
+            .iter()
+            .flat_map(|m| std::iter::once(m).chain(&m.replies))
+            .filter_map(|m| api::message_order(&m.id).map(|order| (order, m)))
+            .max_by_key(|(order, _)| *order)
+    }
+
+    /// The currently selected top-level message.
+    pub fn selected_message(&self) -> Option<&Message> {
+        self.messages.get(self.selected)
+    }
+
     /// Move selection up by one message.
     pub fn select_previous(&mut self) {
         if self.selected > 0 {
@@ -135,7 +427,14 @@ impl MessagesState {
 // ---------------------------------------------------------------------------
 
 /// Render the messages pane into the given area.
-pub fn render(area: Rect, buf: &mut Buffer, state: &MessagesState, focused: bool, user_name: &str) {
+pub fn render(
+    area: Rect,
+    buf: &mut Buffer,
+    state: &MessagesState,
+    previews: &mut ImagePreviews,
+    focused: bool,
+    user_name: &str,
+) {
     let border_style = if focused {
         Style::default().fg(Color::Yellow)
     } else {
@@ -162,7 +461,12 @@ pub fn render(area: Rect, buf: &mut Buffer, state: &MessagesState, focused: bool
 
     // Reserve the first line for the channel header.
     let header_area = Rect::new(inner.x, inner.y, inner.width, 1);
-    render_channel_header(header_area, buf, &state.channel_header);
+    if state.loading_older {
+        let header = format!("{}  (loading older messages...)", state.channel_header);
+        render_channel_header(header_area, buf, &header);
+    } else {
+        render_channel_header(header_area, buf, &state.channel_header);
+    }
 
     // Remaining space for messages.
     let messages_area = Rect::new(
@@ -201,8 +505,8 @@ pub fn render(area: Rect, buf: &mut Buffer, state: &MessagesState, focused: bool
     }
 
     // Pre-render all messages into a line buffer (single pass produces lines + ranges).
-    let (all_lines, msg_line_ranges) =
-        build_message_lines(state, messages_area.width as usize, user_name);
+    let (all_lines, msg_line_ranges, preview_slots) =
+        build_message_lines(state, previews, messages_area.width as usize, user_name);
     let total_lines = all_lines.len();
     let visible_height = messages_area.height as usize;
 
@@ -225,6 +529,21 @@ pub fn render(area: Rect, buf: &mut Buffer, state: &MessagesState, focused: bool
         Paragraph::new(all_lines[line_idx].clone()).render(line_area, buf);
     }
 
+    // Draw image previews over their reserved rows. Graphics protocols
+    // cannot be clipped, so only fully visible previews are drawn.
+    for slot in &preview_slots {
+        if slot.line < scroll || slot.line + slot.height as usize > scroll + visible_height {
+            continue;
+        }
+        let preview_area = Rect::new(
+            messages_area.x + slot.col,
+            messages_area.y + (slot.line - scroll) as u16,
+            slot.width,
+            slot.height,
+        );
+        previews.render(&slot.url, preview_area, buf);
+    }
+
     // Render scroll indicators.
     if total_lines > visible_height {
         let indicator_x = messages_area.x + messages_area.width.saturating_sub(1);
@@ -257,18 +576,47 @@ fn render_channel_header(area: Rect, buf: &mut Buffer, header: &str) {
         .render(area, buf);
 }
 
-/// Build the flat line buffer and per-message line ranges in a single pass.
+/// Rows reserved in the line buffer for an image preview.
+struct PreviewSlot {
+    url: String,
+    /// First reserved line in the line buffer.
+    line: usize,
+    /// Column offset within the messages area.
+    col: u16,
+    width: u16,
+    height: u16,
+}
+
+/// Build the flat line buffer, per-message line ranges and image preview
+/// slots in a single pass.
 fn build_message_lines(
     state: &MessagesState,
+    previews: &ImagePreviews,
     width: usize,
     user_name: &str,
-) -> (Vec<Line<'static>>, Vec<(usize, usize)>) {
-    let today = Local::now().naive_local().date();
-    let mut lines: Vec<Line<'static>> = Vec::new();
+) -> (Vec<Line<'static>>, Vec<(usize, usize)>, Vec<PreviewSlot>) {
+    let ctx = CardContext {
+        today: Local::now().naive_local().date(),
+        viewer: Viewer {
+            name: user_name,
+            mri: state.user_mri.as_deref(),
+        },
+        previews,
+        width,
+    };
+    let mut out = CardBuffer::default();
     let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let first_unread = state.read_horizon.and_then(|horizon| {
+        state.messages.iter().position(|m| {
+            api::message_order(&m.id).is_some_and(|order| order > horizon) && !ctx.viewer.sent(m)
+        })
+    });
 
     for (msg_idx, msg) in state.messages.iter().enumerate() {
-        let start = lines.len();
+        if first_unread == Some(msg_idx) {
+            out.lines.push(unread_divider(width));
+        }
+        let start = out.lines.len();
         let is_selected = msg_idx == state.selected;
         let thread_expanded = state
             .expanded_threads
@@ -277,29 +625,29 @@ fn build_message_lines(
             .unwrap_or(false);
 
         // Render main message card.
-        render_message_card(
-            &mut lines,
-            msg,
-            width,
+        let card = CardLayout {
             is_selected,
-            false,
-            0,
+            is_reply: false,
+            indent: 0,
             msg_idx,
-            today,
-            user_name,
-        );
+        };
+        render_message_card(&mut out, msg, card, &ctx);
 
         // Render thread replies if expanded.
         if thread_expanded && !msg.replies.is_empty() {
             for reply in &msg.replies {
-                render_message_card(
-                    &mut lines, reply, width, false, true, 4, msg_idx, today, user_name,
-                );
+                let card = CardLayout {
+                    is_selected: false,
+                    is_reply: true,
+                    indent: 4,
+                    msg_idx,
+                };
+                render_message_card(&mut out, reply, card, &ctx);
             }
         } else if msg.reply_count > 0 && !thread_expanded {
             // Show collapsed thread indicator.
             let indent = "     ";
-            lines.push(Line::from(vec![
+            out.lines.push(Line::from(vec![
                 Span::raw(indent.to_string()),
                 Span::styled(
                     format!(">> {} replies (Enter to expand)", msg.reply_count),
@@ -309,30 +657,89 @@ fn build_message_lines(
         }
 
         // Blank line between top-level messages.
-        lines.push(Line::from(""));
+        out.lines.push(Line::from(""));
 
-        ranges.push((start, lines.len()));
+        ranges.push((start, out.lines.len()));
     }
 
-    (lines, ranges)
+    (out.lines, ranges, out.slots)
 }
 
-/// Render a single message card (either top-level or reply) into the line buffer.
-///
-/// Uses colored backgrounds instead of ASCII borders. Even/odd `msg_idx`
-/// alternates between two subtle background shades for visual separation.
-fn render_message_card(
-    lines: &mut Vec<Line<'static>>,
-    msg: &Message,
+/// "New messages" rule drawn above the first unread message.
+fn unread_divider(width: usize) -> Line<'static> {
+    let label = " New messages ";
+    let side = width.saturating_sub(label.width()) / 2;
+    let style = Style::default().fg(Color::LightRed);
+    Line::from(vec![
+        Span::styled("\u{2500}".repeat(side), style),
+        Span::styled(label, style.add_modifier(Modifier::BOLD)),
+        Span::styled("\u{2500}".repeat(side), style),
+    ])
+}
+
+/// The logged-in user, for telling our own messages and reactions apart.
+#[derive(Clone, Copy)]
+struct Viewer<'a> {
+    name: &'a str,
+    mri: Option<&'a str>,
+}
+
+impl Viewer<'_> {
+    /// Whether `msg` is one of our own messages.
+    fn sent(&self, msg: &Message) -> bool {
+        match (msg.sender_id.as_deref(), self.mri) {
+            (Some(sender), Some(me)) => sender == me,
+            _ => msg.sender == self.name,
+        }
+    }
+}
+
+/// Per-render inputs shared by every message card.
+struct CardContext<'a> {
+    today: NaiveDate,
+    viewer: Viewer<'a>,
+    previews: &'a ImagePreviews,
+    /// Width of the messages area.
     width: usize,
+}
+
+/// Where a card sits: its place in the list and in its thread.
+#[derive(Clone, Copy)]
+struct CardLayout {
     is_selected: bool,
     is_reply: bool,
     indent: usize,
+    /// Index of the top-level message, for alternating backgrounds.
     msg_idx: usize,
-    today: NaiveDate,
-    user_name: &str,
-) {
-    let is_own = msg.sender == user_name;
+}
+
+/// Lines rendered so far, and the preview slots reserved within them.
+#[derive(Default)]
+struct CardBuffer {
+    lines: Vec<Line<'static>>,
+    slots: Vec<PreviewSlot>,
+}
+
+/// Render a single message card (either top-level or reply) into the line buffer.
+///
+/// Uses colored backgrounds instead of ASCII borders. Even/odd `msg_idx`
+/// alternates between two subtle background shades for visual separation.
+/// Image attachments with a loaded preview get blank rows reserved in
+/// `out.slots`, to be drawn over once the lines are on screen.
+fn render_message_card(out: &mut CardBuffer, msg: &Message, card: CardLayout, ctx: &CardContext) {
+    let CardLayout {
+        is_selected,
+        is_reply,
+        indent,
+        msg_idx,
+    } = card;
+    let width = ctx.width;
+    let CardBuffer { lines, slots } = out;
+    let viewer = ctx.viewer;
+    let is_own = viewer.sent(msg);
+    let mentions_me = viewer
+        .mri
+        .is_some_and(|me| msg.mentions.iter().any(|m| m.mri == me));
     let indent_str: String = " ".repeat(indent);
     let reply_prefix = if is_reply { " -> " } else { "" };
 
@@ -359,6 +766,9 @@ fn render_message_card(
         } else {
             Color::Rgb(55, 55, 70)
         }
+    } else if mentions_me {
+        // Messages that @mention us stand out with an amber tint.
+        Color::Rgb(58, 48, 24)
     } else if is_reply {
         Color::Rgb(30, 30, 38)
     } else if is_own {
@@ -369,7 +779,13 @@ fn render_message_card(
         Color::Rgb(42, 42, 52)
     };
 
-    let selection_indicator = if is_selected && !is_reply { "> " } else { "  " };
+    let selection_indicator = if is_selected && !is_reply {
+        "> "
+    } else if mentions_me {
+        "@ "
+    } else {
+        "  "
+    };
 
     let sender_color = username_to_color(&msg.sender);
     let sender_style = Style::default()
@@ -381,7 +797,7 @@ fn render_message_card(
     let text_style = Style::default().fg(Color::White).bg(bg);
     let bg_style = Style::default().bg(bg);
 
-    let formatted_ts = format_timestamp(&msg.timestamp, today);
+    let formatted_ts = format_timestamp(&msg.timestamp, ctx.today);
 
     // Helper: build a line padded to effective_width, with optional left margin
     // for right-aligned own messages.
@@ -404,7 +820,14 @@ fn render_message_card(
     let used = prefix.len() + msg.sender.len() + ts_gap + formatted_ts.len();
     lines.push(make_bg_line(
         vec![
-            Span::styled(prefix.clone(), bg_style),
+            Span::styled(
+                prefix.clone(),
+                if mentions_me {
+                    bg_style.fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else {
+                    bg_style
+                },
+            ),
             Span::styled(msg.sender.clone(), sender_style),
             Span::styled(" ".repeat(ts_gap), bg_style),
             Span::styled(formatted_ts, timestamp_style),
@@ -412,9 +835,8 @@ fn render_message_card(
         used,
     ));
 
-    // Content lines (word-wrapped).
-    let wrap_width = content_width;
-    let content_lines = wrap_text(&msg.content, wrap_width);
+    // Content lines (word-wrapped, with formatting).
+    let content_lines = richtext::wrap_rich_text(&msg.rich, content_width, text_style);
     let content_prefix = format!(
         "{}{}",
         indent_str,
@@ -424,20 +846,23 @@ fn render_message_card(
             "    " // align with sender name after selection indicator "  "
         }
     );
-    for cl in &content_lines {
-        let used = content_prefix.len() + cl.len();
-        lines.push(make_bg_line(
-            vec![
-                Span::styled(content_prefix.clone(), bg_style),
-                Span::styled(cl.clone(), text_style),
-            ],
-            used,
-        ));
+    for cl in content_lines {
+        let used = content_prefix.len()
+            + cl.iter()
+                .map(|s| UnicodeWidthStr::width(s.content.as_ref()))
+                .sum::<usize>();
+        let mut spans = vec![Span::styled(content_prefix.clone(), bg_style)];
+        spans.extend(cl);
+        lines.push(make_bg_line(spans, used));
     }
 
     // Attachments.
     for att in &msg.attachments {
-        let att_text = format!("[file] {}", att.name);
+        let tag = match att.kind {
+            api::AttachmentKind::File => "file",
+            api::AttachmentKind::Image => "image",
+        };
+        let att_text = format!("[{}] {}", tag, att.name);
         let used = content_prefix.len() + att_text.len();
         lines.push(make_bg_line(
             vec![
@@ -452,6 +877,23 @@ fn render_message_card(
             ],
             used,
         ));
+
+        // Preview rows, when the image is loaded and fits.
+        if let Some((w, h)) = ctx.previews.size(&att.url) {
+            let col = left_margin + content_prefix.len();
+            if content_prefix.len() + w as usize <= effective_width {
+                slots.push(PreviewSlot {
+                    url: att.url.clone(),
+                    line: lines.len(),
+                    col: col as u16,
+                    width: w,
+                    height: h,
+                });
+                for _ in 0..h {
+                    lines.push(make_bg_line(vec![], 0));
+                }
+            }
+        }
     }
 
     // Reactions and reply count.
@@ -463,10 +905,12 @@ fn render_message_card(
         for (i, r) in msg.reactions.iter().enumerate() {
             let r_text = format!("{} {}", r.label, r.count);
             used += r_text.len();
-            spans.push(Span::styled(
-                r_text,
-                Style::default().fg(Color::Yellow).bg(bg),
-            ));
+            // Reactions we added ourselves are highlighted.
+            let mut r_style = Style::default().fg(Color::Yellow).bg(bg);
+            if viewer.mri.is_some_and(|me| r.users.iter().any(|u| u == me)) {
+                r_style = r_style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+            }
+            spans.push(Span::styled(r_text, r_style));
             if i + 1 < msg.reactions.len() || msg.reply_count > 0 {
                 spans.push(Span::styled("   ", bg_style));
                 used += 3;
@@ -558,38 +1002,6 @@ fn format_timestamp(raw: &str, today: NaiveDate) -> String {
     }
 }
 
-/// Simple word-wrapping: split content by newlines first, then wrap long lines.
-fn wrap_text(text: &str, max_width: usize) -> Vec<String> {
-    if max_width == 0 {
-        return vec![];
-    }
-    let mut result = Vec::new();
-    for line in text.lines() {
-        if line.len() <= max_width {
-            result.push(line.to_string());
-        } else {
-            // Word wrap.
-            let words: Vec<&str> = line.split_whitespace().collect();
-            let mut current = String::new();
-            for word in words {
-                if current.is_empty() {
-                    current = word.to_string();
-                } else if current.len() + 1 + word.len() <= max_width {
-                    current.push(' ');
-                    current.push_str(word);
-                } else {
-                    result.push(current);
-                    current = word.to_string();
-                }
-            }
-            if !current.is_empty() {
-                result.push(current);
-            }
-        }
-    }
-    result
-}
-
 /// Derive a deterministic RGB color from a username.
 ///
 /// Hash all bytes, truncate to u8, scale to 0..359 HSV hue, convert
@@ -666,3 +1078,174 @@ fn compute_auto_scroll(
     let max_scroll = total_lines.saturating_sub(visible_height);
     scroll.min(max_scroll)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: &str, parent: Option<&str>) -> api::MessageInfo {
+        api::MessageInfo {
+            id: id.to_string(),
+            sender: "Alice".to_string(),
+            timestamp: String::new(),
+            content: format!("message {}", id),
+            parent_id: parent.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    fn page(messages: Vec<api::MessageInfo>) -> api::MessagePage {
+        api::MessagePage {
+            messages,
+            backward_link: Some("older".to_string()),
+        }
+    }
+
+    fn ids(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|m| m.id.as_str()).collect()
+    }
+
+    #[test]
+    fn replies_are_grouped_under_their_root() {
+        let mut state = MessagesState::default();
+        state.update_messages(
+            "Team > #general",
+            page(vec![
+                info("1", None),
+                info("2", None),
+                info("3", Some("1")),
+                info("4", Some("1")),
+            ]),
+        );
+
+        assert_eq!(ids(&state.messages), ["1", "2"]);
+        assert_eq!(state.messages[0].reply_count, 2);
+        assert_eq!(ids(&state.messages[0].replies), ["3", "4"]);
+        assert_eq!(state.expanded_threads, [false, true]);
+
+        state.selected = 0;
+        state.toggle_thread();
+        assert!(state.expanded_threads[0]);
+    }
+
+    #[test]
+    fn newest_message_includes_thread_replies() {
+        let mut state = MessagesState::default();
+        assert!(state.newest_message().is_none());
+        state.update_messages(
+            "Team > #general",
+            page(vec![
+                info("10", None),
+                info("30", Some("10")),
+                info("20", None),
+            ]),
+        );
+        let newest = state
+            .newest_message()
+            .map(|(order, m)| (order, m.id.as_str()));
+        assert_eq!(newest, Some((30, "30")));
+    }
+
+    #[test]
+    fn older_page_adopts_orphaned_replies() {
+        let mut state = MessagesState::default();
+        state.update_messages(
+            "Team > #general",
+            page(vec![info("5", Some("1")), info("6", None)]),
+        );
+        assert_eq!(ids(&state.messages), ["5", "6"]);
+
+        state.prepend_messages(page(vec![info("1", None)]));
+        assert_eq!(ids(&state.messages), ["1", "6"]);
+        assert_eq!(ids(&state.messages[0].replies), ["5"]);
+        assert_eq!(state.selected, 1);
+    }
+
+    #[test]
+    fn refresh_keeps_backfilled_history_and_selection() {
+        let mut state = MessagesState::default();
+        state.update_messages("Chat", page(vec![info("10", None), info("11", None)]));
+        state.selected = 0;
+        state.prepend_messages(api::MessagePage {
+            messages: vec![info("5", None), info("6", Some("5"))],
+            backward_link: Some("oldest".to_string()),
+        });
+        assert_eq!(ids(&state.messages), ["5", "10", "11"]);
+        state.selected = 0;
+
+        // The synced page arrives without the backfilled messages.
+        state.update_messages(
+            "Chat",
+            page(vec![info("10", None), info("11", None), info("12", None)]),
+        );
+        assert_eq!(ids(&state.messages), ["5", "10", "11", "12"]);
+        assert_eq!(ids(&state.messages[0].replies), ["6"]);
+        assert_eq!(state.older_link.as_deref(), Some("oldest"));
+        assert_eq!(state.selected, 0);
+    }
+
+    #[test]
+    fn pushed_messages_are_deduplicated_and_followed() {
+        let mut state = MessagesState::default();
+        state.update_messages("Chat", page(vec![info("1", None), info("2", None)]));
+        assert_eq!(state.selected, 1);
+
+        state.push_message(info("3", None));
+        assert_eq!(ids(&state.messages), ["1", "2", "3"]);
+        assert_eq!(state.selected, 2);
+
+        // The same message again (e.g. our own, echoed back) is replaced.
+        let mut again = info("3", None);
+        again.content = "updated".to_string();
+        state.push_message(again);
+        assert_eq!(ids(&state.messages), ["1", "2", "3"]);
+        assert_eq!(state.messages[2].content, "updated");
+
+        // A scrolled-back selection stays put.
+        state.selected = 0;
+        state.push_message(info("4", None));
+        assert_eq!(state.selected, 0);
+
+        state.push_message(info("5", Some("1")));
+        assert_eq!(ids(&state.messages), ["1", "2", "3", "4"]);
+        assert_eq!(ids(&state.messages[0].replies), ["5"]);
+    }
+
+    #[test]
+    fn edits_replace_in_place() {
+        let mut state = MessagesState::default();
+        state.update_messages(
+            "Chat",
+            page(vec![info("1", None), info("2", Some("1")), info("3", None)]),
+        );
+
+        let mut edited = info("2", Some("1"));
+        edited.content = "edited reply".to_string();
+        assert!(state.edit_message(edited));
+        assert_eq!(state.messages[0].replies[0].content, "edited reply");
+        assert_eq!(ids(&state.messages), ["1", "3"]);
+
+        assert!(!state.edit_message(info("9", None)));
+    }
+
+    #[test]
+    fn deletes_remove_messages_and_replies() {
+        let mut state = MessagesState::default();
+        state.update_messages(
+            "Chat",
+            page(vec![info("1", None), info("2", Some("1")), info("3", None)]),
+        );
+        assert_eq!(state.selected, 1);
+
+        assert!(state.remove_message("2"));
+        assert!(state.messages[0].replies.is_empty());
+        assert_eq!(state.messages[0].reply_count, 0);
+
+        assert!(state.remove_message("3"));
+        assert_eq!(ids(&state.messages), ["1"]);
+        assert_eq!(state.expanded_threads.len(), 1);
+        assert_eq!(state.selected, 0);
+
+        assert!(!state.remove_message("3"));
+    }
+}
@@ -5,10 +5,17 @@
 mod app;
 mod backend;
 mod compose;
+mod confirm;
 mod debug_log;
 mod help;
+mod images;
 mod log_capture;
+mod members;
 mod messages;
+mod people;
+mod prompt;
+mod reactions;
+mod richtext;
 mod search;
 mod sidebar;
 mod ui;
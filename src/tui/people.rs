@@ -0,0 +1,232 @@
+//! People picker popup: choose who to start a new chat with (Ctrl+N).
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use super::help::centered_rect;
+use crate::api;
+
+/// Popup width in columns (clamped to the terminal).
+const POPUP_WIDTH: u16 = 64;
+
+/// Search results shown at once.
+const MAX_RESULTS: usize = 8;
+
+/// Popup height: border + chosen + input + results + hint + border.
+const POPUP_HEIGHT: u16 = MAX_RESULTS as u16 + 5;
+
+/// State for the open people picker.
+#[derive(Default)]
+pub struct PeoplePicker {
+    /// Name or address typed so far.
+    pub query: String,
+    /// Directory matches for `query`.
+    pub results: Vec<api::PersonInfo>,
+    /// Index into `results`.
+    pub selected: usize,
+    /// People added to the new chat.
+    pub chosen: Vec<api::PersonInfo>,
+    /// Whether a search for `query` is in flight.
+    pub searching: bool,
+    /// Group chat the chosen people are added to, instead of starting a
+    /// new chat.
+    pub add_to: Option<String>,
+}
+
+impl PeoplePicker {
+    /// A picker adding people to an existing group chat.
+    pub fn adding_to(chat_id: String) -> Self {
+        Self {
+            add_to: Some(chat_id),
+            ..Default::default()
+        }
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.query.push(c);
+    }
+
+    /// Delete the last query character, or the last chosen person when the
+    /// query is empty.
+    pub fn backspace(&mut self) {
+        if self.query.pop().is_none() {
+            self.chosen.pop();
+        }
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.selected + 1 < self.results.len().min(MAX_RESULTS) {
+            self.selected += 1;
+        }
+    }
+
+    /// Show search results, unless the query has changed since.
+    pub fn set_results(&mut self, query: &str, results: Vec<api::PersonInfo>) {
+        if query != self.query.trim() {
+            return;
+        }
+        self.results = results;
+        self.selected = 0;
+        self.searching = false;
+    }
+
+    /// Add the highlighted result to the chat and start a new search.
+    /// Returns false if there is nothing to add.
+    pub fn choose_selected(&mut self) -> bool {
+        let Some(person) = self.results.get(self.selected).cloned() else {
+            return false;
+        };
+        if self.chosen.iter().all(|p| p.id != person.id) {
+            self.chosen.push(person);
+        }
+        self.query.clear();
+        self.results.clear();
+        self.selected = 0;
+        true
+    }
+}
+
+/// Render the picker centered over the screen.
+pub fn render_people_picker(frame: &mut Frame, picker: &PeoplePicker) {
+    let area = frame.area();
+    let popup_area = centered_rect(
+        POPUP_WIDTH.min(area.width.saturating_sub(2)),
+        POPUP_HEIGHT.min(area.height.saturating_sub(2)),
+        area,
+    );
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(Span::styled(
+            if picker.add_to.is_some() {
+                " Add people "
+            } else {
+                " New chat "
+            },
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ));
+
+    let mut lines = Vec::new();
+    let chosen = if picker.chosen.is_empty() {
+        Span::styled("(nobody yet)", Style::default().fg(Color::DarkGray))
+    } else {
+        let names: Vec<&str> = picker
+            .chosen
+            .iter()
+            .map(|p| p.display_name.as_str())
+            .collect();
+        Span::styled(
+            names.join(", "),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    };
+    lines.push(Line::from(vec![
+        Span::styled(" With: ", Style::default().fg(Color::Gray)),
+        chosen,
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled(" Find: ", Style::default().fg(Color::Gray)),
+        Span::styled(picker.query.clone(), Style::default().fg(Color::White)),
+        Span::styled("_", Style::default().fg(Color::Cyan)),
+    ]));
+
+    if picker.results.is_empty() {
+        let text = if picker.searching {
+            " Searching..."
+        } else if picker.query.trim().is_empty() {
+            " Type a name or email address"
+        } else {
+            " No matches"
+        };
+        lines.push(Line::from(Span::styled(
+            text,
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for (i, person) in picker.results.iter().take(MAX_RESULTS).enumerate() {
+        let highlight = if i == picker.selected {
+            Style::default().bg(Color::Rgb(55, 55, 70))
+        } else {
+            Style::default()
+        };
+        let mut spans = vec![Span::styled(
+            format!(" {}", person.display_name),
+            highlight.fg(Color::White).add_modifier(Modifier::BOLD),
+        )];
+        if let Some(ref email) = person.email {
+            spans.push(Span::styled(
+                format!("  {}", email),
+                highlight.fg(Color::DarkGray),
+            ));
+        }
+        lines.push(Line::from(spans).style(highlight));
+    }
+
+    let hint_row = popup_area.height.saturating_sub(3) as usize;
+    while lines.len() < hint_row {
+        lines.push(Line::from(""));
+    }
+    let hint = if picker.query.trim().is_empty() && !picker.chosen.is_empty() {
+        if picker.add_to.is_some() {
+            " Enter: add to chat  Backspace: remove  Esc: cancel"
+        } else {
+            " Enter: start chat  Backspace: remove  Esc: cancel"
+        }
+    } else {
+        " Enter: add person  Esc: cancel"
+    };
+    lines.push(Line::from(Span::styled(
+        hint,
+        Style::default().fg(Color::Gray),
+    )));
+    frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person(id: &str) -> api::PersonInfo {
+        api::PersonInfo {
+            id: id.to_string(),
+            display_name: id.to_uppercase(),
+            email: None,
+        }
+    }
+
+    #[test]
+    fn chooses_people_once_and_ignores_stale_results() {
+        let mut picker = PeoplePicker::default();
+        picker.insert_char('a');
+        picker.set_results("b", vec![person("b")]);
+        assert!(picker.results.is_empty());
+
+        picker.set_results("a", vec![person("a"), person("ab")]);
+        picker.move_down();
+        assert!(picker.choose_selected());
+        picker.insert_char('a');
+        picker.set_results("a", vec![person("ab")]);
+        assert!(picker.choose_selected());
+        assert_eq!(picker.chosen.len(), 1);
+        assert!(picker.query.is_empty());
+
+        picker.backspace();
+        assert!(picker.chosen.is_empty());
+        assert!(!picker.choose_selected());
+    }
+}
@@ -0,0 +1,111 @@
+//! File path prompt: a single-line popup for choosing a file to attach.
+
+use std::path::PathBuf;
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use super::help::centered_rect;
+
+/// Popup width in columns (clamped to the terminal).
+const POPUP_WIDTH: u16 = 70;
+
+/// Popup height: border + input + hint + border.
+const POPUP_HEIGHT: u16 = 4;
+
+/// State for the open "attach file" prompt.
+pub struct FilePrompt {
+    /// Conversation the file will be sent to.
+    pub chat_id: String,
+    /// Path typed so far.
+    pub input: String,
+}
+
+impl FilePrompt {
+    pub fn new(chat_id: String) -> Self {
+        Self {
+            chat_id,
+            input: String::new(),
+        }
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.input.push(c);
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+    }
+
+    /// The typed path with a leading `~` expanded, or `None` if empty.
+    pub fn path(&self) -> Option<PathBuf> {
+        expand_path(self.input.trim())
+    }
+}
+
+/// Expand a leading `~` to the home directory.
+fn expand_path(input: &str) -> Option<PathBuf> {
+    if input.is_empty() {
+        return None;
+    }
+    let home = || directories::UserDirs::new().map(|d| d.home_dir().to_path_buf());
+    match input.strip_prefix('~') {
+        Some("") => home(),
+        Some(rest) if rest.starts_with('/') => home().map(|h| h.join(&rest[1..])),
+        _ => Some(PathBuf::from(input)),
+    }
+}
+
+/// Render the prompt centered over the screen.
+pub fn render_file_prompt(frame: &mut Frame, prompt: &FilePrompt) {
+    let area = frame.area();
+    let popup_area = centered_rect(
+        POPUP_WIDTH.min(area.width.saturating_sub(2)),
+        POPUP_HEIGHT.min(area.height.saturating_sub(2)),
+        area,
+    );
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(Span::styled(
+            " Attach file ",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ));
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(" Path: ", Style::default().fg(Color::Gray)),
+            Span::styled(prompt.input.clone(), Style::default().fg(Color::White)),
+            Span::styled("_", Style::default().fg(Color::Cyan)),
+        ]),
+        Line::from(Span::styled(
+            " Enter: send (with compose text)  Esc: cancel",
+            Style::default().fg(Color::Gray),
+        )),
+    ];
+    frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_home_prefix_only() {
+        assert_eq!(expand_path(""), None);
+        assert_eq!(expand_path("/tmp/a.txt"), Some(PathBuf::from("/tmp/a.txt")));
+        assert_eq!(expand_path("~user/a"), Some(PathBuf::from("~user/a")));
+        if let Some(home) = directories::UserDirs::new().map(|d| d.home_dir().to_path_buf()) {
+            assert_eq!(expand_path("~/a.txt"), Some(home.join("a.txt")));
+        }
+    }
+}
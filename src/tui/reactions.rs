@@ -0,0 +1,116 @@
+//! Reaction picker popup: choose one of the standard Teams reactions.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use super::help::centered_rect;
+use crate::api;
+
+/// Popup height: border + reactions + hint + border.
+const POPUP_HEIGHT: u16 = 4;
+
+/// State for the open reaction picker.
+pub struct ReactionPicker {
+    /// Conversation of the message being reacted to.
+    pub chat_id: String,
+    /// Message being reacted to.
+    pub message_id: String,
+    /// Index into [`api::REACTIONS`].
+    pub selected: usize,
+    /// Reaction keys we already hold on this message (picking one removes it).
+    pub mine: Vec<String>,
+}
+
+impl ReactionPicker {
+    pub fn new(chat_id: String, message_id: String, mine: Vec<String>) -> Self {
+        Self {
+            chat_id,
+            message_id,
+            selected: 0,
+            mine,
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        if self.selected + 1 < api::REACTIONS.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Select by 1-based number key. Returns false if out of range.
+    pub fn select_number(&mut self, n: usize) -> bool {
+        if (1..=api::REACTIONS.len()).contains(&n) {
+            self.selected = n - 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The selected reaction key and whether choosing it adds (true) or
+    /// removes (false) our reaction.
+    pub fn choice(&self) -> (&'static str, bool) {
+        let (key, _, _) = api::REACTIONS[self.selected];
+        (key, !self.mine.iter().any(|k| k == key))
+    }
+}
+
+/// Render the picker centered over the screen.
+pub fn render_reaction_picker(frame: &mut Frame, picker: &ReactionPicker) {
+    let mut spans: Vec<Span<'static>> = vec![Span::raw(" ")];
+    for (i, (key, emoji, _)) in api::REACTIONS.iter().enumerate() {
+        let mut style = Style::default().fg(Color::White);
+        if picker.mine.iter().any(|k| k == key) {
+            style = style.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+        }
+        if i == picker.selected {
+            style = style.bg(Color::DarkGray).add_modifier(Modifier::REVERSED);
+        }
+        spans.push(Span::styled(format!(" {} {} ", i + 1, emoji), style));
+        spans.push(Span::raw(" "));
+    }
+
+    let width: u16 = spans
+        .iter()
+        .map(|s| unicode_width::UnicodeWidthStr::width(s.content.as_ref()) as u16)
+        .sum::<u16>()
+        + 2;
+    let area = frame.area();
+    let popup_area = centered_rect(
+        width.min(area.width.saturating_sub(2)),
+        POPUP_HEIGHT.min(area.height.saturating_sub(2)),
+        area,
+    );
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(Span::styled(
+            " React ",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ));
+
+    let (key, add) = picker.choice();
+    let hint = format!(
+        " Enter: {} {}  Esc: close",
+        if add { "add" } else { "remove" },
+        key
+    );
+    let lines = vec![
+        Line::from(spans),
+        Line::from(Span::styled(hint, Style::default().fg(Color::Gray))),
+    ];
+    frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
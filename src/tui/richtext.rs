@@ -0,0 +1,310 @@
+//! Lay out rich message content as wrapped, styled ratatui lines.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::api::{Block, RichSpan, RichText, SpanStyle};
+
+/// Width of a horizontal rule, in columns.
+const RULE_WIDTH: usize = 20;
+
+/// One paragraph-like unit before wrapping.
+struct Para {
+    /// Decoration before the first line (quote bars, list marker).
+    first_prefix: Vec<Span<'static>>,
+    /// Decoration before continuation lines.
+    rest_prefix: Vec<Span<'static>>,
+    segments: Vec<(String, Style)>,
+}
+
+/// Wrap rich text to `width` columns. `base` is the card's text style (its
+/// background is kept on every span). Returns the spans of each line.
+pub fn wrap_rich_text(rich: &RichText, width: usize, base: Style) -> Vec<Vec<Span<'static>>> {
+    let mut paras = Vec::new();
+    layout_blocks(&rich.blocks, base, &[], &[], &mut paras);
+    paras
+        .into_iter()
+        .flat_map(|p| wrap_para(p, width.max(1)))
+        .collect()
+}
+
+/// ratatui style for an inline span.
+fn span_style(style: &SpanStyle, base: Style) -> Style {
+    let mut s = base;
+    if style.bold {
+        s = s.add_modifier(Modifier::BOLD);
+    }
+    if style.italic {
+        s = s.add_modifier(Modifier::ITALIC);
+    }
+    if style.underline {
+        s = s.add_modifier(Modifier::UNDERLINED);
+    }
+    if style.strike {
+        s = s.add_modifier(Modifier::CROSSED_OUT);
+    }
+    if style.code {
+        s = s.fg(Color::Cyan);
+    } else if style.mention {
+        s = s.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    } else if style.link.is_some() {
+        s = s.fg(Color::LightBlue).add_modifier(Modifier::UNDERLINED);
+    }
+    s
+}
+
+/// Styled segments for inline spans; link targets that differ from the
+/// link text are shown after it.
+fn segments(spans: &[RichSpan], base: Style) -> Vec<(String, Style)> {
+    let mut out = Vec::new();
+    for span in spans {
+        out.push((span.text.clone(), span_style(&span.style, base)));
+        if let Some(url) = span.style.link.as_deref() {
+            if url != span.text.trim() {
+                out.push((format!(" <{}>", url), base.fg(Color::DarkGray)));
+            }
+        }
+    }
+    out
+}
+
+fn layout_blocks(
+    blocks: &[Block],
+    base: Style,
+    first: &[Span<'static>],
+    rest: &[Span<'static>],
+    out: &mut Vec<Para>,
+) {
+    for (i, block) in blocks.iter().enumerate() {
+        let prefix = if i == 0 { first } else { rest };
+        match block {
+            Block::Paragraph(spans) => out.push(Para {
+                first_prefix: prefix.to_vec(),
+                rest_prefix: rest.to_vec(),
+                segments: segments(spans, base),
+            }),
+            Block::Quote(inner) => {
+                let bar = Span::styled("\u{2502} ", base.fg(Color::DarkGray));
+                let f = [prefix, std::slice::from_ref(&bar)].concat();
+                let r = [rest, &[bar]].concat();
+                layout_blocks(inner, base.fg(Color::Gray), &f, &r, out);
+            }
+            Block::List { ordered, items } => {
+                for (n, item) in items.iter().enumerate() {
+                    let marker = if *ordered {
+                        format!("{}. ", n + 1)
+                    } else {
+                        "\u{2022} ".to_string()
+                    };
+                    let indent = " ".repeat(marker.width());
+                    let item_prefix = if i == 0 && n == 0 { first } else { rest };
+                    let f = [item_prefix, &[Span::styled(marker, base)]].concat();
+                    let r = [rest, &[Span::styled(indent, base)]].concat();
+                    if item.is_empty() {
+                        out.push(Para {
+                            first_prefix: f,
+                            rest_prefix: r,
+                            segments: Vec::new(),
+                        });
+                    } else {
+                        layout_blocks(item, base, &f, &r, out);
+                    }
+                }
+            }
+            Block::Code(text) => {
+                let style = base.fg(Color::Cyan);
+                for (n, line) in text.lines().enumerate() {
+                    out.push(Para {
+                        first_prefix: if n == 0 { prefix } else { rest }.to_vec(),
+                        rest_prefix: rest.to_vec(),
+                        segments: vec![(line.to_string(), style)],
+                    });
+                }
+            }
+            Block::Table(rows) => {
+                let sep = (" \u{2502} ".to_string(), base.fg(Color::DarkGray));
+                for (r, row) in rows.iter().enumerate() {
+                    let cell_base = if r == 0 {
+                        base.add_modifier(Modifier::BOLD)
+                    } else {
+                        base
+                    };
+                    let mut segs = Vec::new();
+                    for (c, cell) in row.iter().enumerate() {
+                        if c > 0 {
+                            segs.push(sep.clone());
+                        }
+                        segs.extend(segments(cell, cell_base));
+                    }
+                    out.push(Para {
+                        first_prefix: if r == 0 { prefix } else { rest }.to_vec(),
+                        rest_prefix: rest.to_vec(),
+                        segments: segs,
+                    });
+                }
+            }
+            Block::Reply(quote) => {
+                let bar = Span::styled("\u{2503} ", base.fg(Color::Cyan));
+                let r = [rest, std::slice::from_ref(&bar)].concat();
+                out.push(Para {
+                    first_prefix: [prefix, &[bar]].concat(),
+                    rest_prefix: r.clone(),
+                    segments: vec![(
+                        format!("\u{21a9} {}", quote.sender),
+                        base.fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                    )],
+                });
+                let quoted = base.fg(Color::Gray).add_modifier(Modifier::ITALIC);
+                layout_blocks(&quote.blocks, quoted, &r, &r, out);
+            }
+            Block::Rule => out.push(Para {
+                first_prefix: prefix.to_vec(),
+                rest_prefix: rest.to_vec(),
+                segments: vec![("\u{2500}".repeat(RULE_WIDTH), base.fg(Color::DarkGray))],
+            }),
+        }
+    }
+}
+
+fn prefix_width(prefix: &[Span<'static>]) -> usize {
+    prefix.iter().map(|s| s.content.width()).sum()
+}
+
+/// Greedy word wrap. Spaces at a line break and at the end are dropped;
+/// words wider than the line are split.
+fn wrap_para(para: Para, width: usize) -> Vec<Vec<Span<'static>>> {
+    let rest_width = prefix_width(&para.rest_prefix);
+    let mut lines = Vec::new();
+    let mut line = para.first_prefix.clone();
+    let mut line_start = prefix_width(&para.first_prefix);
+    let mut col = line_start;
+    // Spaces are held back until the next word shows they are not at a break.
+    let mut pending: Option<(&str, Style)> = None;
+
+    for (text, style) in &para.segments {
+        for piece in split_pieces(text) {
+            if piece == "\n" {
+                pending = None;
+                lines.push(std::mem::replace(&mut line, para.rest_prefix.clone()));
+                line_start = rest_width;
+                col = rest_width;
+                continue;
+            }
+            if piece.starts_with(' ') {
+                pending = Some((piece, *style));
+                continue;
+            }
+            let space = pending.take();
+            let space_w = space.map_or(0, |(s, _)| s.width());
+            if col + space_w + piece.width() > width && col > line_start {
+                lines.push(std::mem::replace(&mut line, para.rest_prefix.clone()));
+                line_start = rest_width;
+                col = rest_width;
+            } else if let Some((s, s_style)) = space {
+                line.push(Span::styled(s.to_string(), s_style));
+                col += space_w;
+            }
+            // Split words that do not fit on a line of their own.
+            let mut word = String::new();
+            for c in piece.chars() {
+                let cw = c.to_string().width();
+                if col + cw > width && col > line_start {
+                    line.push(Span::styled(std::mem::take(&mut word), *style));
+                    lines.push(std::mem::replace(&mut line, para.rest_prefix.clone()));
+                    line_start = rest_width;
+                    col = rest_width;
+                }
+                word.push(c);
+                col += cw;
+            }
+            if !word.is_empty() {
+                line.push(Span::styled(word, *style));
+            }
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+/// Split text into words, runs of spaces and single newlines.
+fn split_pieces(text: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut kind = None;
+    for (i, c) in text.char_indices() {
+        let k = match c {
+            '\n' => 0,
+            ' ' => 1,
+            _ => 2,
+        };
+        if kind != Some(k) || k == 0 {
+            if i > start {
+                pieces.push(&text[start..i]);
+            }
+            start = i;
+            kind = Some(k);
+        }
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: Vec<Vec<Span<'static>>>) -> Vec<String> {
+        lines
+            .into_iter()
+            .map(|l| l.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn wraps_words_and_keeps_prefixes() {
+        let rich = RichText::from_html(
+            "<p>one two three four</p><ul><li>alpha beta gamma</li></ul><blockquote>quoted text here</blockquote>",
+        );
+        assert_eq!(
+            texts(wrap_rich_text(&rich, 10, Style::default())),
+            [
+                "one two",
+                "three four",
+                "\u{2022} alpha",
+                "  beta",
+                "  gamma",
+                "\u{2502} quoted",
+                "\u{2502} text",
+                "\u{2502} here",
+            ]
+        );
+    }
+
+    #[test]
+    fn reply_quote_has_header_and_bar() {
+        let rich = RichText::from_html(
+            "<blockquote itemscope itemtype=\"http://schema.skype.com/Reply\" itemid=\"1\">\
+             <strong itemprop=\"mri\" itemid=\"8:orgid:a\">Alice</strong>\
+             <span itemprop=\"time\" itemid=\"1\"></span><p itemprop=\"preview\">Ship it?</p>\
+             </blockquote><p>Yes</p>",
+        );
+        assert_eq!(
+            texts(wrap_rich_text(&rich, 20, Style::default())),
+            ["\u{2503} \u{21a9} Alice", "\u{2503} Ship it?", "Yes"]
+        );
+    }
+
+    #[test]
+    fn code_keeps_indentation_and_splits_long_words() {
+        let rich = RichText::from_html("<pre>  x = 1\nabcdefghijkl</pre>");
+        assert_eq!(
+            texts(wrap_rich_text(&rich, 8, Style::default())),
+            ["  x = 1", "abcdefgh", "ijkl"]
+        );
+    }
+}
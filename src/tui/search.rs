@@ -11,6 +11,11 @@ use ratatui::{
 
 use super::messages::MessagesState;
 use super::sidebar::SidebarState;
+use crate::api::{self, SearchHit};
+use crate::cache::search::{SearchIndex, SearchQuery};
+
+/// Maximum number of cached-history matches added to the results.
+const MAX_CACHED_RESULTS: usize = 50;
 
 // ---------------------------------------------------------------------------
 // Search result types
@@ -34,8 +39,8 @@ pub enum SearchResultKind {
     Channel(usize, usize),
     /// A direct chat: (chat index).
     Chat(usize),
-    /// A message: (message index).
-    Message(usize),
+    /// A message in any conversation (the open one or the local cache).
+    Message { chat_id: String, message_id: String },
 }
 
 // ---------------------------------------------------------------------------
@@ -55,6 +60,12 @@ pub struct SearchState {
     pub results: Vec<SearchResult>,
     /// Index of the currently selected result (for navigation).
     pub selected: usize,
+    /// Index over cached history, loaded in the background on activation.
+    pub index: Option<SearchIndex>,
+    /// Server-side hits for `remote_query`.
+    pub remote_hits: Vec<SearchHit>,
+    /// Query the server-side hits belong to.
+    pub remote_query: String,
 }
 
 impl SearchState {
@@ -74,6 +85,9 @@ impl SearchState {
         self.cursor_pos = 0;
         self.results.clear();
         self.selected = 0;
+        self.index = None;
+        self.remote_hits.clear();
+        self.remote_query.clear();
     }
 
     /// Insert a character at the cursor position.
@@ -147,10 +161,16 @@ impl SearchState {
         self.results.get(self.selected)
     }
 
-    /// Update search results by filtering against sidebar and messages data.
+    /// Update search results by filtering against sidebar data, the open
+    /// conversation and the cached-history index.
     ///
-    /// Called whenever the query changes.
-    pub fn update_results(&mut self, sidebar: &SidebarState, messages: &MessagesState) {
+    /// Called whenever the query changes (or the index finishes loading).
+    pub fn update_results(
+        &mut self,
+        sidebar: &SidebarState,
+        messages: &MessagesState,
+        current_chat_id: Option<&str>,
+    ) {
         self.results.clear();
         self.selected = 0;
 
@@ -187,38 +207,63 @@ impl SearchState {
             }
         }
 
-        // Search messages (sender and content).
-        for (mi, msg) in messages.messages.iter().enumerate() {
-            let sender_match = msg.sender.to_lowercase().contains(&query);
-            let content_match = msg.content.to_lowercase().contains(&query);
-
-            if sender_match || content_match {
-                // Build a short snippet of the matching content.
-                let snippet = if content_match {
-                    // Take the first line of content, truncated.
-                    let first_line = msg.content.lines().next().unwrap_or("");
-                    let truncated: String = first_line.chars().take(50).collect();
-                    if truncated.len() < first_line.len() {
-                        format!("{}...", truncated)
-                    } else {
-                        truncated
-                    }
-                } else {
-                    msg.content
-                        .lines()
-                        .next()
-                        .unwrap_or("")
-                        .chars()
-                        .take(50)
-                        .collect()
+        // Search the open conversation (includes live messages not yet cached).
+        if let Some(chat_id) = current_chat_id {
+            let all = messages
+                .messages
+                .iter()
+                .flat_map(|m| std::iter::once(m).chain(&m.replies));
+            for msg in all {
+                let sender_match = msg.sender.to_lowercase().contains(&query);
+                let content_match = msg.content.to_lowercase().contains(&query);
+
+                if sender_match || content_match {
+                    self.results.push(SearchResult {
+                        kind: SearchResultKind::Message {
+                            chat_id: chat_id.to_string(),
+                            message_id: msg.id.clone(),
+                        },
+                        label: format!("{} ({})", msg.sender, msg.timestamp),
+                        context: snippet(&msg.content),
+                    });
+                }
+            }
+        }
+
+        // Search cached history of every other conversation, then add
+        // server-side hits for the same query that are not cached.
+        let mut hits = match self.index {
+            Some(ref index) => {
+                let cached = SearchQuery {
+                    text: query.clone(),
+                    ..Default::default()
                 };
+                index.search(&cached, MAX_CACHED_RESULTS)
+            }
+            None => Vec::new(),
+        };
+        if self.remote_query.to_lowercase() == query {
+            api::merge_hits(&mut hits, self.remote_hits.iter().cloned());
+        }
 
-                self.results.push(SearchResult {
-                    kind: SearchResultKind::Message(mi),
-                    label: format!("{} ({})", msg.sender, msg.timestamp),
-                    context: snippet,
-                });
+        for hit in hits {
+            if Some(hit.conversation_id.as_str()) == current_chat_id {
+                continue;
             }
+            let chat_name = sidebar
+                .item_name_by_id(&hit.conversation_id)
+                .unwrap_or_else(|| hit.conversation_id.clone());
+            self.results.push(SearchResult {
+                kind: SearchResultKind::Message {
+                    chat_id: hit.conversation_id,
+                    message_id: hit.message.id,
+                },
+                label: format!(
+                    "{} in {} ({})",
+                    hit.message.sender, chat_name, hit.message.timestamp
+                ),
+                context: snippet(&hit.message.content),
+            });
         }
     }
 
@@ -232,6 +277,17 @@ impl SearchState {
     }
 }
 
+/// First line of a message, truncated to 50 characters.
+fn snippet(content: &str) -> String {
+    let first_line = content.lines().next().unwrap_or("");
+    let truncated: String = first_line.chars().take(50).collect();
+    if truncated.len() < first_line.len() {
+        format!("{}...", truncated)
+    } else {
+        truncated
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Rendering
 // ---------------------------------------------------------------------------
@@ -443,7 +499,7 @@ fn render_result_row(area: Rect, buf: &mut Buffer, result: &SearchResult, select
     let icon = match &result.kind {
         SearchResultKind::Channel(_, _) => "#",
         SearchResultKind::Chat(_) => "\u{1F464}",
-        SearchResultKind::Message(_) => "\u{1F4AC}",
+        SearchResultKind::Message { .. } => "\u{1F4AC}",
     };
 
     let icon_style = Style::default()
@@ -45,8 +45,43 @@ pub struct Chat {
     pub is_group: bool,
     /// Number of unread messages (0 = no badge, use dot for "some")
     pub unread: u32,
-    /// Whether this contact is online (show presence dot)
-    pub online: bool,
+    /// MRI of the other person in a 1:1 chat.
+    pub partner: Option<String>,
+    /// The partner's presence, once known (shown as a coloured dot).
+    pub presence: Option<Presence>,
+}
+
+/// Availability of a chat partner, as shown in the sidebar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+    Available,
+    Busy,
+    Away,
+    Offline,
+}
+
+impl Presence {
+    /// Map a Teams availability ("Available", "BusyIdle", "BeRightBack",
+    /// ...); unknown values give `None`.
+    pub fn from_availability(availability: &str) -> Option<Self> {
+        let presence = match availability {
+            a if a.starts_with("Available") => Self::Available,
+            a if a.starts_with("Busy") || a.starts_with("DoNotDisturb") => Self::Busy,
+            a if a.starts_with("Away") || a.starts_with("BeRightBack") => Self::Away,
+            a if a.starts_with("Offline") => Self::Offline,
+            _ => return None,
+        };
+        Some(presence)
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Self::Available => Color::Green,
+            Self::Busy => Color::Red,
+            Self::Away => Color::Yellow,
+            Self::Offline => Color::DarkGray,
+        }
+    }
 }
 
 /// Sidebar state: owns the data and tracks navigation.
@@ -72,7 +107,17 @@ impl Default for SidebarState {
 
 impl SidebarState {
     /// Update teams data from API response.
+    ///
+    /// Unread counts accumulated from live events are carried over by ID.
     pub fn update_teams(&mut self, teams: Vec<api::TeamInfo>) {
+        let previous = std::mem::take(&mut self.teams);
+        let unread_of = |id: &str| {
+            previous
+                .iter()
+                .flat_map(|t| t.channels.iter())
+                .find(|c| c.id == id)
+                .map_or(0, |c| c.unread)
+        };
         self.teams = teams
             .into_iter()
             .map(|t| Team {
@@ -83,9 +128,9 @@ impl SidebarState {
                     .channels
                     .into_iter()
                     .map(|c| Channel {
+                        unread: unread_of(&c.id),
                         name: c.name,
                         id: c.id,
-                        unread: 0,
                     })
                     .collect(),
             })
@@ -94,20 +139,151 @@ impl SidebarState {
     }
 
     /// Update chats data from API response.
+    ///
+    /// Unread counts accumulated from live events and known presence are
+    /// carried over by ID.
     pub fn update_chats(&mut self, chats: Vec<api::ChatInfo>) {
+        let previous = std::mem::take(&mut self.chats);
         self.chats = chats
             .into_iter()
-            .map(|c| Chat {
-                name: c.name,
-                id: c.id,
-                is_group: c.is_group,
-                unread: 0,
-                online: false,
+            .map(|c| {
+                let old = previous.iter().find(|p| p.id == c.id);
+                Chat {
+                    unread: old.map_or(0, |p| p.unread),
+                    presence: old.and_then(|p| p.presence),
+                    name: c.name,
+                    id: c.id,
+                    is_group: c.is_group,
+                    partner: c.partner_mri,
+                }
             })
             .collect();
         self.clamp_selection();
     }
 
+    /// MRIs of everyone we have a 1:1 chat with.
+    pub fn partners(&self) -> Vec<String> {
+        let mut partners: Vec<String> = self
+            .chats
+            .iter()
+            .filter_map(|c| c.partner.clone())
+            .collect();
+        partners.sort();
+        partners.dedup();
+        partners
+    }
+
+    /// Show `availability` for the 1:1 chats with the user `mri`.
+    pub fn set_presence(&mut self, mri: &str, availability: &str) {
+        let presence = Presence::from_availability(availability);
+        for chat in self
+            .chats
+            .iter_mut()
+            .filter(|c| c.partner.as_deref() == Some(mri))
+        {
+            chat.presence = presence;
+        }
+    }
+
+    /// Bump the unread badge of the chat or channel with the given ID.
+    ///
+    /// Returns false if no sidebar item has that ID (e.g. a brand new chat).
+    pub fn mark_unread(&mut self, id: &str) -> bool {
+        if let Some(chat) = self.chats.iter_mut().find(|c| c.id == id) {
+            chat.unread += 1;
+            return true;
+        }
+        if let Some(channel) = self
+            .teams
+            .iter_mut()
+            .flat_map(|t| t.channels.iter_mut())
+            .find(|c| c.id == id)
+        {
+            channel.unread += 1;
+            return true;
+        }
+        false
+    }
+
+    /// Set the unread badge of the chat or channel with the given ID.
+    ///
+    /// Returns false if no sidebar item has that ID (yet).
+    pub fn set_unread(&mut self, id: &str, count: u32) -> bool {
+        if let Some(chat) = self.chats.iter_mut().find(|c| c.id == id) {
+            chat.unread = count;
+            return true;
+        }
+        if let Some(channel) = self
+            .teams
+            .iter_mut()
+            .flat_map(|t| t.channels.iter_mut())
+            .find(|c| c.id == id)
+        {
+            channel.unread = count;
+            return true;
+        }
+        false
+    }
+
+    /// Clear the unread badge of the chat or channel with the given ID.
+    pub fn clear_unread(&mut self, id: &str) {
+        for chat in self.chats.iter_mut().filter(|c| c.id == id) {
+            chat.unread = 0;
+        }
+        for channel in self
+            .teams
+            .iter_mut()
+            .flat_map(|t| t.channels.iter_mut())
+            .filter(|c| c.id == id)
+        {
+            channel.unread = 0;
+        }
+    }
+
+    /// Whether the given ID belongs to a team channel (rather than a chat).
+    pub fn is_channel(&self, id: &str) -> bool {
+        self.teams
+            .iter()
+            .flat_map(|t| t.channels.iter())
+            .any(|c| c.id == id)
+    }
+
+    /// Display name of the chat or channel with the given ID.
+    pub fn item_name_by_id(&self, id: &str) -> Option<String> {
+        if let Some(chat) = self.chats.iter().find(|c| c.id == id) {
+            return Some(chat.name.clone());
+        }
+        self.teams.iter().find_map(|team| {
+            team.channels
+                .iter()
+                .find(|c| c.id == id)
+                .map(|c| format!("{} > #{}", team.name, c.name))
+        })
+    }
+
+    /// Select the chat or channel with the given ID, expanding its team.
+    ///
+    /// Returns false if no sidebar item has that ID.
+    pub fn select_by_id(&mut self, id: &str) -> bool {
+        for team in &mut self.teams {
+            if team.channels.iter().any(|c| c.id == id) {
+                team.expanded = true;
+            }
+        }
+        let found = self.flat_items().iter().position(|item| match *item {
+            SidebarItem::Channel(ti, ci) => self.teams[ti].channels[ci].id == id,
+            SidebarItem::Chat(ci) => self.chats[ci].id == id,
+            _ => false,
+        });
+        match found {
+            Some(idx) => {
+                self.selected = idx;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get the chat/channel ID of the currently selected item.
     pub fn selected_item_id(&self) -> Option<String> {
         let items = self.flat_items();
@@ -452,19 +628,17 @@ fn render_item(buf: &mut Buffer, ctx: &RowCtx, item: &SidebarItem, state: &Sideb
             let label = format!("{}{} {}", cursor, icon, chat.name);
             let badge = if chat.unread > 0 {
                 format!("{}", chat.unread)
-            } else if chat.online {
-                "*".to_string()
+            } else if chat.presence.is_some() {
+                "\u{25CF}".to_string()
             } else {
                 String::new()
             };
 
             let style = item_style(ctx.selected, chat.unread > 0);
-            let bstyle = if chat.unread > 0 {
-                badge_style(ctx.selected)
-            } else if chat.online {
-                Style::default().fg(Color::Green)
-            } else {
-                style
+            let bstyle = match chat.presence {
+                _ if chat.unread > 0 => badge_style(ctx.selected),
+                Some(presence) => style.fg(presence.color()),
+                None => style,
             };
 
             render_row(buf, ctx.area, &label, &badge, style, bstyle);
@@ -523,3 +697,65 @@ fn render_row(
     let row_area = Rect::new(area.x, area.y, area.width, 1);
     Paragraph::new(line).render(row_area, buf);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chat(id: &str, partner: Option<&str>) -> api::ChatInfo {
+        api::ChatInfo {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            is_group: partner.is_none(),
+            partner_mri: partner.map(String::from),
+            last_message_time: None,
+            last_message_sender: None,
+            last_message_preview: None,
+        }
+    }
+
+    #[test]
+    fn live_messages_mark_chats_and_channels_unread() {
+        let mut state = SidebarState::default();
+        state.update_teams(vec![api::TeamInfo {
+            id: "team".to_string(),
+            name: "Team".to_string(),
+            channels: vec![api::ChannelInfo {
+                id: "19:general".to_string(),
+                name: "General".to_string(),
+            }],
+        }]);
+        state.update_chats(vec![chat("19:a", None)]);
+
+        assert!(state.mark_unread("19:a"));
+        assert!(state.mark_unread("19:a"));
+        assert!(state.mark_unread("19:general"));
+        assert!(!state.mark_unread("19:new"));
+        assert_eq!(state.chats[0].unread, 2);
+        assert_eq!(state.teams[0].channels[0].unread, 1);
+
+        // Counts survive a refresh of the chat list.
+        state.update_chats(vec![chat("19:a", None)]);
+        assert_eq!(state.chats[0].unread, 2);
+
+        state.clear_unread("19:a");
+        assert_eq!(state.chats[0].unread, 0);
+    }
+
+    #[test]
+    fn presence_follows_partners_across_refreshes() {
+        let mut state = SidebarState::default();
+        state.update_chats(vec![chat("19:a", Some("8:orgid:bob")), chat("19:b", None)]);
+        assert_eq!(state.partners(), ["8:orgid:bob"]);
+
+        state.set_presence("8:orgid:bob", "BusyIdle");
+        assert_eq!(state.chats[0].presence, Some(Presence::Busy));
+        assert_eq!(state.chats[1].presence, None);
+
+        state.update_chats(vec![chat("19:a", Some("8:orgid:bob"))]);
+        assert_eq!(state.chats[0].presence, Some(Presence::Busy));
+
+        state.set_presence("8:orgid:bob", "PresenceUnknown");
+        assert_eq!(state.chats[0].presence, None);
+    }
+}
@@ -11,11 +11,17 @@ use ratatui::{
 
 use super::app::{App, Pane};
 use super::compose;
+use super::confirm;
 use super::debug_log;
 use super::help;
+use super::members;
 use super::messages;
+use super::people;
+use super::prompt;
+use super::reactions;
 use super::search;
 use super::sidebar;
+use crate::trouter::ConnectionState;
 
 /// Percentage of main area height allocated to content when debug log is visible.
 /// The remainder goes to the debug log pane.
@@ -30,8 +36,23 @@ fn status_indicator(is_online: bool) -> (&'static str, Color) {
     }
 }
 
+/// Status bar span describing the Trouter push connection.
+fn push_status(state: &ConnectionState) -> Span<'static> {
+    match state {
+        ConnectionState::Connecting => {
+            Span::styled("live: connecting", Style::default().fg(Color::Yellow))
+        }
+        ConnectionState::Connected => Span::styled("live", Style::default().fg(Color::Green)),
+        ConnectionState::Reconnecting { attempt, backoff } => Span::styled(
+            format!("live: retry in {}s (#{})", backoff.as_secs(), attempt),
+            Style::default().fg(Color::Yellow),
+        ),
+        ConnectionState::Stopped => Span::styled("live: off", Style::default().fg(Color::Red)),
+    }
+}
+
 /// Main render function
-pub fn render(frame: &mut Frame, app: &App) {
+pub fn render(frame: &mut Frame, app: &mut App) {
     let area = frame.area();
 
     // Layout: header (1 line) + main content + status bar (1 line)
@@ -76,15 +97,38 @@ pub fn render(frame: &mut Frame, app: &App) {
     ])
     .areas(content_area);
 
+    // The member panel takes the right edge of the messages pane
+    let (messages_area, member_area) = if app.member_panel.is_some() {
+        let [messages, panel] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Length(members::PANEL_WIDTH),
+        ])
+        .areas(messages_area);
+        (messages, Some(panel))
+    } else {
+        (messages_area, None)
+    };
+
     // Render messages pane
     messages::render(
         messages_area,
         frame.buffer_mut(),
         &app.messages,
+        &mut app.previews,
         app.active_pane == Pane::Messages,
         &app.user_name,
     );
 
+    if let (Some(panel), Some(area)) = (app.member_panel.as_ref(), member_area) {
+        members::render(
+            area,
+            frame.buffer_mut(),
+            panel,
+            &app.members,
+            app.messages.user_mri.as_deref(),
+        );
+    }
+
     // Render compose box
     compose::render(
         compose_area,
@@ -94,6 +138,15 @@ pub fn render(frame: &mut Frame, app: &App) {
         app.active_pane == Pane::Compose,
     );
 
+    // Render the mention popup above the compose box
+    if app.active_pane == Pane::Compose {
+        let candidates = app.mention_candidates();
+        if let Some(mention) = app.compose.mention.filter(|_| !candidates.is_empty()) {
+            let selected = mention.selected.min(candidates.len() - 1);
+            compose::render_mention_popup(frame, compose_area, &candidates, selected);
+        }
+    }
+
     // Render debug log pane if visible
     if let Some(debug_area) = debug_log_area {
         debug_log::render(debug_area, frame.buffer_mut(), &app.debug_log);
@@ -107,6 +160,26 @@ pub fn render(frame: &mut Frame, app: &App) {
         search::render_search_overlay(frame, &app.search);
     }
 
+    // Render reaction picker
+    if let Some(ref picker) = app.reaction_picker {
+        reactions::render_reaction_picker(frame, picker);
+    }
+
+    // Render the new-chat people picker
+    if let Some(ref picker) = app.people_picker {
+        people::render_people_picker(frame, picker);
+    }
+
+    // Render the attach-file prompt
+    if let Some(ref file_prompt) = app.file_prompt {
+        prompt::render_file_prompt(frame, file_prompt);
+    }
+
+    // Render delete confirmation (above content and search)
+    if let Some(ref pending) = app.pending_delete {
+        confirm::render_confirm_popup(frame, "Delete message?", &pending.preview);
+    }
+
     // Render help popup overlay (on top of everything else)
     if app.show_help {
         help::render_help_popup(frame);
@@ -188,12 +261,22 @@ fn render_status(area: Rect, buf: &mut Buffer, app: &App) {
 
     let sep_style = Style::default().fg(Color::DarkGray);
 
+    let push = push_status(&app.push_state);
+
     let channel_display = if app.channel_name.is_empty() {
         "(none)".to_string()
     } else {
         app.channel_name.clone()
     };
     let channel = Span::styled(channel_display, Style::default().fg(Color::Yellow));
+    let member_count = match app.members.len() {
+        0 => Span::raw(""),
+        1 => Span::styled(" (1 member)", Style::default().fg(Color::Gray)),
+        n => Span::styled(
+            format!(" ({} members)", n),
+            Style::default().fg(Color::Gray),
+        ),
+    };
 
     let pane = Span::styled(
         format!("Tab: {} ", app.active_pane.as_str()),
@@ -207,7 +290,10 @@ fn render_status(area: Rect, buf: &mut Buffer, app: &App) {
     let status_line = Line::from(vec![
         connection,
         Span::styled(" | ", sep_style),
+        push,
+        Span::styled(" | ", sep_style),
         channel,
+        member_count,
         Span::styled(" | ", sep_style),
         pane,
         Span::styled(" | ", sep_style),